@@ -0,0 +1,89 @@
+//! Trigger matching with and without the hook dispatcher
+//!
+//! Run with `cargo bench --bench dispatch`. Both versions check 500 hooks
+//! spread over 100 channels against the same messages; `linear` is how
+//! `listen` matched before the dispatcher.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use discli::hooks::{
+    should_trigger, CompiledHookConfig, HookDispatcher, HooksConfig, IncomingEvent, TestMessage,
+};
+
+const HOOKS: usize = 500;
+const CHANNELS: usize = 100;
+
+/// A hooks file with a mix of prefix, contains and regex triggers
+fn hooks() -> Vec<CompiledHookConfig> {
+    let mut yaml = String::from("version: \"1.0\"\nhooks:\n");
+    for i in 0..HOOKS {
+        let channel = 1000 + i % CHANNELS;
+        let trigger = match i % 3 {
+            0 => format!("{{ type: prefix, prefix: \"!cmd{}\" }}", i),
+            1 => format!("{{ type: contains, substring: \"keyword{}\" }}", i),
+            _ => format!("{{ type: regex, pattern: \"(?i)^report (bug|issue) #{}\\\\b\" }}", i),
+        };
+        yaml.push_str(&format!(
+            "  - {{ id: h{}, channels: [\"{}\"], trigger: {}, prompt_file: p.txt, action: {{ type: reply }} }}\n",
+            i, channel, trigger
+        ));
+    }
+    let config: HooksConfig = serde_yaml::from_str(&yaml).unwrap();
+    config.compile_enabled().unwrap()
+}
+
+/// Messages across the channels, most of which trigger nothing
+fn messages() -> Vec<IncomingEvent> {
+    (0..CHANNELS)
+        .map(|i| {
+            let content = match i % 4 {
+                0 => format!("!cmd{} with some arguments", i),
+                1 => format!("report bug #{} in the latest build", i + 2),
+                _ => "just chatting about nothing in particular, no commands here".to_string(),
+            };
+            TestMessage {
+                channel: (1000 + i).to_string(),
+                guild: Some("1".to_string()),
+                author: "2".to_string(),
+                author_name: "someone".to_string(),
+                content,
+                ..Default::default()
+            }
+            .to_event()
+            .unwrap()
+        })
+        .collect()
+}
+
+fn trigger_matching(c: &mut Criterion) {
+    let events = messages();
+    let hooks = hooks();
+    let dispatcher = HookDispatcher::new(self::hooks());
+
+    let mut group = c.benchmark_group("match 100 messages against 500 hooks");
+    group.bench_function("linear", |b| {
+        b.iter(|| {
+            events
+                .iter()
+                .map(|event| hooks.iter().filter(|hook| should_trigger(hook, event)).count())
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("dispatcher", |b| {
+        b.iter(|| {
+            events
+                .iter()
+                .map(|event| {
+                    dispatcher
+                        .candidates(black_box(event))
+                        .into_iter()
+                        .filter(|hook| should_trigger(hook, event))
+                        .count()
+                })
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, trigger_matching);
+criterion_main!(benches);
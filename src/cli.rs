@@ -1,17 +1,39 @@
 //! CLI argument definitions using clap
 
 use clap::{Parser, Subcommand};
+use discli::discord::sharding::ShardRange;
+use discli::sink::SinkSpec;
 use std::path::PathBuf;
 
+/// Exit codes documented in `--help`, see `ErrorClass::exit_code`
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  Success
+  1  General error
+  2  Invalid command-line usage
+  3  Configuration error (missing settings, unknown channel)
+  4  Authentication error (invalid token)
+  5  Permission error (missing access or permissions)
+  6  Validation error (invalid message, attachment or payload)
+  7  Rate limited by Discord
+  8  Network error or Discord server error";
+
 /// A CLI tool for sending Discord notifications with image support
 #[derive(Parser)]
 #[command(name = "discli")]
 #[command(about = "Send messages and images to Discord from the command line", long_about = None)]
+#[command(after_long_help = EXIT_CODES_HELP)]
 pub struct Cli {
     /// Subcommand to execute
     #[command(subcommand)]
     pub command: Option<Commands>,
 
+    /// Where to send output messages
+    ///
+    /// One of: discord, webhook:<url>, stdout, stdout:json, file:<path>
+    #[arg(long, global = true, value_name = "SINK", default_value = "discord")]
+    pub sink: SinkSpec,
+
     /// Legacy: direct message argument (for backward compatibility)
     ///
     /// DEPRECATED: Use 'discli send' instead
@@ -145,6 +167,9 @@ pub enum Commands {
     /// Start the hook listener (long-running mode)
     ///
     /// Listens for messages in configured channels and triggers hooks.
+    /// By default the listener detaches and runs in the background, writing
+    /// its PID to --pidfile and its output to --log-file; on systems other
+    /// than Unix it always runs in the foreground.
     /// Use --foreground to run in foreground (Ctrl+C to stop).
     Listen {
         /// Run in foreground (Ctrl+C to stop)
@@ -153,6 +178,26 @@ pub enum Commands {
         #[arg(short, long, default_value = "false")]
         foreground: bool,
 
+        /// Pidfile used to detect, stop and query a background listener
+        ///
+        /// Default: ./discli.pid (foreground mode writes one only if given)
+        #[arg(long, value_name = "PATH")]
+        pidfile: Option<PathBuf>,
+
+        /// Append output to this file; reopened on SIGHUP
+        ///
+        /// Default: ./discli.log in background mode, the terminal otherwise
+        #[arg(long, value_name = "PATH")]
+        log_file: Option<PathBuf>,
+
+        /// Stop the background listener named in the pidfile
+        #[arg(long, conflicts_with_all = ["foreground", "status"])]
+        stop: bool,
+
+        /// Report whether the background listener is running
+        #[arg(long, conflicts_with = "foreground")]
+        status: bool,
+
         /// Path to hooks configuration file
         ///
         /// Default: ./hooks.yaml
@@ -170,6 +215,271 @@ pub enum Commands {
         /// Shows debug messages
         #[arg(short, long, default_value = "false")]
         verbose: bool,
+
+        /// Seconds to wait for running hooks on Ctrl+C or SIGTERM
+        ///
+        /// Hooks still running after this are aborted
+        #[arg(long, value_name = "SECS", default_value = "10")]
+        shutdown_timeout: u64,
+
+        /// Total number of shards
+        ///
+        /// Default: the count Discord recommends via GET /gateway/bot
+        #[arg(long, value_name = "N")]
+        shards: Option<u32>,
+
+        /// Shards to run in this process, e.g. 0-3 (inclusive) or 5
+        ///
+        /// Default: all shards. Use this to split shards across processes.
+        #[arg(long, value_name = "FIRST-LAST")]
+        shard_range: Option<ShardRange>,
+
+        /// Seconds between checks of the hooks file for edits
+        ///
+        /// An edited hooks.yaml is reloaded if it is valid. SIGHUP also
+        /// triggers a reload. 0 disables the file check.
+        #[arg(long, value_name = "SECS", default_value = "2")]
+        reload_interval: u64,
+
+        /// Record every event the listener sees to this file
+        ///
+        /// Writes one JSON line per event; feed the file to `discli replay`.
+        /// An existing file is replaced.
+        #[arg(long, value_name = "PATH")]
+        record: Option<PathBuf>,
+    },
+
+    /// Run hooks on events recorded with `listen --record`
+    ///
+    /// Feeds each recorded event through the hooks' triggers and actions
+    /// without connecting to Discord. Hook responses go to stdout unless
+    /// --sink stdout:json or --sink file:<path> is given.
+    /// Does not need DISCORD_TOKEN or DISCORD_CHANNEL_ID.
+    Replay {
+        /// Recording to replay (JSON lines, as written by `listen --record`)
+        #[arg(value_name = "FILE")]
+        recording: PathBuf,
+
+        /// Path to hooks configuration file
+        ///
+        /// Default: ./hooks.yaml
+        #[arg(long, value_name = "PATH")]
+        hooks_file: Option<PathBuf>,
+
+        /// Path to prompts directory
+        ///
+        /// Default: ./prompts
+        #[arg(short, long, value_name = "PATH")]
+        prompts_dir: Option<PathBuf>,
+
+        /// Pace events as they were recorded instead of as fast as possible
+        #[arg(long)]
+        realtime: bool,
+
+        /// Speed-up factor for --realtime, e.g. 2 for twice as fast
+        #[arg(long, value_name = "FACTOR", default_value = "1.0", requires = "realtime")]
+        speed: f64,
+
+        /// Enable verbose logging
+        ///
+        /// Shows which hooks trigger and their responses
+        #[arg(short, long, default_value = "false")]
+        verbose: bool,
+    },
+
+    /// Inspect and test hook configuration without connecting to Discord
+    ///
+    /// Does not need DISCORD_TOKEN or DISCORD_CHANNEL_ID.
+    Hooks {
+        #[command(subcommand)]
+        command: HooksCommand,
+    },
+
+    /// Check configuration, token, channel access and permissions
+    ///
+    /// Verifies the bot token, the configured channel and the bot's
+    /// permissions in it, then checks that the hooks file and prompts
+    /// directory load. Exits non-zero if anything is missing.
+    Doctor {
+        /// Path to hooks configuration file
+        ///
+        /// Default: ./hooks.yaml
+        #[arg(long, value_name = "PATH")]
+        hooks_file: Option<PathBuf>,
+
+        /// Path to prompts directory
+        ///
+        /// Default: ./prompts
+        #[arg(short, long, value_name = "PATH")]
+        prompts_dir: Option<PathBuf>,
+
+        /// Discord API base URL
+        ///
+        /// Point this at a local mock of the Discord API for testing.
+        /// Default: DISCORD_API_BASE or https://discord.com/api/v10
+        #[arg(long, value_name = "URL")]
+        api_base: Option<String>,
+    },
+
+    /// Run a local mock of the Discord API
+    ///
+    /// Serves the message, channel, webhook and reaction endpoints discli
+    /// uses and keeps posted messages in memory (see GET /_mock/messages).
+    /// Point DISCORD_API_BASE at it to test notification steps offline.
+    /// Does not need DISCORD_TOKEN or DISCORD_CHANNEL_ID.
+    MockServer {
+        /// Address to listen on
+        #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:8787")]
+        listen: String,
+
+        /// Answer every Nth API request with 429 Too Many Requests
+        #[arg(long, value_name = "N")]
+        rate_limit_every: Option<u64>,
+
+        /// Answer every Nth API request with --fail-status
+        #[arg(long, value_name = "N")]
+        fail_every: Option<u64>,
+
+        /// HTTP status code for injected failures
+        #[arg(long, value_name = "CODE", default_value = "500")]
+        fail_status: u16,
+    },
+}
+
+/// `discli hooks` subcommands
+#[derive(Subcommand)]
+pub enum HooksCommand {
+    /// Check which hooks a message would trigger, and why the others don't
+    ///
+    /// Builds a message from the options, runs it past every hook's
+    /// channels, trigger and filter, and prints the rendered prompt and
+    /// action of each hook that matches. With --run the processor runs and
+    /// the response goes to stdout (or --sink stdout:json / file:<path>).
+    Simulate {
+        /// Channel ID the message is posted in
+        #[arg(long, value_name = "ID")]
+        channel: String,
+
+        /// Server ID the message is posted in
+        ///
+        /// Default: none, i.e. a direct message to the bot
+        #[arg(long, value_name = "ID")]
+        guild: Option<String>,
+
+        /// User ID of the message author
+        #[arg(long, value_name = "ID", default_value = "1")]
+        author: String,
+
+        /// Username of the message author
+        #[arg(long, value_name = "NAME", default_value = "simulated-user")]
+        author_name: String,
+
+        /// Role ID the author has in the server (repeatable)
+        #[arg(long = "role", value_name = "ID")]
+        roles: Vec<String>,
+
+        /// Permission the author has in the channel, e.g. MANAGE_MESSAGES
+        /// (repeatable)
+        #[arg(long = "permission", value_name = "NAME")]
+        permissions: Vec<String>,
+
+        /// Category ID the channel is in
+        #[arg(long, value_name = "ID")]
+        category: Option<String>,
+
+        /// The author is a bot account
+        #[arg(long)]
+        bot: bool,
+
+        /// The message mentions the bot
+        #[arg(long)]
+        mentions_bot: bool,
+
+        /// The message replies to one of the bot's messages
+        #[arg(long)]
+        reply_to_bot: bool,
+
+        /// The message is in a thread the bot started
+        #[arg(long)]
+        in_bot_thread: bool,
+
+        /// The message replies to someone else's message
+        #[arg(long)]
+        reply: bool,
+
+        /// The message has an embed
+        #[arg(long)]
+        embed: bool,
+
+        /// Filename of an attachment, e.g. crash.log (repeatable)
+        #[arg(long = "attachment", value_name = "FILENAME")]
+        attachments: Vec<String>,
+
+        /// Message content
+        #[arg(long, value_name = "TEXT")]
+        content: String,
+
+        /// Run the processor and show what the action would send
+        #[arg(long)]
+        run: bool,
+
+        /// Path to hooks configuration file
+        ///
+        /// Default: ./hooks.yaml
+        #[arg(long, value_name = "PATH")]
+        hooks_file: Option<PathBuf>,
+
+        /// Path to prompts directory
+        ///
+        /// Default: ./prompts
+        #[arg(short, long, value_name = "PATH")]
+        prompts_dir: Option<PathBuf>,
+    },
+
+    /// Run a hook test suite (hooks.test.yaml)
+    ///
+    /// Runs each test's message through the hooks with processors stubbed
+    /// out and checks which hooks fire, their prompts and their responses.
+    /// Exits with code 6 if any test fails.
+    Test {
+        /// Test suite to run
+        #[arg(value_name = "FILE", default_value = "hooks.test.yaml")]
+        suite: PathBuf,
+
+        /// Path to hooks configuration file
+        ///
+        /// Default: the suite's hooks_file, else ./hooks.yaml
+        #[arg(long, value_name = "PATH")]
+        hooks_file: Option<PathBuf>,
+
+        /// Path to prompts directory
+        ///
+        /// Default: the suite's prompts_dir, else ./prompts
+        #[arg(short, long, value_name = "PATH")]
+        prompts_dir: Option<PathBuf>,
+
+        /// Write a JUnit XML report to this file
+        #[arg(long, value_name = "PATH")]
+        junit: Option<PathBuf>,
+    },
+
+    /// Check hooks.yaml and its prompt files for mistakes
+    ///
+    /// Reports every problem found, as path:line:column: message, instead
+    /// of stopping at the first. Exits with code 6 if there are any, so it
+    /// can gate a deploy.
+    Validate {
+        /// Path to hooks configuration file
+        ///
+        /// Default: ./hooks.yaml
+        #[arg(long, value_name = "PATH")]
+        hooks_file: Option<PathBuf>,
+
+        /// Path to prompts directory
+        ///
+        /// Default: ./prompts
+        #[arg(short, long, value_name = "PATH")]
+        prompts_dir: Option<PathBuf>,
     },
 }
 
@@ -182,6 +492,7 @@ mod tests {
         // Just verify that struct can be created
         let cli = Cli {
             command: None,
+            sink: SinkSpec::Discord,
             legacy_message: vec![],
         };
         assert!(cli.command.is_none());
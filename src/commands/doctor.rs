@@ -0,0 +1,564 @@
+//! Doctor command implementation - verifies configuration end to end
+
+use discli::config::Config;
+use discli::discord::permissions::compute_channel_permissions;
+use discli::discord::DiscordClient;
+use discli::error::{DiscliError, ErrorClass, Result};
+use discli::hooks::config::HooksConfig;
+use discli::prompt::{PromptLoader, PromptRegistry};
+use std::path::{Path, PathBuf};
+use twilight_model::guild::Permissions;
+
+/// Permissions the bot needs in the configured channel, with fix-up hints
+const REQUIRED_PERMISSIONS: &[(Permissions, &str, &str)] = &[
+    (
+        Permissions::VIEW_CHANNEL,
+        "VIEW_CHANNEL",
+        "Allow \"View Channel\" for the bot's role in the channel settings",
+    ),
+    (
+        Permissions::SEND_MESSAGES,
+        "SEND_MESSAGES",
+        "Allow \"Send Messages\" for the bot's role (check channel overwrites too)",
+    ),
+    (
+        Permissions::ATTACH_FILES,
+        "ATTACH_FILES",
+        "Allow \"Attach Files\" so `discli send --attach` and `discli image` work",
+    ),
+    (
+        Permissions::EMBED_LINKS,
+        "EMBED_LINKS",
+        "Allow \"Embed Links\" so `discli embed` messages render",
+    ),
+    (
+        Permissions::READ_MESSAGE_HISTORY,
+        "READ_MESSAGE_HISTORY",
+        "Allow \"Read Message History\" so hooks can reply to messages",
+    ),
+];
+
+/// What a thread needs instead of SEND_MESSAGES
+const THREAD_SEND_PERMISSION: (Permissions, &str, &str) = (
+    Permissions::SEND_MESSAGES_IN_THREADS,
+    "SEND_MESSAGES_IN_THREADS",
+    "Allow \"Send Messages in Threads\" for the bot's role in the parent channel",
+);
+
+/// Outcome of a single doctor check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// Everything is in order
+    Pass,
+    /// Not fatal, but worth looking at
+    Warn,
+    /// Something discli needs is missing or broken
+    Fail,
+}
+
+/// Result of a single doctor check
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    /// Short name of what was checked
+    pub name: String,
+    /// Outcome of the check
+    pub status: CheckStatus,
+    /// What was found
+    pub detail: String,
+    /// How to fix it, if the check did not pass
+    pub hint: Option<String>,
+    /// What kind of problem a failed check found, for the exit code
+    pub class: Option<ErrorClass>,
+}
+
+impl CheckResult {
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            hint: None,
+            class: None,
+        }
+    }
+
+    fn warn(name: impl Into<String>, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+            class: None,
+        }
+    }
+
+    fn fail(
+        name: impl Into<String>,
+        class: ErrorClass,
+        detail: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+            class: Some(class),
+        }
+    }
+}
+
+/// Execute the doctor command
+///
+/// # Arguments
+///
+/// * `config` - Application configuration
+/// * `hooks_file` - Optional hooks file override
+/// * `prompts_dir` - Optional prompts directory override
+/// * `api_base` - Optional Discord API base URL override (defaults to `DISCORD_API_BASE`)
+///
+/// # Returns
+///
+/// `Ok(())` if no check failed
+pub async fn execute(
+    config: &Config,
+    hooks_file: Option<PathBuf>,
+    prompts_dir: Option<PathBuf>,
+    api_base: Option<String>,
+) -> Result<()> {
+    let api_base = api_base.unwrap_or_else(|| config.api_base.clone());
+    let client = DiscordClient::with_base_url(config.discord_token.clone(), api_base);
+    let hooks_path = hooks_file.unwrap_or_else(|| config.hooks_file.clone());
+    let prompts_dir = prompts_dir.unwrap_or_else(|| config.prompts_dir.clone());
+
+    let results = run_checks(&client, &config.channel_id, &hooks_path, &prompts_dir).await;
+
+    for result in &results {
+        let icon = match result.status {
+            CheckStatus::Pass => "✅",
+            CheckStatus::Warn => "⚠️ ",
+            CheckStatus::Fail => "❌",
+        };
+        println!("{} {}: {}", icon, result.name, result.detail);
+        if let Some(hint) = &result.hint {
+            println!("   → {}", hint);
+        }
+    }
+
+    // Exit as the first failure would have, e.g. 4 for a rejected token
+    let failures: Vec<&CheckResult> = results
+        .iter()
+        .filter(|r| r.status == CheckStatus::Fail)
+        .collect();
+    if let Some(first) = failures.first() {
+        return Err(DiscliError::ChecksFailed {
+            count: failures.len(),
+            class: first.class.unwrap_or(ErrorClass::General),
+        });
+    }
+
+    println!();
+    println!("All checks passed");
+    Ok(())
+}
+
+/// Run every doctor check and collect the results
+///
+/// Discord checks stop at the first failure, since later checks depend on
+/// earlier ones (no channel without a valid token, and so on).
+pub async fn run_checks(
+    client: &DiscordClient,
+    channel_id: &str,
+    hooks_path: &Path,
+    prompts_dir: &Path,
+) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+    check_discord(client, channel_id, &mut results).await;
+    check_hooks(hooks_path, prompts_dir, &mut results);
+    check_prompts(prompts_dir, &mut results);
+    results
+}
+
+/// Check the token, the channel and the bot's permissions in it
+async fn check_discord(client: &DiscordClient, channel_id: &str, results: &mut Vec<CheckResult>) {
+    let user = match client.get_current_user().await {
+        Ok(user) => {
+            results.push(CheckResult::pass(
+                "Token",
+                format!("authenticated as {} ({})", user.username, user.id),
+            ));
+            user
+        }
+        Err(e) => {
+            results.push(CheckResult::fail(
+                "Token",
+                e.class(),
+                e.to_string(),
+                "Check DISCORD_TOKEN; reset it under Developer Portal → Bot if it was revoked",
+            ));
+            return;
+        }
+    };
+
+    let channel = match client.get_channel(channel_id).await {
+        Ok(channel) => channel,
+        Err(e) => {
+            results.push(CheckResult::fail(
+                "Channel",
+                e.class(),
+                e.to_string(),
+                "Check DISCORD_CHANNEL_ID and that the bot has been invited to the server",
+            ));
+            return;
+        }
+    };
+
+    let guild_id = match &channel.guild_id {
+        Some(guild_id) => guild_id.clone(),
+        None => {
+            results.push(CheckResult::pass(
+                "Channel",
+                format!("{} is a direct message channel", channel.id),
+            ));
+            return;
+        }
+    };
+
+    let guild = match client.get_guild(&guild_id).await {
+        Ok(guild) => guild,
+        Err(e) => {
+            results.push(CheckResult::fail(
+                "Guild",
+                e.class(),
+                e.to_string(),
+                "Make sure the bot is still a member of the server",
+            ));
+            return;
+        }
+    };
+
+    // Threads take their overwrites from the parent channel
+    let parent = match channel.parent_id.as_deref().filter(|_| channel.is_thread()) {
+        Some(parent_id) => match client.get_channel(parent_id).await {
+            Ok(parent) => Some(parent),
+            Err(e) => {
+                results.push(CheckResult::fail(
+                    "Channel",
+                    e.class(),
+                    format!("thread {}'s parent channel: {}", channel.id, e),
+                    "Allow the bot to view the thread's parent channel",
+                ));
+                return;
+            }
+        },
+        None => None,
+    };
+
+    results.push(CheckResult::pass(
+        "Channel",
+        format!(
+            "#{} ({}) in {}",
+            channel.name.as_deref().unwrap_or("unknown"),
+            channel.id,
+            guild.name
+        ),
+    ));
+
+    let member = match client.get_guild_member(&guild_id, &user.id).await {
+        Ok(member) => member,
+        Err(e) => {
+            results.push(CheckResult::fail(
+                "Membership",
+                e.class(),
+                e.to_string(),
+                "Invite the bot to the server that owns the channel",
+            ));
+            return;
+        }
+    };
+
+    let overwrites_from = parent.as_ref().unwrap_or(&channel);
+    let permissions = compute_channel_permissions(&guild, overwrites_from, &user.id, &member.roles);
+    for (permission, name, hint) in REQUIRED_PERMISSIONS.iter().map(|required| {
+        if channel.is_thread() && required.0 == Permissions::SEND_MESSAGES {
+            &THREAD_SEND_PERMISSION
+        } else {
+            required
+        }
+    }) {
+        let check = format!("Permission {}", name);
+        if permissions.contains(*permission) {
+            results.push(CheckResult::pass(check, "granted"));
+        } else {
+            results.push(CheckResult::fail(
+                check,
+                ErrorClass::Permission,
+                format!("missing in channel {}", channel.id),
+                *hint,
+            ));
+        }
+    }
+}
+
+/// Check that the hooks file parses and every enabled hook compiles
+fn check_hooks(hooks_path: &Path, prompts_dir: &Path, results: &mut Vec<CheckResult>) {
+    if !hooks_path.exists() {
+        results.push(CheckResult::warn(
+            "Hooks",
+            format!("{} not found", hooks_path.display()),
+            "Only needed for `discli listen`; copy hooks.yaml.example to get started",
+        ));
+        return;
+    }
+
+    let hooks_config = match HooksConfig::load(hooks_path) {
+        Ok(config) => config,
+        Err(e) => {
+            results.push(CheckResult::fail(
+                "Hooks",
+                e.class(),
+                e.to_string(),
+                format!("Fix {} and run `discli doctor` again", hooks_path.display()),
+            ));
+            return;
+        }
+    };
+
+    let mut registry = PromptRegistry::new(prompts_dir.to_path_buf());
+    let mut problems = 0;
+    for hook in hooks_config.enabled_hooks() {
+        if let Err(e) = hook.compile() {
+            problems += 1;
+            results.push(CheckResult::fail(
+                format!("Hook {}", hook.id),
+                ErrorClass::Config,
+                e.to_string(),
+                "Fix the trigger configuration of this hook",
+            ));
+        }
+        if let Err(e) = registry.get(&hook.prompt_file) {
+            problems += 1;
+            results.push(CheckResult::fail(
+                format!("Hook {}", hook.id),
+                ErrorClass::Config,
+                format!("prompt file {}: {}", hook.prompt_file.display(), e),
+                format!("Create the prompt file under {}", prompts_dir.display()),
+            ));
+        }
+    }
+
+    if problems == 0 {
+        results.push(CheckResult::pass(
+            "Hooks",
+            format!(
+                "{} hooks loaded from {} ({} enabled)",
+                hooks_config.hooks.len(),
+                hooks_path.display(),
+                hooks_config.enabled_hooks().len()
+            ),
+        ));
+    }
+}
+
+/// Check that the prompts directory exists and its templates load
+fn check_prompts(prompts_dir: &Path, results: &mut Vec<CheckResult>) {
+    if !prompts_dir.exists() {
+        results.push(CheckResult::warn(
+            "Prompts",
+            format!("{} not found", prompts_dir.display()),
+            "Only needed for `discli listen`; use --prompts-dir or PROMPTS_DIR",
+        ));
+        return;
+    }
+
+    let mut loader = PromptLoader::new(prompts_dir.to_path_buf());
+    match loader.load_all() {
+        Ok(templates) => results.push(CheckResult::pass(
+            "Prompts",
+            format!("{} templates in {}", templates.len(), prompts_dir.display()),
+        )),
+        Err(e) => results.push(CheckResult::fail(
+            "Prompts",
+            ErrorClass::Config,
+            e.to_string(),
+            "Make sure the prompts directory is readable",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use discli::mock::{MockServer, MockServerConfig};
+    use tempfile::TempDir;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve canned JSON responses keyed by request path, returning the base URL
+    async fn stub_api(routes: Vec<(&'static str, u16, &'static str)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(_) => return,
+                };
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+
+                let (status, body) = routes
+                    .iter()
+                    .find(|(route, _, _)| *route == path)
+                    .map(|(_, status, body)| (*status, *body))
+                    .unwrap_or((404, r#"{"message": "404: Not Found", "code": 0}"#));
+
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+
+    const USER: &str = r#"{"id": "500", "username": "discli-bot", "bot": true}"#;
+    const CHANNEL: &str = r#"{"id": "10", "type": 0, "guild_id": "1", "name": "general",
+        "permission_overwrites": [{"id": "1", "type": 0, "allow": "0", "deny": "32768"}]}"#;
+    const GUILD: &str = r#"{"id": "1", "name": "Test Guild", "owner_id": "2",
+        "roles": [{"id": "1", "name": "@everyone", "permissions": "117760"}]}"#;
+    const MEMBER: &str = r#"{"roles": []}"#;
+
+    fn missing_paths() -> (PathBuf, PathBuf) {
+        (
+            PathBuf::from("/nonexistent/hooks.yaml"),
+            PathBuf::from("/nonexistent/prompts"),
+        )
+    }
+
+    fn find<'a>(results: &'a [CheckResult], name: &str) -> &'a CheckResult {
+        results.iter().find(|r| r.name == name).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_doctor_reports_missing_permission() {
+        let base = stub_api(vec![
+            ("/users/@me", 200, USER),
+            ("/channels/10", 200, CHANNEL),
+            ("/guilds/1", 200, GUILD),
+            ("/guilds/1/members/500", 200, MEMBER),
+        ])
+        .await;
+        let client = DiscordClient::with_base_url("token".to_string(), base);
+        let (hooks, prompts) = missing_paths();
+
+        let results = run_checks(&client, "10", &hooks, &prompts).await;
+
+        assert_eq!(find(&results, "Token").status, CheckStatus::Pass);
+        assert_eq!(find(&results, "Channel").status, CheckStatus::Pass);
+        assert_eq!(find(&results, "Permission SEND_MESSAGES").status, CheckStatus::Pass);
+        // ATTACH_FILES (1 << 15) is denied by the @everyone overwrite
+        assert_eq!(find(&results, "Permission ATTACH_FILES").status, CheckStatus::Fail);
+        assert_eq!(find(&results, "Permission ATTACH_FILES").class, Some(ErrorClass::Permission));
+        assert_eq!(find(&results, "Hooks").status, CheckStatus::Warn);
+    }
+
+    #[tokio::test]
+    async fn test_doctor_checks_threads_against_parent_channel() {
+        // The thread is in #general (10), whose @everyone overwrite denies
+        // SEND_MESSAGES_IN_THREADS (1 << 38)
+        const THREAD: &str = r#"{"id": "30", "type": 11, "guild_id": "1", "name": "help",
+            "parent_id": "10", "owner_id": "2"}"#;
+        const PARENT: &str = r#"{"id": "10", "type": 0, "guild_id": "1", "name": "general",
+            "permission_overwrites": [{"id": "1", "type": 0, "allow": "0", "deny": "274877906944"}]}"#;
+        const GUILD_WITH_THREADS: &str = r#"{"id": "1", "name": "Test Guild", "owner_id": "2",
+            "roles": [{"id": "1", "name": "@everyone", "permissions": "274878024704"}]}"#;
+        let base = stub_api(vec![
+            ("/users/@me", 200, USER),
+            ("/channels/30", 200, THREAD),
+            ("/channels/10", 200, PARENT),
+            ("/guilds/1", 200, GUILD_WITH_THREADS),
+            ("/guilds/1/members/500", 200, MEMBER),
+        ])
+        .await;
+        let client = DiscordClient::with_base_url("token".to_string(), base);
+        let (hooks, prompts) = missing_paths();
+
+        let results = run_checks(&client, "30", &hooks, &prompts).await;
+
+        assert_eq!(find(&results, "Channel").status, CheckStatus::Pass);
+        assert!(results.iter().all(|r| r.name != "Permission SEND_MESSAGES"));
+        let send = find(&results, "Permission SEND_MESSAGES_IN_THREADS");
+        assert_eq!(send.status, CheckStatus::Fail);
+        assert_eq!(send.class, Some(ErrorClass::Permission));
+        assert_eq!(find(&results, "Permission ATTACH_FILES").status, CheckStatus::Pass);
+    }
+
+    #[tokio::test]
+    async fn test_doctor_stops_on_bad_token() {
+        let base = stub_api(vec![(
+            "/users/@me",
+            401,
+            r#"{"message": "401: Unauthorized", "code": 0}"#,
+        )])
+        .await;
+        let client = DiscordClient::with_base_url("bad".to_string(), base);
+        let (hooks, prompts) = missing_paths();
+
+        let results = run_checks(&client, "10", &hooks, &prompts).await;
+
+        assert_eq!(find(&results, "Token").status, CheckStatus::Fail);
+        assert_eq!(find(&results, "Token").class, Some(ErrorClass::Auth));
+        assert!(results.iter().all(|r| r.name != "Channel"));
+    }
+
+    #[tokio::test]
+    async fn test_doctor_passes_against_mock_server() {
+        let server = MockServer::start("127.0.0.1:0", MockServerConfig::default())
+            .await
+            .unwrap();
+        let client = DiscordClient::with_base_url("token".to_string(), server.base_url());
+        let (hooks, prompts) = missing_paths();
+
+        let results = run_checks(&client, "10", &hooks, &prompts).await;
+
+        assert!(results
+            .iter()
+            .filter(|r| r.name == "Token" || r.name.starts_with("Permission"))
+            .all(|r| r.status == CheckStatus::Pass));
+    }
+
+    #[test]
+    fn test_check_hooks_missing_prompt_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let hooks_path = temp_dir.path().join("hooks.yaml");
+        std::fs::write(
+            &hooks_path,
+            r#"
+version: "1.0"
+hooks:
+  - id: "echo"
+    channels: ["10"]
+    trigger:
+      type: "prefix"
+      prefix: "!echo"
+    prompt_file: "missing.txt"
+    action:
+      type: "reply"
+"#,
+        )
+        .unwrap();
+
+        let mut results = Vec::new();
+        check_hooks(&hooks_path, temp_dir.path(), &mut results);
+
+        assert_eq!(find(&results, "Hook echo").status, CheckStatus::Fail);
+    }
+}
@@ -1,15 +1,16 @@
 //! Embed command implementation
 
-use crate::config::Config;
-use crate::discord::types::{DiscordMessage, Embed, EmbedAuthor, EmbedField, EmbedFooter, EmbedMedia, EmbedThumbnail};
-use crate::discord::DiscordClient;
-use crate::error::Result;
+use discli::config::Config;
+use discli::discord::types::{DiscordMessage, Embed, EmbedAuthor, EmbedField, EmbedFooter, EmbedMedia, EmbedThumbnail};
+use discli::error::Result;
+use discli::sink::MessageSink;
 
 /// Execute the embed command
 ///
 /// # Arguments
 ///
 /// * `config` - Application configuration
+/// * `sink` - Where to send the message
 /// * `title` - Embed title
 /// * `embed_url` - URL for the title (makes it clickable)
 /// * `description` - Embed description
@@ -26,8 +27,10 @@ use crate::error::Result;
 /// # Returns
 ///
 /// `Ok(())` if message was sent successfully
+#[allow(clippy::too_many_arguments)]
 pub async fn execute(
     config: &Config,
+    sink: &dyn MessageSink,
     title: Option<String>,
     embed_url: Option<String>,
     description: Option<String>,
@@ -42,11 +45,12 @@ pub async fn execute(
     content: Option<String>,
 ) -> Result<()> {
     // Build the embed
-    let mut embed = Embed::default();
-
-    embed.title = title;
-    embed.url = embed_url;
-    embed.description = description;
+    let mut embed = Embed {
+        title,
+        url: embed_url,
+        description,
+        ..Default::default()
+    };
 
     // Parse color (hex string like "FF5500" or "#FF5500")
     if let Some(color_str) = color {
@@ -102,9 +106,7 @@ pub async fn execute(
     };
 
     // Send message
-    let client = DiscordClient::new(config.discord_token.clone());
-    client
-        .send_message(&config.channel_id, &discord_message)
+    sink.send_message(&config.channel_id, &discord_message)
         .await?;
 
     println!("Successfully sent embed to channel {}", config.channel_id);
@@ -116,7 +118,7 @@ pub async fn execute(
 fn parse_hex_color(color: &str) -> Result<u32> {
     let color = color.trim_start_matches('#');
     u32::from_str_radix(color, 16)
-        .map_err(|_| crate::error::DiscliError::Validation(format!("Invalid hex color: {}", color)))
+        .map_err(|_| discli::error::DiscliError::Validation(format!("Invalid hex color: {}", color)))
 }
 
 /// Parse a field string in format "name:value:inline"
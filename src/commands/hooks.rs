@@ -0,0 +1,348 @@
+//! Hooks command implementations - inspect, test and validate hook configuration
+
+use discli::config::Config;
+use discli::error::{DiscliError, Result};
+use discli::hooks::config::{CompiledTrigger, HookAction, HooksConfig};
+use discli::hooks::dispatch::{decide, run_order, Skipped};
+use discli::hooks::event::IncomingEvent;
+use discli::hooks::executor::HookExecutor;
+use discli::hooks::testing::{junit_report, TestMessage, TestSuite};
+use discli::hooks::trigger::{trace_trigger, Mismatch, TriggerTrace};
+use discli::hooks::validate::validate_file;
+use discli::prompt::registry::PromptRegistry;
+use discli::sink::MessageSink;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Options for `hooks simulate`
+pub struct SimulateOptions {
+    /// Hooks file override
+    pub hooks_file: Option<PathBuf>,
+    /// Prompts directory override
+    pub prompts_dir: Option<PathBuf>,
+    /// Channel the message is posted in
+    pub channel: String,
+    /// Server the message is posted in; `None` for a direct message
+    pub guild: Option<String>,
+    /// ID of the message author
+    pub author: String,
+    /// Username of the message author
+    pub author_name: String,
+    /// Author's role IDs in the server
+    pub roles: Vec<String>,
+    /// Author's permissions in the channel
+    pub permissions: Vec<String>,
+    /// Category the channel is in
+    pub category: Option<String>,
+    /// Whether the author is a bot account
+    pub bot: bool,
+    /// Whether the message mentions the bot
+    pub mentions_bot: bool,
+    /// Whether the message replies to the bot
+    pub reply_to_bot: bool,
+    /// Whether the message is in a thread the bot started
+    pub in_bot_thread: bool,
+    /// Whether the message replies to someone else's message
+    pub reply: bool,
+    /// Whether the message has an embed
+    pub embed: bool,
+    /// Attachment filenames
+    pub attachments: Vec<String>,
+    /// Message content
+    pub content: String,
+    /// Run the processor and action of matching hooks
+    pub run: bool,
+}
+
+/// Execute `hooks simulate` - check one message against every hook
+///
+/// Prints, for each hook in the order they run, whether it matches and
+/// otherwise why not, including hooks skipped by `continue: false` or as
+/// unneeded fallbacks. For matching hooks it prints the rendered prompt and
+/// where the action would send the response; with `run` the processor runs
+/// and the response goes to `sink`.
+pub async fn simulate(
+    config: &Config,
+    sink: Arc<dyn MessageSink>,
+    options: SimulateOptions,
+) -> Result<()> {
+    let hooks_path = options
+        .hooks_file
+        .clone()
+        .unwrap_or_else(|| config.hooks_file.clone());
+    if !hooks_path.exists() {
+        return Err(DiscliError::Config(format!(
+            "Hooks file not found: {} (use --hooks-file or create hooks.yaml)",
+            hooks_path.display()
+        )));
+    }
+    let hooks_config = HooksConfig::load(&hooks_path)?;
+    let prompts_dir = options
+        .prompts_dir
+        .clone()
+        .unwrap_or_else(|| config.prompts_dir.clone());
+
+    let message = TestMessage {
+        channel: options.channel.clone(),
+        guild: options.guild.clone(),
+        author: options.author.clone(),
+        author_name: options.author_name.clone(),
+        roles: options.roles.clone(),
+        bot: options.bot,
+        category: options.category.clone(),
+        permissions: options.permissions.clone(),
+        mentions_bot: options.mentions_bot,
+        reply_to_bot: options.reply_to_bot,
+        in_bot_thread: options.in_bot_thread,
+        reply: options.reply,
+        embed: options.embed,
+        attachments: options.attachments.clone(),
+        content: options.content.clone(),
+    };
+    let event = message.to_event()?;
+    let context = message.context()?;
+    let place = match &options.guild {
+        Some(guild) => format!("channel {} (server {})", options.channel, guild),
+        None => format!("DM channel {}", options.channel),
+    };
+    println!(
+        "Message from {} ({}) in {}: {:?}",
+        options.author_name, options.author, place, options.content
+    );
+    println!();
+
+    let mut registry = PromptRegistry::new(prompts_dir.clone());
+    let executor = if options.run {
+        let mut executor_config = config.clone();
+        executor_config.prompts_dir = prompts_dir;
+        Some(
+            HookExecutor::new(executor_config, sink)
+                .with_settings(hooks_config.settings.clone()),
+        )
+    } else {
+        None
+    };
+
+    let mut compiled = Vec::new();
+    for hook in &hooks_config.hooks {
+        if !hook.enabled {
+            println!("✗ {}: disabled", hook.id);
+            continue;
+        }
+        match hook.compile() {
+            Ok(hook) => compiled.push(hook),
+            Err(e) => println!("✗ {}: failed to compile: {}", hook.id, e),
+        }
+    }
+
+    let mut matched = 0;
+    for (hook, outcome) in decide(&run_order(&compiled), &event, &context) {
+        match outcome {
+            Ok(()) => {}
+            Err(Skipped::Mismatch(Mismatch::Trigger)) if is_combination(&hook.trigger) => {
+                println!("✗ {}: trigger did not match", hook.id);
+                print_trace(&trace_trigger(&hook.trigger, &event, &context));
+                continue;
+            }
+            Err(Skipped::Mismatch(Mismatch::Trigger)) => {
+                println!("✗ {}: trigger {} did not match", hook.id, hook.trigger);
+                continue;
+            }
+            Err(skipped) => {
+                println!("✗ {}: {}", hook.id, skipped);
+                continue;
+            }
+        }
+
+        matched += 1;
+        println!("✓ {}: matched", hook.id);
+        if is_combination(&hook.trigger) {
+            print_trace(&trace_trigger(&hook.trigger, &event, &context));
+        }
+        match registry.render(&hook.prompt_file, &event.variables_in(&context)) {
+            Ok(prompt) => {
+                println!("  Prompt ({}):", hook.prompt_file.display());
+                for line in prompt.lines() {
+                    println!("    {}", line);
+                }
+            }
+            Err(e) => println!("  Prompt error: {}", e),
+        }
+        println!("  Action: {}", describe_action(&hook.action, &event));
+
+        if let Some(executor) = &executor {
+            match executor.execute_in(hook, &event, &context).await {
+                Ok(result) => {
+                    if let Some(error) = result.error {
+                        println!("  Error: {}", error);
+                    }
+                }
+                Err(e) => println!("  Failed: {}", e),
+            }
+        }
+    }
+
+    println!();
+    println!("{} of {} hooks matched", matched, hooks_config.hooks.len());
+    Ok(())
+}
+
+/// Whether a trigger combines others, so is worth explaining branch by
+/// branch
+fn is_combination(trigger: &CompiledTrigger) -> bool {
+    !trigger.branches().is_empty()
+}
+
+/// Print which branches of a trigger matched, indented under the hook
+fn print_trace(trace: &TriggerTrace) {
+    println!("  Trigger:");
+    for line in trace.to_string().lines() {
+        println!("    {}", line);
+    }
+}
+
+/// Where an action would send the hook's response
+fn describe_action(action: &HookAction, event: &IncomingEvent) -> String {
+    let or_none = |id: Option<String>| id.unwrap_or_else(|| "(none)".to_string());
+    match action {
+        HookAction::Reply => format!(
+            "reply in channel {}",
+            or_none(event.reply_channel_id().map(|id| id.to_string()))
+        ),
+        HookAction::SendDm => format!(
+            "DM user {}",
+            or_none(event.user_id().map(|id| id.to_string()))
+        ),
+        HookAction::Forward { channel_id } => format!("forward to channel {}", channel_id),
+        HookAction::Webhook { url } => format!("post to webhook {}", url),
+    }
+}
+
+/// Options for `hooks test`
+pub struct TestOptions {
+    /// Hooks file override
+    pub hooks_file: Option<PathBuf>,
+    /// Prompts directory override
+    pub prompts_dir: Option<PathBuf>,
+    /// Write a JUnit XML report here
+    pub junit: Option<PathBuf>,
+}
+
+/// Execute `hooks test` - run a hook test suite
+///
+/// The hooks file and prompts directory come from the options, then the
+/// suite, then the configuration. Every enabled hook must compile.
+///
+/// # Errors
+///
+/// Returns a validation error if any test fails
+pub async fn test(config: &Config, suite_path: &Path, options: TestOptions) -> Result<()> {
+    let suite = TestSuite::load(suite_path)?;
+    let hooks_path = options
+        .hooks_file
+        .or_else(|| suite.hooks_file.clone())
+        .unwrap_or_else(|| config.hooks_file.clone());
+    let prompts_dir = options
+        .prompts_dir
+        .or_else(|| suite.prompts_dir.clone())
+        .unwrap_or_else(|| config.prompts_dir.clone());
+
+    if !hooks_path.exists() {
+        return Err(DiscliError::Config(format!(
+            "Hooks file not found: {} (use --hooks-file or set hooks_file in the suite)",
+            hooks_path.display()
+        )));
+    }
+    let hooks_config = HooksConfig::load(&hooks_path)?;
+    let hooks = hooks_config.compile_enabled()?;
+
+    let outcomes = suite.run(&hooks, &prompts_dir).await;
+    for outcome in &outcomes {
+        if outcome.passed() {
+            println!("✓ {}", outcome.name);
+        } else {
+            println!("✗ {}", outcome.name);
+            for failure in &outcome.failures {
+                println!("    {}", failure);
+            }
+        }
+    }
+
+    let failed = outcomes.iter().filter(|o| !o.passed()).count();
+    println!();
+    println!("{} passed, {} failed", outcomes.len() - failed, failed);
+
+    if let Some(path) = options.junit {
+        let name = suite_path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "hooks".to_string());
+        std::fs::write(&path, junit_report(&name, &outcomes))?;
+        println!("JUnit report written to {}", path.display());
+    }
+
+    if failed > 0 {
+        return Err(DiscliError::Validation(format!(
+            "{} of {} hook tests failed",
+            failed,
+            outcomes.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Options for `hooks validate`
+pub struct ValidateOptions {
+    /// Hooks file override
+    pub hooks_file: Option<PathBuf>,
+    /// Prompts directory override
+    pub prompts_dir: Option<PathBuf>,
+}
+
+/// Execute `hooks validate` - report every problem in the hooks file
+///
+/// # Errors
+///
+/// Returns a validation error if any problem is found
+pub fn validate(config: &Config, options: ValidateOptions) -> Result<()> {
+    let hooks_path = options
+        .hooks_file
+        .unwrap_or_else(|| config.hooks_file.clone());
+    if !hooks_path.exists() {
+        return Err(DiscliError::Config(format!(
+            "Hooks file not found: {} (use --hooks-file or create hooks.yaml)",
+            hooks_path.display()
+        )));
+    }
+    let prompts_dir = options
+        .prompts_dir
+        .unwrap_or_else(|| config.prompts_dir.clone());
+
+    let diagnostics = validate_file(&hooks_path, &prompts_dir)?;
+    if diagnostics.is_empty() {
+        println!("{} is valid", hooks_path.display());
+        return Ok(());
+    }
+
+    for diagnostic in &diagnostics {
+        match diagnostic.location {
+            Some(at) => println!(
+                "{}:{}:{}: {}",
+                hooks_path.display(),
+                at.line,
+                at.column,
+                diagnostic.message
+            ),
+            None => println!("{}: {}", hooks_path.display(), diagnostic.message),
+        }
+    }
+    println!();
+
+    let count = diagnostics.len();
+    Err(DiscliError::Validation(format!(
+        "{} {} found in {}",
+        count,
+        if count == 1 { "problem" } else { "problems" },
+        hooks_path.display()
+    )))
+}
@@ -1,7 +1,8 @@
 //! Image command implementation
 
-use crate::config::Config;
-use crate::error::Result;
+use discli::config::Config;
+use discli::error::Result;
+use discli::sink::MessageSink;
 use std::path::PathBuf;
 
 /// Execute the image command
@@ -12,6 +13,7 @@ use std::path::PathBuf;
 /// # Arguments
 ///
 /// * `config` - Application configuration
+/// * `sink` - Where to send the message
 /// * `attach` - List of file paths to attach (at least one required)
 /// * `caption` - Optional caption text for the images
 /// * `embed_url` - List of image URLs to embed (future feature)
@@ -21,6 +23,7 @@ use std::path::PathBuf;
 /// `Ok(())` if message was sent successfully
 pub async fn execute(
     config: &Config,
+    sink: &dyn MessageSink,
     attach: Vec<PathBuf>,
     caption: Option<String>,
     embed_url: Vec<String>,
@@ -29,5 +32,5 @@ pub async fn execute(
     let content = caption.unwrap_or_default();
 
     // Reuse the send command's logic
-    super::send::execute(config, content, attach, embed_url, None).await
+    super::send::execute(config, sink, content, attach, embed_url, None).await
 }
@@ -1,112 +1,509 @@
 //! Listen command implementation - starts the hook listener
 
-use crate::config::Config;
-use crate::discord::DiscordGateway;
-use crate::hooks::config::{CompiledHookConfig, HooksConfig};
-use crate::hooks::executor::HookExecutor;
-use crate::hooks::trigger::should_trigger;
-use crate::error::{DiscliError, Result};
+use discli::config::Config;
+use discli::discord::sharding::{ShardPlan, ShardRange, ShardStatuses};
+use discli::discord::gateway::privileged_intent_names;
+use discli::discord::{DiscordClient, DiscordGateway};
+use discli::hooks::config::{CompiledHookConfig, ErrorStrategy, HooksConfig};
+use discli::hooks::dispatch::{select, HookDispatcher};
+use discli::hooks::context::{ContextNeeds, EventContext, GuildCache};
+use discli::hooks::event::IncomingEvent;
+use discli::hooks::executor::HookExecutor;
+use discli::hooks::recording::EventRecorder;
+use discli::hooks::reload::{HooksReloader, Reload, ReloadSummary};
+use discli::error::{DiscliError, Result};
+use discli::sink::MessageSink;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use twilight_model::gateway::payload::incoming::MessageCreate;
+use std::time::Duration;
+use tokio::sync::{watch, RwLock};
+use tokio::time::{Interval, MissedTickBehavior};
+use tracing_subscriber::EnvFilter;
+use twilight_gateway::{Event, EventTypeFlags};
+use twilight_model::gateway::Intents;
+
+/// Options for the listen command
+pub struct ListenOptions {
+    /// Hooks file override
+    pub hooks_file: Option<PathBuf>,
+    /// Prompts directory override
+    pub prompts_dir: Option<PathBuf>,
+    /// Print hook activity
+    pub verbose: bool,
+    /// How long shutdown waits for running hooks
+    pub shutdown_timeout: Duration,
+    /// Total shard count, instead of Discord's recommendation
+    pub shards: Option<u32>,
+    /// Shards to run in this process
+    pub shard_range: Option<ShardRange>,
+    /// Record every event to this file for `discli replay`
+    pub record: Option<PathBuf>,
+    /// How often to check the hooks file for edits; `None` only reloads
+    /// on SIGHUP
+    pub reload_interval: Option<Duration>,
+}
 
 /// Execute the listen command - starts the hook listener
 pub async fn execute(
     config: &Config,
-    hooks_file: Option<std::path::PathBuf>,
-    prompts_dir: Option<std::path::PathBuf>,
-    verbose: bool,
+    sink: Arc<dyn MessageSink>,
+    options: ListenOptions,
 ) -> Result<()> {
+    let ListenOptions {
+        hooks_file,
+        prompts_dir,
+        verbose,
+        shutdown_timeout,
+        shards,
+        shard_range,
+        record,
+        reload_interval,
+    } = options;
+
+    // Gateway logs (connects, reconnects, shutdown) go to stderr, which is
+    // the log file when running in the background
+    let default_level = if verbose { "discli=info" } else { "discli=warn" };
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_level)),
+        )
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .try_init();
+
     // Load hook configuration
     let hooks_path = hooks_file.unwrap_or_else(|| config.hooks_file.clone());
-    
-    if !hooks_path.exists() {
-        return Err(DiscliError::Config(format!(
-            "Hooks file not found: {} (use --hooks-file or create hooks.yaml)",
-            hooks_path.display()
-        )));
-    }
-    
-    let hooks_config = HooksConfig::load(&hooks_path)?;
+    let (hooks_config, compiled_hooks) = load_hooks(&hooks_path, verbose)?;
     
     // Override prompts_dir if provided
     let prompts_dir = prompts_dir.unwrap_or_else(|| config.prompts_dir.clone());
     
     if verbose {
-        println!("Loaded {} hooks from {}", hooks_config.hooks.len(), hooks_path.display());
         println!("Prompts directory: {}", prompts_dir.display());
     }
-    
-    // Compile hooks
-    let mut compiled_hooks: Vec<CompiledHookConfig> = Vec::new();
-    for hook in hooks_config.enabled_hooks() {
-        match hook.compile() {
-            Ok(compiled) => {
-                if verbose {
-                    println!("Compiled hook: {} ({:?})", compiled.id, compiled.trigger);
-                }
-                compiled_hooks.push(compiled);
-            }
-            Err(e) => {
-                eprintln!("Warning: Failed to compile hook {}: {}", hook.id, e);
-            }
+
+    let recorder = match &record {
+        Some(path) => {
+            println!("Recording events to {}", path.display());
+            Some(EventRecorder::create(path).await?)
         }
-    }
+        None => None,
+    };
     
-    if compiled_hooks.is_empty() {
-        return Err(DiscliError::Config("No valid hooks to execute".into()));
+    // Use Discord's recommended shard count unless one was given
+    let plan = match shards {
+        Some(_) => ShardPlan::resolve(None, shards, shard_range)?,
+        None => {
+            let client =
+                DiscordClient::with_base_url(config.discord_token.clone(), config.api_base.clone());
+            let recommended = client.get_gateway_bot().await?;
+            ShardPlan::resolve(Some(&recommended), None, shard_range)?
+        }
+    };
+
+    // Only ask for what the hooks use; privileged intents must be enabled
+    // in the Developer Portal or Discord closes the connection with 4014
+    let intents = hooks_config.required_intents();
+    if verbose {
+        let names: Vec<&str> = intents.iter_names().map(|(name, _)| name).collect();
+        println!("Gateway intents: {}", names.join(", "));
+        let privileged = privileged_intent_names(intents);
+        if !privileged.is_empty() {
+            println!("Privileged intents required: {}", privileged.join(", "));
+        }
     }
-    
-    println!("Starting Discord gateway...");
+
+    println!("Starting Discord gateway ({})...", plan);
     println!("Press Ctrl+C to stop");
     
     // Create gateway
-    let gateway = DiscordGateway::new(config.discord_token.clone());
+    let mut gateway = DiscordGateway::with_shards(config.discord_token.clone(), &plan, intents)
+        .with_event_types(hooks_config.required_event_types())
+        .with_shutdown_timeout(shutdown_timeout);
+    if let Some(recorder) = &recorder {
+        // READY tells a replay who the bot is
+        gateway = gateway.with_raw_dispatches(
+            recorder.sender(),
+            hooks_config.required_event_types() | EventTypeFlags::READY,
+        );
+    }
+    tokio::spawn(report_shard_status(gateway.status()));
     
     // Create hook executor
     let mut executor_config = config.clone();
     executor_config.prompts_dir = prompts_dir;
-    let executor = Arc::new(RwLock::new(HookExecutor::new(executor_config)));
+    let executor = Arc::new(
+        HookExecutor::new(executor_config, sink).with_settings(hooks_config.settings.clone()),
+    );
     
-    // Shared compiled hooks
-    let hooks = Arc::new(RwLock::new(compiled_hooks));
+    // Shared compiled hooks; a reload swaps in a whole new set, so events
+    // already being handled keep the set they started with
+    let hooks = Arc::new(RwLock::new(Arc::new(HookDispatcher::new(compiled_hooks))));
+    let reloader = HooksReloader::new(hooks_path.clone(), hooks_config.clone());
+    tokio::spawn(watch_hooks(
+        reloader,
+        Arc::clone(&hooks),
+        Arc::clone(&executor),
+        reload_interval,
+        hooks_config.required_event_types(),
+        intents,
+    ));
     
+    // Filters on roles, categories and permissions look up what events
+    // don't carry
+    let guilds = Arc::new(GuildCache::new(DiscordClient::with_base_url(
+        config.discord_token.clone(),
+        config.api_base.clone(),
+    )));
+
     // Start listening
-    gateway.listen(move |event: MessageCreate| {
+    let listened = gateway.listen(move |event: Event| {
         let hooks = Arc::clone(&hooks);
         let executor = Arc::clone(&executor);
+        let guilds = Arc::clone(&guilds);
+
+        if let Event::Ready(ready) = &event {
+            guilds.set_bot_user(ready.user.id.to_string());
+        }
+
+        let event = IncomingEvent::from_gateway(event);
         
-        tokio::spawn(async move {
-            // Get current hooks
-            let hooks = hooks.read().await;
+        // The gateway tracks this task so shutdown can wait for it
+        async move {
+            let Some(event) = event else {
+                return;
+            };
             
-            // Check each hook
-            for hook in hooks.iter() {
-                if should_trigger(hook, &event) {
-                    if verbose {
-                        println!("Triggering hook: {}", hook.id);
+            let hooks = Arc::clone(&*hooks.read().await);
+            run_hooks(&hooks, &executor, &event, Some(&guilds), verbose).await;
+        }
+    }).await;
+
+    if let Some(recorder) = recorder {
+        if let Err(e) = recorder.finish().await {
+            eprintln!("Warning: Failed to record events: {}", e);
+        }
+    }
+    listened?;
+    
+    println!("Stopped");
+    Ok(())
+}
+
+/// Load the hooks file and compile its enabled hooks
+///
+/// As with a reload (see [`HooksReloader::reload`]), every enabled hook
+/// must compile: a hook with a broken trigger stops the listener from
+/// starting rather than silently never firing.
+///
+/// # Errors
+///
+/// Returns a configuration error if the file is missing or invalid, if any
+/// enabled hook fails to compile, or if no hook is enabled
+pub fn load_hooks(
+    hooks_path: &Path,
+    verbose: bool,
+) -> Result<(HooksConfig, Vec<CompiledHookConfig>)> {
+    if !hooks_path.exists() {
+        return Err(DiscliError::Config(format!(
+            "Hooks file not found: {} (use --hooks-file or create hooks.yaml)",
+            hooks_path.display()
+        )));
+    }
+    
+    let hooks_config = HooksConfig::load(hooks_path)?;
+    
+    if verbose {
+        println!("Loaded {} hooks from {}", hooks_config.hooks.len(), hooks_path.display());
+    }
+    
+    // Compile hooks
+    let compiled_hooks = hooks_config.compile_enabled()?;
+    if verbose {
+        for compiled in &compiled_hooks {
+            println!(
+                "Compiled hook: {} on {} ({:?})",
+                compiled.id, compiled.event, compiled.trigger
+            );
+        }
+    }
+    
+    if compiled_hooks.is_empty() {
+        return Err(DiscliError::Config("No enabled hooks".into()));
+    }
+
+    Ok((hooks_config, compiled_hooks))
+}
+
+/// Run every hook that triggers on `event`, in priority order
+///
+/// Only the dispatcher's candidates for the event are checked, and checks
+/// stop at a hook with `continue: false`. Returns how many hooks ran.
+/// Hooks of one event run one after another, so a hook waiting in its
+/// `on_busy` queue delays the hooks after it; other events don't wait.
+/// Failures are reported on stderr and don't stop later hooks. Without
+/// `guilds`, filters see only what the event carries.
+pub async fn run_hooks(
+    hooks: &HookDispatcher,
+    executor: &HookExecutor,
+    event: &IncomingEvent,
+    guilds: Option<&GuildCache>,
+    verbose: bool,
+) -> usize {
+    let candidates = hooks.candidates(event);
+    let needs = ContextNeeds::for_event(candidates.iter().copied(), event);
+    let context = match guilds {
+        Some(guilds) if needs.any() => guilds.resolve(event, needs).await,
+        _ => EventContext::default(),
+    };
+    run_hooks_in(hooks, executor, event, &context, verbose).await
+}
+
+/// Run the hooks an event triggers in an already resolved context
+///
+/// Returns how many hooks were triggered.
+pub async fn run_hooks_in(
+    hooks: &HookDispatcher,
+    executor: &HookExecutor,
+    event: &IncomingEvent,
+    context: &EventContext,
+    verbose: bool,
+) -> usize {
+    let candidates = hooks.candidates(event);
+    let mut triggered = 0;
+    for hook in select(&candidates, event, context) {
+        triggered += 1;
+        if verbose {
+            println!("Triggering hook: {}", hook.id);
+        }
+        
+        match executor.execute_in(hook, event, context).await {
+            Ok(result) => {
+                if verbose {
+                    if let Some(response) = result.response {
+                        println!("Hook {} executed: {}", hook.id, response);
                     }
-                    
-                    let mut executor = executor.write().await;
-                    match executor.execute(hook, &event).await {
-                        Ok(result) => {
-                            if verbose {
-                                if let Some(response) = result.response {
-                                    println!("Hook {} executed: {}", hook.id, response);
-                                }
-                                if let Some(error) = result.error {
-                                    eprintln!("Hook {} error: {}", hook.id, error);
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Hook {} execution failed: {}", hook.id, e);
-                        }
+                    if let Some(error) = result.error {
+                        eprintln!("Hook {} error: {}", hook.id, error);
                     }
                 }
             }
-        });
-    }).await?;
-    
-    Ok(())
+            Err(e) => {
+                if executor.error_strategy() != ErrorStrategy::Ignore {
+                    eprintln!("Hook {} execution failed: {}", hook.id, e);
+                }
+            }
+        }
+    }
+    triggered
+}
+
+/// Reload the hooks file when it changes on disk or SIGHUP arrives
+///
+/// A new hook set replaces the running one only if the whole file is
+/// valid, and brings its settings with it. The gateway keeps the event
+/// types and intents it started with, so hooks that need more are reported
+/// as needing a restart.
+async fn watch_hooks(
+    mut reloader: HooksReloader,
+    hooks: Arc<RwLock<Arc<HookDispatcher>>>,
+    executor: Arc<HookExecutor>,
+    interval: Option<Duration>,
+    event_types: EventTypeFlags,
+    intents: Intents,
+) {
+    let mut ticker = interval.map(|interval| {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        ticker
+    });
+    let mut hangup = Hangup::new();
+
+    loop {
+        let reason = tokio::select! {
+            _ = next_tick(&mut ticker) => {
+                if !reloader.changed_on_disk() {
+                    continue;
+                }
+                "file changed"
+            }
+            _ = hangup.recv() => "SIGHUP",
+        };
+
+        match reload_hooks(&mut reloader, &hooks, &executor).await {
+            Ok((config, summary)) => {
+                println!("Reloaded {} ({}): {}", reloader.path().display(), reason, summary);
+                if !event_types.contains(config.required_event_types())
+                    || !intents.contains(config.required_intents())
+                {
+                    eprintln!(
+                        "Warning: Some hooks need gateway events or intents the listener \
+                         did not start with; restart discli listen to enable them"
+                    );
+                }
+            }
+            Err(e) => eprintln!(
+                "Not reloading {} ({}): {}; keeping the previous hooks",
+                reloader.path().display(),
+                reason,
+                e
+            ),
+        }
+    }
+}
+
+/// Reload the hooks file and swap in its hooks and settings
+///
+/// Returns the new configuration and what changed.
+///
+/// # Errors
+///
+/// Returns the reload error if the file isn't valid; the running hooks
+/// are left as they were
+async fn reload_hooks(
+    reloader: &mut HooksReloader,
+    hooks: &RwLock<Arc<HookDispatcher>>,
+    executor: &HookExecutor,
+) -> Result<(HooksConfig, ReloadSummary)> {
+    let Reload {
+        config,
+        hooks: compiled,
+        summary,
+    } = reloader.reload()?;
+    executor.retain_hooks(&compiled);
+    *hooks.write().await = Arc::new(HookDispatcher::new(compiled));
+    executor.set_settings(config.settings.clone());
+    Ok((config, summary))
+}
+
+/// Wait for the next tick, or forever without a ticker
+async fn next_tick(ticker: &mut Option<Interval>) {
+    match ticker {
+        Some(ticker) => {
+            ticker.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// SIGHUP, where the platform has it
+struct Hangup {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl Hangup {
+    fn new() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            let signal = signal(SignalKind::hangup())
+                .map_err(|e| eprintln!("Warning: Cannot reload hooks on SIGHUP: {}", e))
+                .ok();
+            Self { signal }
+        }
+        #[cfg(not(unix))]
+        Self {}
+    }
+
+    /// Wait for the next SIGHUP
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = self.signal.as_mut() {
+            if signal.recv().await.is_some() {
+                return;
+            }
+        }
+        std::future::pending().await
+    }
+}
+
+/// Print a line whenever a shard changes state
+async fn report_shard_status(mut status: watch::Receiver<ShardStatuses>) {
+    let mut last = ShardStatuses::new();
+    loop {
+        let current = status.borrow_and_update().clone();
+        for (number, shard_status) in &current {
+            if last.get(number) != Some(shard_status) {
+                println!("Shard {}: {}", number, shard_status);
+            }
+        }
+        last = current;
+
+        if status.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use discli::sink::MemorySink;
+    use tempfile::TempDir;
+
+    const HOOK: &str = r#"
+version: "1.0"
+hooks:
+  - id: ID
+    channels: ["10"]
+    trigger: { type: prefix, prefix: "!ID" }
+    prompt_file: p.txt
+    action: { type: reply }
+"#;
+
+    async fn current_ids(hooks: &RwLock<Arc<HookDispatcher>>) -> Vec<String> {
+        hooks.read().await.hooks().iter().map(|h| h.id.clone()).collect()
+    }
+
+    #[test]
+    fn test_load_hooks_rejects_hook_that_fails_to_compile() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hooks.yaml");
+        let broken = r#"
+  - id: broken
+    channels: ["10"]
+    trigger: { type: regex, pattern: "(" }
+    prompt_file: p.txt
+    action: { type: reply }
+"#;
+        std::fs::write(&path, format!("{}{}", HOOK.replace("ID", "fine"), broken)).unwrap();
+
+        let Err(err) = load_hooks(&path, false) else {
+            panic!("hooks file with a broken hook loaded");
+        };
+        assert!(err.to_string().contains("Failed to compile hook broken"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_reload_hooks_swaps_valid_edits_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hooks.yaml");
+        std::fs::write(&path, HOOK.replace("ID", "first")).unwrap();
+
+        let (config, compiled) = load_hooks(&path, false).unwrap();
+        let hooks = RwLock::new(Arc::new(HookDispatcher::new(compiled)));
+        let executor = HookExecutor::with_prompts_dir(
+            temp_dir.path().to_path_buf(),
+            Arc::new(MemorySink::new()),
+        );
+        let mut reloader = HooksReloader::new(path.clone(), config);
+
+        std::fs::write(&path, HOOK.replace("ID", "second")).unwrap();
+        assert!(reloader.changed_on_disk());
+        let (_, summary) = reload_hooks(&mut reloader, &hooks, &executor).await.unwrap();
+        assert_eq!(summary.to_string(), "1 added (second), 1 removed (first), 0 unchanged");
+        assert_eq!(current_ids(&hooks).await, vec!["second"]);
+
+        // A broken edit is noticed and rejected, and the last good hooks stay
+        std::fs::write(&path, "version: \"1.0\"\nhooks: [").unwrap();
+        assert!(reloader.changed_on_disk());
+        let Err(err) = reload_hooks(&mut reloader, &hooks, &executor).await else {
+            panic!("a broken hooks file should not reload");
+        };
+        assert!(matches!(err, DiscliError::Config(_)), "{}", err);
+        assert!(!reloader.changed_on_disk());
+        assert_eq!(current_ids(&hooks).await, vec!["second"]);
+    }
 }
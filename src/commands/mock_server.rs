@@ -0,0 +1,62 @@
+//! Mock server command implementation - runs a local Discord API stand-in
+
+use discli::error::Result;
+use discli::mock::{MockServer, MockServerConfig};
+use tracing_subscriber::EnvFilter;
+
+/// Execute the mock-server command
+///
+/// Serves the mock Discord API until Ctrl+C is pressed.
+///
+/// # Arguments
+///
+/// * `listen` - Address to bind, e.g. `127.0.0.1:8787`
+/// * `rate_limit_every` - Answer every Nth API request with a 429
+/// * `fail_every` - Answer every Nth API request with `fail_status`
+/// * `fail_status` - Status code for injected failures
+pub async fn execute(
+    listen: String,
+    rate_limit_every: Option<u64>,
+    fail_every: Option<u64>,
+    fail_status: u16,
+) -> Result<()> {
+    // Request logs go to stderr; RUST_LOG overrides the default level
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("discli=info")),
+        )
+        .with_writer(std::io::stderr)
+        .try_init();
+
+    let config = MockServerConfig {
+        rate_limit_every,
+        fail_every,
+        fail_status,
+        ..Default::default()
+    };
+    let server = MockServer::start(&listen, config).await?;
+
+    println!(
+        "Mock Discord API listening on http://{}",
+        server.local_addr()
+    );
+    println!("  export DISCORD_API_BASE={}", server.base_url());
+    println!(
+        "  Posted messages: http://{}/_mock/messages",
+        server.local_addr()
+    );
+    if let Some(n) = rate_limit_every {
+        println!("  Rate limiting every {} request(s)", n);
+    }
+    if let Some(n) = fail_every {
+        println!("  Failing every {} request(s) with HTTP {}", n, fail_status);
+    }
+    println!("Press Ctrl+C to stop");
+
+    tokio::signal::ctrl_c().await?;
+    println!(
+        "\nStopping mock server ({} message(s) received)",
+        server.messages().len()
+    );
+    Ok(())
+}
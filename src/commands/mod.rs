@@ -1,8 +1,10 @@
 //! Command implementations
 
+pub mod doctor;
 pub mod embed;
+pub mod hooks;
 pub mod image;
 pub mod listen;
+pub mod mock_server;
+pub mod replay;
 pub mod send;
-
-pub use listen::execute as listen;
@@ -0,0 +1,192 @@
+//! Replay command implementation - runs hooks on recorded gateway events
+
+use crate::commands::listen::{load_hooks, run_hooks_in};
+use discli::config::Config;
+use discli::error::{DiscliError, Result};
+use discli::hooks::context::{BotIdentity, EventContext};
+use discli::hooks::dispatch::HookDispatcher;
+use discli::hooks::event::IncomingEvent;
+use discli::hooks::executor::HookExecutor;
+use discli::hooks::recording::{read_recording, Recording};
+use discli::sink::MessageSink;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::time::Instant;
+
+/// Options for the replay command
+pub struct ReplayOptions {
+    /// Hooks file override
+    pub hooks_file: Option<PathBuf>,
+    /// Prompts directory override
+    pub prompts_dir: Option<PathBuf>,
+    /// Wait between events as long as the recording did
+    pub realtime: bool,
+    /// Speed-up factor for real-time pacing
+    pub speed: f64,
+    /// Print hook activity
+    pub verbose: bool,
+}
+
+/// What a replay knows about the bot and its threads, from the recording
+///
+/// There is no server cache to ask, so the context holds only the bot's
+/// user (from the recorded READY) and the owners of threads created
+/// during the recording.
+#[derive(Debug, Default)]
+struct ReplayContext {
+    bot: Option<BotIdentity>,
+    thread_owners: HashMap<String, String>,
+}
+
+impl ReplayContext {
+    fn new(bot_user: Option<String>) -> Self {
+        Self {
+            bot: bot_user.map(|user_id| BotIdentity {
+                user_id,
+                role_id: None,
+            }),
+            thread_owners: HashMap::new(),
+        }
+    }
+
+    /// The context for the next recorded event
+    fn next(&mut self, event: &IncomingEvent) -> EventContext {
+        if let IncomingEvent::ThreadCreate(thread) = event {
+            if let Some(owner_id) = thread.owner_id {
+                self.thread_owners.insert(thread.id.to_string(), owner_id.to_string());
+            }
+        }
+        let thread_owner = event
+            .channel_id()
+            .and_then(|channel_id| self.thread_owners.get(&channel_id.to_string()))
+            .cloned();
+        EventContext {
+            bot: self.bot.clone(),
+            thread_owner,
+            ..EventContext::default()
+        }
+    }
+}
+
+/// Execute the replay command - feeds a recording through the hooks
+///
+/// Events are processed one at a time, in recorded order, so a replay of
+/// the same recording always runs the same hooks in the same order.
+pub async fn execute(
+    config: &Config,
+    sink: Arc<dyn MessageSink>,
+    recording: &Path,
+    options: ReplayOptions,
+) -> Result<()> {
+    let ReplayOptions {
+        hooks_file,
+        prompts_dir,
+        realtime,
+        speed,
+        verbose,
+    } = options;
+
+    if !(speed.is_finite() && speed > 0.0) {
+        return Err(DiscliError::Validation(format!(
+            "--speed must be a positive number, got {}",
+            speed
+        )));
+    }
+
+    let hooks_path = hooks_file.unwrap_or_else(|| config.hooks_file.clone());
+    let (hooks_config, hooks) = load_hooks(&hooks_path, verbose)?;
+    let hooks = HookDispatcher::new(hooks);
+    let Recording { bot_user, events } = read_recording(recording)?;
+
+    if verbose {
+        println!("Replaying {} events from {}", events.len(), recording.display());
+    }
+
+    let mut executor_config = config.clone();
+    executor_config.prompts_dir = prompts_dir.unwrap_or_else(|| config.prompts_dir.clone());
+    let executor = HookExecutor::new(executor_config, sink).with_settings(hooks_config.settings);
+
+    let mut context = ReplayContext::new(bot_user);
+    let started = Instant::now();
+    let mut triggered = 0;
+    for recorded in &events {
+        if realtime {
+            tokio::time::sleep_until(started + recorded.offset.div_f64(speed)).await;
+        }
+        let event_context = context.next(&recorded.event);
+        triggered += run_hooks_in(&hooks, &executor, &recorded.event, &event_context, verbose).await;
+    }
+
+    println!("Replayed {} events, {} hooks triggered", events.len(), triggered);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use discli::sink::MemorySink;
+    use tempfile::TempDir;
+
+    const HOOKS: &str = r#"
+version: "1.0"
+hooks:
+  - id: mentioned
+    channels: ["10"]
+    trigger: { type: mention }
+    prompt_file: p.txt
+    action: { type: reply }
+    processing: { processor_type: command, cmd: ["cat"] }
+"#;
+
+    fn message(content: &str, mentions: &str) -> String {
+        format!(
+            r#"{{"offset_ms":0,"t":"MESSAGE_CREATE","d":{{"id":"1000","channel_id":"10","author":{{"id":"2000","username":"tester","discriminator":"0"}},"content":"{}","timestamp":"2024-01-01T00:00:00.000000+00:00","edited_timestamp":null,"tts":false,"mention_everyone":false,"mentions":[{}],"mention_roles":[],"attachments":[],"embeds":[],"pinned":false,"type":0}}}}"#,
+            content, mentions
+        )
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_replay_triggers_mention_hooks() {
+        let temp_dir = TempDir::new().unwrap();
+        let hooks_file = temp_dir.path().join("hooks.yaml");
+        std::fs::write(&hooks_file, HOOKS).unwrap();
+        std::fs::write(temp_dir.path().join("p.txt"), "Echo: {{content}}").unwrap();
+        let bot = r#"{"id":"999","username":"discli","discriminator":"0","avatar":null,"public_flags":0}"#;
+        let recording = temp_dir.path().join("events.jsonl");
+        std::fs::write(
+            &recording,
+            [
+                format!(r#"{{"offset_ms":0,"t":"READY","d":{{"user":{}}}}}"#, bot),
+                message("<@999> hi", bot),
+                message("hi", ""),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let config = Config {
+            discord_token: "token".to_string(),
+            channel_id: "10".to_string(),
+            api_base: "http://127.0.0.1:0".to_string(),
+            hook_enabled: true,
+            hooks_file,
+            prompts_dir: temp_dir.path().to_path_buf(),
+            log_level: "info".to_string(),
+        };
+        let sink = Arc::new(MemorySink::new());
+        let options = ReplayOptions {
+            hooks_file: None,
+            prompts_dir: None,
+            realtime: false,
+            speed: 1.0,
+            verbose: false,
+        };
+        execute(&config, sink.clone(), &recording, options).await.unwrap();
+
+        let records = sink.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].content.as_deref(), Some("Echo: hi"));
+    }
+}
@@ -1,9 +1,9 @@
 //! Send command implementation
 
-use crate::config::Config;
-use crate::discord::DiscordClient;
-use crate::error::Result;
-use crate::message::MessageBuilder;
+use discli::config::Config;
+use discli::error::Result;
+use discli::message::MessageBuilder;
+use discli::sink::MessageSink;
 use std::path::PathBuf;
 
 /// Execute the send command
@@ -11,6 +11,7 @@ use std::path::PathBuf;
 /// # Arguments
 ///
 /// * `config` - Application configuration
+/// * `sink` - Where to send the message
 /// * `content` - Message content to send
 /// * `attach` - List of file paths to attach
 /// * `embed_url` - List of image URLs to embed (future feature)
@@ -21,17 +22,18 @@ use std::path::PathBuf;
 /// `Ok(())` if message was sent successfully
 pub async fn execute(
     config: &Config,
+    sink: &dyn MessageSink,
     content: String,
     attach: Vec<PathBuf>,
     _embed_url: Vec<String>,
     _caption: Option<String>,
 ) -> Result<()> {
     // Validate attachment count (files + URLs)
-    crate::message::validation::validate_attachment_count(attach.len() + _embed_url.len())?;
+    discli::message::validation::validate_attachment_count(attach.len() + _embed_url.len())?;
 
     // Validate content length if present
     if !content.is_empty() {
-        crate::message::validation::validate_content_length(&content)?;
+        discli::message::validation::validate_content_length(&content)?;
     }
 
     // Build message
@@ -54,14 +56,12 @@ pub async fn execute(
     let discord_message = builder.build();
 
     // Send message
-    let client = DiscordClient::new(config.discord_token.clone());
-    client
-        .send_message(&config.channel_id, &discord_message)
+    sink.send_message(&config.channel_id, &discord_message)
         .await?;
 
     // Print success message
     let summary = if attach.is_empty() {
-        format!("text message")
+        "text message".to_string()
     } else {
         format!("message with {} image attachment(s)", attach.len())
     };
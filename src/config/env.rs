@@ -1,5 +1,6 @@
 //! Environment configuration for discli
 
+use crate::discord::client::DEFAULT_API_BASE;
 use crate::error::{DiscliError, Result};
 use std::env;
 use std::path::PathBuf;
@@ -11,6 +12,8 @@ pub struct Config {
     pub discord_token: String,
     /// Discord channel ID to send messages to
     pub channel_id: String,
+    /// Base URL of the Discord REST API (overridable for mock servers)
+    pub api_base: String,
     
     // Hook system configuration
     /// Whether hook system is enabled
@@ -30,6 +33,9 @@ impl Config {
     /// 1. First, it tries to load a `discli.env` file if it exists
     /// 2. Then, it reads `DISCORD_TOKEN` and `DISCORD_CHANNEL_ID` from environment variables
     ///
+    /// `DISCORD_API_BASE` optionally overrides the Discord REST API base URL,
+    /// e.g. to point discli at `discli mock-server`.
+    ///
     /// # Returns
     ///
     /// Returns a `Config` struct containing the loaded configuration
@@ -50,15 +56,33 @@ impl Config {
     /// # }
     /// ```
     pub fn load() -> Result<Self> {
+        Self::from_env(true)
+    }
+
+    /// Load configuration for commands that never contact Discord
+    ///
+    /// Like [`Config::load`], but `DISCORD_TOKEN` and `DISCORD_CHANNEL_ID`
+    /// may be unset, in which case they are left empty. Used by
+    /// `discli replay`.
+    pub fn load_offline() -> Result<Self> {
+        Self::from_env(false)
+    }
+
+    fn from_env(require_credentials: bool) -> Result<Self> {
         // Try to load .env file (ignore errors if file doesn't exist)
         dotenv::from_filename("discli.env").ok();
 
         // Load required environment variables
-        let discord_token = env::var("DISCORD_TOKEN")
-            .map_err(|_| DiscliError::Config("DISCORD_TOKEN not set".into()))?;
+        let required = |name: &str| match env::var(name) {
+            Ok(value) => Ok(value),
+            Err(_) if !require_credentials => Ok(String::new()),
+            Err(_) => Err(DiscliError::Config(format!("{} not set", name))),
+        };
+        let discord_token = required("DISCORD_TOKEN")?;
+        let channel_id = required("DISCORD_CHANNEL_ID")?;
 
-        let channel_id = env::var("DISCORD_CHANNEL_ID")
-            .map_err(|_| DiscliError::Config("DISCORD_CHANNEL_ID not set".into()))?;
+        let api_base = env::var("DISCORD_API_BASE")
+            .unwrap_or_else(|_| DEFAULT_API_BASE.to_string());
 
         // Hook system configuration (with defaults)
         let hook_enabled = env::var("HOOK_ENABLED")
@@ -79,6 +103,7 @@ impl Config {
         Ok(Config {
             discord_token,
             channel_id,
+            api_base,
             hook_enabled,
             hooks_file,
             prompts_dir,
@@ -101,6 +126,7 @@ mod tests {
         let config = result.unwrap();
         assert_eq!(config.discord_token, "test");
         assert_eq!(config.channel_id, "123");
+        assert_eq!(config.api_base, DEFAULT_API_BASE);
         assert!(!config.hook_enabled);
         assert_eq!(config.hooks_file, PathBuf::from("./hooks.yaml"));
         assert_eq!(config.prompts_dir, PathBuf::from("./prompts"));
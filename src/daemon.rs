@@ -0,0 +1,390 @@
+//! Daemon support for `discli listen`: detaching, pidfile and log file
+//!
+//! Detaching forks the process, so it must happen before the tokio runtime
+//! starts; everything else here runs either before the runtime or on it.
+
+use discli::error::{DiscliError, Result};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Whether `discli listen` can detach and run in the background; where it
+/// can't, it runs in the foreground
+pub const BACKGROUND_SUPPORTED: bool = cfg!(unix);
+
+/// Default pidfile for `discli listen`
+pub const DEFAULT_PIDFILE: &str = "discli.pid";
+
+/// Default log file for `discli listen` in background mode
+pub const DEFAULT_LOG_FILE: &str = "discli.log";
+
+/// How long the parent watches the detached listener for startup failures
+const STARTUP_GRACE: Duration = Duration::from_secs(1);
+
+/// Which side of the fork we are on
+pub enum Fork {
+    /// The original process, with the listener's PID
+    Parent(i32),
+    /// The detached listener
+    Child,
+}
+
+/// A pidfile owned by this process, removed again on drop
+pub struct Pidfile {
+    path: PathBuf,
+}
+
+impl Pidfile {
+    /// Write this process's PID to `path`
+    ///
+    /// A pidfile left behind by a process that is no longer running is
+    /// replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if another listener is running with this pidfile
+    /// or the file cannot be written
+    pub fn acquire(path: &Path) -> Result<Self> {
+        ensure_not_running(path)?;
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|e| {
+                DiscliError::Daemon(format!("Cannot create pidfile {}: {}", path.display(), e))
+            })?;
+        writeln!(file, "{}", std::process::id())?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for Pidfile {
+    fn drop(&mut self) {
+        // Only remove the file if it still names us
+        if read_pid(&self.path).ok().flatten() == Some(std::process::id() as i32) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Fail if a live process owns the pidfile; remove it if it is stale
+///
+/// # Errors
+///
+/// Returns an error if the listener named in the pidfile is still running
+pub fn ensure_not_running(path: &Path) -> Result<()> {
+    match read_pid(path)? {
+        Some(pid) if is_running(pid) => Err(DiscliError::Daemon(format!(
+            "discli listen is already running (pid {}, pidfile {})",
+            pid,
+            path.display()
+        ))),
+        Some(pid) => {
+            eprintln!(
+                "Removing stale pidfile {} (pid {} is not running)",
+                path.display(),
+                pid
+            );
+            fs::remove_file(path)?;
+            Ok(())
+        }
+        None if path.exists() => {
+            eprintln!("Removing unreadable pidfile {}", path.display());
+            fs::remove_file(path)?;
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Read the PID stored in a pidfile
+///
+/// Returns `Ok(None)` if the file does not exist or holds no valid PID.
+pub fn read_pid(path: &Path) -> Result<Option<i32>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content.trim().parse().ok().filter(|pid| *pid > 0)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Print whether the listener named in the pidfile is running
+///
+/// # Errors
+///
+/// Returns an error if it is not running, so the exit code reflects it
+pub fn status(pidfile: &Path) -> Result<()> {
+    match read_pid(pidfile)? {
+        Some(pid) if is_running(pid) => {
+            println!("discli listen is running (pid {})", pid);
+            Ok(())
+        }
+        Some(pid) => Err(DiscliError::Daemon(format!(
+            "discli listen is not running (stale pidfile {} names pid {})",
+            pidfile.display(),
+            pid
+        ))),
+        None => Err(DiscliError::Daemon(format!(
+            "discli listen is not running (no pidfile at {})",
+            pidfile.display()
+        ))),
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use super::*;
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    /// Whether a process with this PID exists
+    pub fn is_running(pid: i32) -> bool {
+        // Signal 0 only checks whether the process can be signalled;
+        // EPERM means it exists but belongs to someone else
+        let alive = unsafe { libc::kill(pid, 0) } == 0
+            || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+        alive && !is_other_program(pid)
+    }
+
+    /// Whether the PID was reused by something that isn't discli
+    #[cfg(target_os = "linux")]
+    fn is_other_program(pid: i32) -> bool {
+        match fs::read(format!("/proc/{}/cmdline", pid)) {
+            Ok(cmdline) => !String::from_utf8_lossy(&cmdline).contains("discli"),
+            Err(_) => false,
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn is_other_program(_pid: i32) -> bool {
+        false
+    }
+
+    /// Fork and detach from the terminal
+    ///
+    /// The child starts a new session and has stdin pointed at `/dev/null`
+    /// and stdout/stderr at `log_file`. The parent waits briefly so that a
+    /// listener failing at startup is reported instead of silently exiting.
+    ///
+    /// Must be called before any threads (such as the tokio runtime) exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if forking fails, the log file cannot be opened, or
+    /// the listener exits during startup
+    pub fn daemonize(log_file: &Path) -> Result<Fork> {
+        // Open the log file up front so errors surface in the terminal
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_file)
+            .map_err(|e| {
+                DiscliError::Daemon(format!("Cannot open log file {}: {}", log_file.display(), e))
+            })?;
+
+        match unsafe { libc::fork() } {
+            -1 => Err(std::io::Error::last_os_error().into()),
+            0 => {
+                if unsafe { libc::setsid() } == -1 {
+                    return Err(std::io::Error::last_os_error().into());
+                }
+                redirect_stdin()?;
+                redirect_output(log_file)?;
+                Ok(Fork::Child)
+            }
+            pid => {
+                let deadline = Instant::now() + STARTUP_GRACE;
+                while Instant::now() < deadline {
+                    let mut status = 0;
+                    if unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) } == pid {
+                        return Err(DiscliError::Daemon(format!(
+                            "discli listen exited during startup; see {}",
+                            log_file.display()
+                        )));
+                    }
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                Ok(Fork::Parent(pid))
+            }
+        }
+    }
+
+    /// Point stdout and stderr at the end of `log_file`
+    ///
+    /// Called again on SIGHUP so that rotated logs are picked up.
+    pub fn redirect_output(log_file: &Path) -> Result<()> {
+        let path = CString::new(log_file.as_os_str().as_bytes())
+            .map_err(|_| DiscliError::Daemon("Log file path contains a NUL byte".into()))?;
+        let fd = unsafe {
+            libc::open(
+                path.as_ptr(),
+                libc::O_WRONLY | libc::O_CREAT | libc::O_APPEND | libc::O_CLOEXEC,
+                0o644,
+            )
+        };
+        if fd == -1 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let _ = std::io::stdout().flush();
+        let _ = std::io::stderr().flush();
+        let ok = unsafe { libc::dup2(fd, 1) != -1 && libc::dup2(fd, 2) != -1 };
+        let err = std::io::Error::last_os_error();
+        unsafe { libc::close(fd) };
+        if ok {
+            Ok(())
+        } else {
+            Err(err.into())
+        }
+    }
+
+    fn redirect_stdin() -> Result<()> {
+        let fd = unsafe { libc::open(c"/dev/null".as_ptr(), libc::O_RDONLY) };
+        if fd == -1 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        unsafe {
+            libc::dup2(fd, 0);
+            libc::close(fd);
+        }
+        Ok(())
+    }
+
+    /// Reopen the log file whenever SIGHUP arrives (for logrotate)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signal handler cannot be installed
+    pub fn reopen_log_on_sighup(log_file: PathBuf) -> Result<()> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = signal(SignalKind::hangup())?;
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                match redirect_output(&log_file) {
+                    Ok(()) => eprintln!("Received SIGHUP, reopened {}", log_file.display()),
+                    Err(e) => eprintln!("Received SIGHUP, cannot reopen log file: {}", e),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Send SIGTERM to the listener and wait for it to exit
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no listener is running or it does not exit in time
+    pub fn stop(pidfile: &Path, timeout: Duration) -> Result<()> {
+        let pid = match read_pid(pidfile)? {
+            Some(pid) if is_running(pid) => pid,
+            Some(pid) => {
+                fs::remove_file(pidfile)?;
+                return Err(DiscliError::Daemon(format!(
+                    "discli listen is not running (removed stale pidfile naming pid {})",
+                    pid
+                )));
+            }
+            None => {
+                return Err(DiscliError::Daemon(format!(
+                    "discli listen is not running (no pidfile at {})",
+                    pidfile.display()
+                )))
+            }
+        };
+
+        if unsafe { libc::kill(pid, libc::SIGTERM) } == -1 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        println!("Sent SIGTERM to discli listen (pid {})", pid);
+
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if !is_running(pid) {
+                println!("Stopped");
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        Err(DiscliError::Daemon(format!(
+            "discli listen (pid {}) did not exit within {}s",
+            pid,
+            timeout.as_secs()
+        )))
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use super::*;
+
+    const UNSUPPORTED: &str = "Background mode is only supported on Unix; use --foreground";
+
+    pub fn is_running(_pid: i32) -> bool {
+        false
+    }
+
+    pub fn daemonize(_log_file: &Path) -> Result<Fork> {
+        Err(DiscliError::Daemon(UNSUPPORTED.into()))
+    }
+
+    pub fn redirect_output(_log_file: &Path) -> Result<()> {
+        Err(DiscliError::Daemon("--log-file is only supported on Unix".into()))
+    }
+
+    pub fn reopen_log_on_sighup(_log_file: PathBuf) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn stop(_pidfile: &Path, _timeout: Duration) -> Result<()> {
+        Err(DiscliError::Daemon(UNSUPPORTED.into()))
+    }
+}
+
+pub use imp::{daemonize, is_running, redirect_output, reopen_log_on_sighup, stop};
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pidfile_written_and_removed() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("discli.pid");
+
+        let pidfile = Pidfile::acquire(&path).unwrap();
+        assert_eq!(read_pid(&path).unwrap(), Some(std::process::id() as i32));
+        assert!(status(&path).is_ok());
+
+        drop(pidfile);
+        assert!(!path.exists());
+        assert!(status(&path).is_err());
+    }
+
+    #[test]
+    fn test_running_pid_blocks_acquire() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("discli.pid");
+        // The test binary's name contains "discli", so it counts as a listener
+        fs::write(&path, format!("{}\n", std::process::id())).unwrap();
+
+        let err = Pidfile::acquire(&path).err().unwrap();
+        assert!(err.to_string().contains("already running"));
+    }
+
+    #[test]
+    fn test_stale_pidfile_replaced() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("discli.pid");
+        // PIDs are capped well below i32::MAX on Linux
+        fs::write(&path, format!("{}\n", i32::MAX)).unwrap();
+
+        let _pidfile = Pidfile::acquire(&path).unwrap();
+        assert_eq!(read_pid(&path).unwrap(), Some(std::process::id() as i32));
+    }
+}
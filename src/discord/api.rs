@@ -1,8 +1,10 @@
 //! Discord API request handlers
 
-use crate::discord::types::{Embed, FileAttachment};
+use crate::discord::error::DiscordApiError;
+use crate::discord::types::{DiscordMessage, Embed, FileAttachment};
 use crate::error::{DiscliError, Result};
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde_json::json;
 use tokio::io::AsyncReadExt;
 
@@ -71,6 +73,55 @@ pub async fn send_multipart_message(
     content: &Option<String>,
     attachments: &[FileAttachment],
 ) -> Result<()> {
+    let form = build_multipart_form(content, attachments).await?;
+
+    // Send request
+    let response = client
+        .post(url)
+        .header("Authorization", format!("Bot {}", token))
+        .multipart(form)
+        .send()
+        .await?;
+
+    check_response(response).await
+}
+
+/// Execute a Discord webhook
+///
+/// Webhook URLs carry their own credentials, so no bot token is sent.
+///
+/// # Arguments
+///
+/// * `client` - HTTP client to use for the request
+/// * `url` - Webhook URL (`https://discord.com/api/webhooks/{id}/{token}`)
+/// * `message` - The message to post
+///
+/// # Errors
+///
+/// Returns an error if reading attachments fails, the HTTP request fails
+/// or Discord returns an error
+pub async fn execute_webhook(client: &Client, url: &str, message: &DiscordMessage) -> Result<()> {
+    let request = match message {
+        DiscordMessage::Simple { content } => client.post(url).json(&json!({ "content": content })),
+        DiscordMessage::WithAttachments {
+            content,
+            attachments,
+        } => client
+            .post(url)
+            .multipart(build_multipart_form(content, attachments).await?),
+        DiscordMessage::WithEmbeds { content, embeds } => {
+            client.post(url).json(&embed_payload(content, embeds)?)
+        }
+    };
+
+    check_response(request.send().await?).await
+}
+
+/// Build the multipart form for a message with attachments
+async fn build_multipart_form(
+    content: &Option<String>,
+    attachments: &[FileAttachment],
+) -> Result<reqwest::multipart::Form> {
     let mut form = reqwest::multipart::Form::new();
 
     // Add payload_json if we have attachments (or content to send)
@@ -135,11 +186,86 @@ pub async fn send_multipart_message(
         form = form.part(key, part);
     }
 
-    // Send request
+    Ok(form)
+}
+
+/// Fetch a JSON resource from Discord
+///
+/// # Arguments
+///
+/// * `client` - HTTP client to use for the request
+/// * `url` - Full API URL of the resource
+/// * `token` - Discord bot token
+///
+/// # Returns
+///
+/// The decoded response body
+///
+/// # Errors
+///
+/// Returns an error if the HTTP request fails, Discord returns an error,
+/// or the body cannot be decoded into `T`
+pub async fn get_json<T: DeserializeOwned>(client: &Client, url: &str, token: &str) -> Result<T> {
+    let response = client
+        .get(url)
+        .header("Authorization", format!("Bot {}", token))
+        .send()
+        .await?;
+
+    let response = ensure_success(response).await?;
+    Ok(response.json::<T>().await?)
+}
+
+/// Create a JSON resource on Discord and decode the response
+///
+/// # Arguments
+///
+/// * `client` - HTTP client to use for the request
+/// * `url` - Full API URL to post to
+/// * `token` - Discord bot token
+/// * `body` - JSON request body
+///
+/// # Returns
+///
+/// The decoded response body
+///
+/// # Errors
+///
+/// Returns an error if the HTTP request fails, Discord returns an error,
+/// or the body cannot be decoded into `T`
+pub async fn post_json<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    token: &str,
+    body: &serde_json::Value,
+) -> Result<T> {
     let response = client
         .post(url)
         .header("Authorization", format!("Bot {}", token))
-        .multipart(form)
+        .json(body)
+        .send()
+        .await?;
+
+    let response = ensure_success(response).await?;
+    Ok(response.json::<T>().await?)
+}
+
+/// Create or replace a resource on Discord that takes no request body
+///
+/// # Arguments
+///
+/// * `client` - HTTP client to use for the request
+/// * `url` - Full API URL to put to
+/// * `token` - Discord bot token
+///
+/// # Errors
+///
+/// Returns an error if the HTTP request fails or Discord returns an error
+pub async fn put_empty(client: &Client, url: &str, token: &str) -> Result<()> {
+    let response = client
+        .put(url)
+        .header("Authorization", format!("Bot {}", token))
+        .header("Content-Length", "0")
         .send()
         .await?;
 
@@ -148,17 +274,22 @@ pub async fn send_multipart_message(
 
 /// Check HTTP response and handle errors
 async fn check_response(response: reqwest::Response) -> Result<()> {
+    ensure_success(response).await.map(|_| ())
+}
+
+/// Turn a non-success HTTP response into a `DiscliError`
+async fn ensure_success(response: reqwest::Response) -> Result<reqwest::Response> {
     let status = response.status();
 
     if !status.is_success() {
         let error_text = response.text().await?;
-        return Err(DiscliError::DiscordApi(format!(
-            "Discord API returned error status {}: {}",
-            status, error_text
+        return Err(DiscliError::DiscordApi(DiscordApiError::from_response(
+            status.as_u16(),
+            &error_text,
         )));
     }
 
-    Ok(())
+    Ok(response)
 }
 
 /// Send a message with rich embeds to Discord
@@ -185,6 +316,21 @@ pub async fn send_embed_message(
     content: &Option<String>,
     embeds: &[Embed],
 ) -> Result<()> {
+    let payload = embed_payload(content, embeds)?;
+
+    let response = client
+        .post(url)
+        .header("Authorization", format!("Bot {}", token))
+        .header("Content-Type", "application/json")
+        .json(&payload)
+        .send()
+        .await?;
+
+    check_response(response).await
+}
+
+/// Build the JSON payload for a message with embeds
+fn embed_payload(content: &Option<String>, embeds: &[Embed]) -> Result<serde_json::Value> {
     // Validate embed count
     if embeds.len() > 10 {
         return Err(DiscliError::Validation(format!(
@@ -203,13 +349,5 @@ pub async fn send_embed_message(
         payload["content"] = json!(text);
     }
 
-    let response = client
-        .post(url)
-        .header("Authorization", format!("Bot {}", token))
-        .header("Content-Type", "application/json")
-        .json(&payload)
-        .send()
-        .await?;
-
-    check_response(response).await
+    Ok(payload)
 }
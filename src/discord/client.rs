@@ -1,10 +1,18 @@
 //! Discord API client
 
-use crate::discord::api::{send_embed_message, send_json_message, send_multipart_message};
-use crate::discord::types::DiscordMessage;
+use crate::discord::api::{
+    execute_webhook, get_json, post_json, put_empty, send_embed_message, send_json_message,
+    send_multipart_message,
+};
+use crate::discord::types::{
+    ChannelInfo, CurrentUser, DiscordMessage, GatewayBotInfo, GuildInfo, GuildMemberInfo,
+};
 use crate::error::Result;
 use reqwest::Client;
 
+/// Default base URL for the Discord REST API
+pub const DEFAULT_API_BASE: &str = "https://discord.com/api/v10";
+
 /// Discord API client for sending messages
 pub struct DiscordClient {
     /// HTTP client for making API requests
@@ -26,11 +34,23 @@ impl DiscordClient {
     ///
     /// A new `DiscordClient` instance
     pub fn new(token: String) -> Self {
+        Self::with_base_url(token, DEFAULT_API_BASE.to_string())
+    }
+
+    /// Create a Discord API client that talks to a custom API base URL
+    ///
+    /// Useful for pointing discli at a local mock of the Discord API.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - Discord bot token
+    /// * `base_url` - API base URL, e.g. `http://127.0.0.1:8787/api/v10`
+    pub fn with_base_url(token: String, base_url: String) -> Self {
         let http_client = Client::new();
         Self {
             http_client,
             token,
-            base_url: "https://discord.com/api/v10".to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
         }
     }
 
@@ -73,16 +93,109 @@ impl DiscordClient {
                 content,
                 embeds,
             } => {
-                send_embed_message(&self.http_client, &url, &self.token, &content, &embeds).await
+                send_embed_message(&self.http_client, &url, &self.token, content, embeds).await
+            }
+        }
+    }
+
+    /// Post a message through a Discord webhook URL
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Webhook URL
+    /// * `message` - The message to post
+    pub async fn execute_webhook(&self, url: &str, message: &DiscordMessage) -> Result<()> {
+        execute_webhook(&self.http_client, url, message).await
+    }
+
+    /// Fetch the user the token belongs to (`GET /users/@me`)
+    pub async fn get_current_user(&self) -> Result<CurrentUser> {
+        let url = format!("{}/users/@me", self.base_url);
+        get_json(&self.http_client, &url, &self.token).await
+    }
+
+    /// Fetch a channel (`GET /channels/{channel.id}`)
+    pub async fn get_channel(&self, channel_id: &str) -> Result<ChannelInfo> {
+        let url = format!("{}/channels/{}", self.base_url, channel_id);
+        get_json(&self.http_client, &url, &self.token).await
+    }
+
+    /// Open (or fetch) the DM channel with a user (`POST /users/@me/channels`)
+    pub async fn create_dm(&self, user_id: &str) -> Result<ChannelInfo> {
+        let url = format!("{}/users/@me/channels", self.base_url);
+        let body = serde_json::json!({ "recipient_id": user_id });
+        post_json(&self.http_client, &url, &self.token, &body).await
+    }
+
+    /// Send a direct message to a user
+    ///
+    /// Fails with Discord error 50007 if the user does not accept DMs from
+    /// the bot.
+    pub async fn send_dm(&self, user_id: &str, message: &DiscordMessage) -> Result<()> {
+        let channel = self.create_dm(user_id).await?;
+        self.send_message(&channel.id, message).await
+    }
+
+    /// React to a message as the bot
+    /// (`PUT /channels/{channel.id}/messages/{message.id}/reactions/{emoji}/@me`)
+    ///
+    /// # Arguments
+    ///
+    /// * `emoji` - A Unicode emoji, or `name:id` for a custom emoji
+    pub async fn add_reaction(&self, channel_id: &str, message_id: &str, emoji: &str) -> Result<()> {
+        let url = format!(
+            "{}/channels/{}/messages/{}/reactions/{}/@me",
+            self.base_url,
+            channel_id,
+            message_id,
+            encode_path_segment(emoji)
+        );
+        put_empty(&self.http_client, &url, &self.token).await
+    }
+
+    /// Fetch a guild including its roles (`GET /guilds/{guild.id}`)
+    pub async fn get_guild(&self, guild_id: &str) -> Result<GuildInfo> {
+        let url = format!("{}/guilds/{}", self.base_url, guild_id);
+        get_json(&self.http_client, &url, &self.token).await
+    }
+
+    /// Fetch a guild member (`GET /guilds/{guild.id}/members/{user.id}`)
+    pub async fn get_guild_member(&self, guild_id: &str, user_id: &str) -> Result<GuildMemberInfo> {
+        let url = format!("{}/guilds/{}/members/{}", self.base_url, guild_id, user_id);
+        get_json(&self.http_client, &url, &self.token).await
+    }
+
+    /// Fetch the recommended shard count and session limits (`GET /gateway/bot`)
+    pub async fn get_gateway_bot(&self) -> Result<GatewayBotInfo> {
+        let url = format!("{}/gateway/bot", self.base_url);
+        get_json(&self.http_client, &url, &self.token).await
+    }
+}
+
+/// Percent-encode a URL path segment, keeping unreserved characters and `:`
+fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b':' => {
+                encoded.push(byte as char)
             }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
         }
     }
+    encoded
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_encode_path_segment() {
+        assert_eq!(encode_path_segment("👍"), "%F0%9F%91%8D");
+        assert_eq!(encode_path_segment("party_blob:1234"), "party_blob:1234");
+    }
+
     #[test]
     fn test_client_creation() {
         let token = "test_token".to_string();
@@ -90,4 +203,13 @@ mod tests {
         assert_eq!(client.token, "test_token");
         assert_eq!(client.base_url, "https://discord.com/api/v10");
     }
+
+    #[test]
+    fn test_client_custom_base_url() {
+        let client = DiscordClient::with_base_url(
+            "test_token".to_string(),
+            "http://127.0.0.1:8787/api/v10/".to_string(),
+        );
+        assert_eq!(client.base_url, "http://127.0.0.1:8787/api/v10");
+    }
 }
@@ -0,0 +1,239 @@
+//! Decoding of Discord API error responses
+//!
+//! Discord reports failures as a JSON body with a numeric `code`, a
+//! `message` and, for invalid payloads, a nested `errors` tree keyed by
+//! field path. This module turns that body into a structured error with a
+//! human-readable hint.
+
+use serde_json::Value;
+use std::fmt;
+
+/// JSON error code: Unknown Channel
+pub const UNKNOWN_CHANNEL: u64 = 10003;
+/// JSON error code: Unknown Guild
+pub const UNKNOWN_GUILD: u64 = 10004;
+/// JSON error code: Unknown Message
+pub const UNKNOWN_MESSAGE: u64 = 10008;
+/// JSON error code: Unknown User
+pub const UNKNOWN_USER: u64 = 10013;
+/// JSON error code: Unknown Webhook
+pub const UNKNOWN_WEBHOOK: u64 = 10015;
+/// JSON error code: Unauthorized
+pub const UNAUTHORIZED: u64 = 40001;
+/// JSON error code: Request entity too large
+pub const REQUEST_TOO_LARGE: u64 = 40005;
+/// JSON error code: Missing Access
+pub const MISSING_ACCESS: u64 = 50001;
+/// JSON error code: Cannot send an empty message
+pub const EMPTY_MESSAGE: u64 = 50006;
+/// JSON error code: Cannot send messages to this user
+pub const CANNOT_MESSAGE_USER: u64 = 50007;
+/// JSON error code: Cannot send messages in a non-text channel
+pub const NON_TEXT_CHANNEL: u64 = 50008;
+/// JSON error code: Missing Permissions
+pub const MISSING_PERMISSIONS: u64 = 50013;
+/// JSON error code: Invalid Form Body
+pub const INVALID_FORM_BODY: u64 = 50035;
+
+/// A single field-level error from an Invalid Form Body response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// Path of the offending field, e.g. `embeds[0].title`
+    pub path: String,
+    /// Discord's error code for the field, e.g. `BASE_TYPE_MAX_LENGTH`
+    pub code: String,
+    /// Human-readable description
+    pub message: String,
+}
+
+/// A decoded Discord API error response
+#[derive(Debug, Clone)]
+pub struct DiscordApiError {
+    /// HTTP status code
+    pub status: u16,
+    /// Discord JSON error code, if the body contained one
+    pub code: Option<u64>,
+    /// Error message from Discord (or the raw body if it was not JSON)
+    pub message: String,
+    /// Field errors flattened from the nested `errors` tree
+    pub field_errors: Vec<FieldError>,
+    /// Seconds to wait before retrying (rate limit responses only)
+    pub retry_after: Option<f64>,
+}
+
+impl DiscordApiError {
+    /// Decode an error response from its status code and body
+    ///
+    /// Bodies that are not Discord JSON errors (e.g. an HTML page from a
+    /// proxy) are kept verbatim as the message.
+    pub fn from_response(status: u16, body: &str) -> Self {
+        let json: Option<Value> = serde_json::from_str(body).ok().filter(Value::is_object);
+
+        let Some(json) = json else {
+            return Self {
+                status,
+                code: None,
+                message: body.trim().to_string(),
+                field_errors: Vec::new(),
+                retry_after: None,
+            };
+        };
+
+        let mut field_errors = Vec::new();
+        if let Some(errors) = json.get("errors") {
+            collect_field_errors(errors, "", &mut field_errors);
+        }
+
+        Self {
+            status,
+            code: json.get("code").and_then(Value::as_u64).filter(|code| *code != 0),
+            message: json
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            field_errors,
+            retry_after: json.get("retry_after").and_then(Value::as_f64),
+        }
+    }
+
+    /// A short suggestion on how to fix the error, if one is known
+    pub fn hint(&self) -> Option<&'static str> {
+        let hint = match self.code {
+            Some(UNKNOWN_CHANNEL) => {
+                "Check DISCORD_CHANNEL_ID (or the hook's channel ID); the channel may have been deleted"
+            }
+            Some(UNKNOWN_GUILD) => "The bot is not a member of that server",
+            Some(UNKNOWN_MESSAGE) => "The message was deleted before discli could act on it",
+            Some(UNKNOWN_USER) => "Check the user ID",
+            Some(UNKNOWN_WEBHOOK) => "The webhook was deleted or its URL is wrong",
+            Some(UNAUTHORIZED) => "Check DISCORD_TOKEN; it may have been reset",
+            Some(REQUEST_TOO_LARGE) => "Attachments exceed the upload limit; send fewer or smaller files",
+            Some(MISSING_ACCESS) => {
+                "The bot cannot see this channel; grant it View Channel or invite it to the server (run `discli doctor`)"
+            }
+            Some(EMPTY_MESSAGE) => "Provide message content, an attachment or an embed",
+            Some(CANNOT_MESSAGE_USER) => "The user has DMs disabled or shares no server with the bot",
+            Some(NON_TEXT_CHANNEL) => "The channel is not a text channel",
+            Some(MISSING_PERMISSIONS) => {
+                "The bot lacks a permission required for this action (run `discli doctor`)"
+            }
+            Some(INVALID_FORM_BODY) => "Discord rejected the message payload; fix the fields listed above",
+            _ => match self.status {
+                401 => "Check DISCORD_TOKEN; it may be invalid or revoked",
+                403 => "The bot lacks access to this resource (run `discli doctor`)",
+                404 => "Check the channel ID",
+                429 => "You are being rate limited; wait before sending more messages",
+                500..=599 => "Discord is having problems; try again later",
+                _ => return None,
+            },
+        };
+        Some(hint)
+    }
+}
+
+impl fmt::Display for DiscordApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "status {}", self.status)?;
+        if !self.message.is_empty() {
+            write!(f, ": {}", self.message)?;
+        }
+        if let Some(code) = self.code {
+            write!(f, " (code {})", code)?;
+        }
+        if let Some(retry_after) = self.retry_after {
+            write!(f, ", retry after {:.1}s", retry_after)?;
+        }
+        for field in &self.field_errors {
+            write!(f, "\n  {}: {} ({})", field.path, field.message, field.code)?;
+        }
+        Ok(())
+    }
+}
+
+/// Walk the nested `errors` tree, collecting every `_errors` leaf with its path
+fn collect_field_errors(node: &Value, path: &str, out: &mut Vec<FieldError>) {
+    let Some(map) = node.as_object() else {
+        return;
+    };
+
+    for (key, value) in map {
+        if key == "_errors" {
+            for error in value.as_array().into_iter().flatten() {
+                out.push(FieldError {
+                    path: path.to_string(),
+                    code: error
+                        .get("code")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    message: error
+                        .get("message")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                });
+            }
+            continue;
+        }
+
+        let child = if key.chars().all(|c| c.is_ascii_digit()) {
+            format!("{}[{}]", path, key)
+        } else if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        collect_field_errors(value, &child, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_invalid_form_body() {
+        let body = r#"{
+            "code": 50035,
+            "message": "Invalid Form Body",
+            "errors": {
+                "content": {"_errors": [{"code": "BASE_TYPE_MAX_LENGTH", "message": "Must be 2000 or fewer in length."}]},
+                "embeds": {"0": {"title": {"_errors": [{"code": "BASE_TYPE_MAX_LENGTH", "message": "Must be 256 or fewer in length."}]}}}
+            }
+        }"#;
+
+        let err = DiscordApiError::from_response(400, body);
+
+        assert_eq!(err.code, Some(INVALID_FORM_BODY));
+        assert_eq!(err.message, "Invalid Form Body");
+        let paths: Vec<&str> = err.field_errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["content", "embeds[0].title"]);
+        assert!(err.to_string().contains("embeds[0].title: Must be 256 or fewer in length."));
+    }
+
+    #[test]
+    fn test_decode_missing_access() {
+        let err = DiscordApiError::from_response(403, r#"{"message": "Missing Access", "code": 50001}"#);
+        assert_eq!(err.code, Some(MISSING_ACCESS));
+        assert_eq!(err.to_string(), "status 403: Missing Access (code 50001)");
+        assert!(err.hint().unwrap().contains("View Channel"));
+    }
+
+    #[test]
+    fn test_decode_rate_limit() {
+        let body = r#"{"message": "You are being rate limited.", "retry_after": 1.5, "global": false}"#;
+        let err = DiscordApiError::from_response(429, body);
+        assert_eq!(err.code, None);
+        assert_eq!(err.retry_after, Some(1.5));
+        assert!(err.hint().is_some());
+    }
+
+    #[test]
+    fn test_decode_non_json_body() {
+        let err = DiscordApiError::from_response(502, "<html>Bad Gateway</html>");
+        assert_eq!(err.code, None);
+        assert_eq!(err.message, "<html>Bad Gateway</html>");
+        assert!(err.field_errors.is_empty());
+    }
+}
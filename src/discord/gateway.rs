@@ -1,33 +1,127 @@
 //! Discord Gateway module
 //!
 //! This module provides WebSocket connectivity to the Discord Gateway,
-//! allowing real-time event listening including MESSAGE_CREATE events.
+//! allowing real-time event listening (messages, reactions, members and
+//! more) across one or more shards.
 
-use crate::error::Result;
+use crate::discord::sharding::{ShardPlan, ShardStatus, ShardStatuses};
+use crate::error::{DiscliError, Result};
+use std::future::Future;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::{JoinHandle, JoinSet};
 use tracing::{info, warn};
-use twilight_gateway::{CloseFrame, Event, EventTypeFlags, Intents, Shard, ShardId, StreamExt};
-use twilight_model::gateway::payload::incoming::MessageCreate;
+use twilight_gateway::error::ReceiveMessageErrorType;
+use twilight_gateway::queue::InMemoryQueue;
+use futures_util::StreamExt as _;
+use twilight_gateway::error::ReceiveMessageError;
+use twilight_gateway::{
+    create_iterator, parse, CloseFrame, ConfigBuilder, Event, EventTypeFlags, Intents, Message,
+    Shard, ShardId, StreamExt,
+};
+use twilight_model::gateway::CloseCode;
+
+/// How long to wait for in-flight handler tasks on shutdown by default
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for Discord to acknowledge our close frame
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// First delay before reconnecting after a dropped connection
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest delay between reconnection attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Intents used by [`DiscordGateway::new`]: guild messages with their content
+pub const DEFAULT_INTENTS: Intents = Intents::GUILD_MESSAGES.union(Intents::MESSAGE_CONTENT);
+
+/// Privileged intents, by the name the Developer Portal shows for them
+const PRIVILEGED_INTENTS: [(Intents, &str); 3] = [
+    (Intents::MESSAGE_CONTENT, "Message Content"),
+    (Intents::GUILD_MEMBERS, "Server Members"),
+    (Intents::GUILD_PRESENCES, "Presence"),
+];
+
+/// Default capacity of the buffer merging events from all shards
+pub const DEFAULT_EVENT_BUFFER: usize = 256;
+
+/// What shards do when the event buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backpressure {
+    /// Wait for room, which pauses reading from the gateway
+    #[default]
+    Block,
+    /// Discard the oldest buffered events to make room
+    DropOldest,
+}
 
 /// Discord Gateway client for receiving real-time events
 ///
-/// This client connects to Discord's WebSocket Gateway and listens for events,
-/// specifically MESSAGE_CREATE events. It handles reconnection automatically
-/// with proper heartbeat handling.
+/// This client connects to Discord's WebSocket Gateway with one or more
+/// shards and listens for events, MESSAGE_CREATE unless configured with
+/// [`DiscordGateway::with_event_types`].
+/// Recoverable disconnects are retried with exponential backoff; fatal close
+/// codes (such as an invalid token or disallowed intents) end the listener
+/// with an error.
 pub struct DiscordGateway {
-    /// The shard for the gateway connection
-    shard: Shard,
-    /// Discord bot token
-    token: String,
-    /// Flag to control shutdown
-    shutdown_flag: Arc<std::sync::atomic::AtomicBool>,
+    /// The shards run by this gateway
+    shards: Vec<Shard>,
+    /// Set to `true` to request a graceful shutdown
+    shutdown_tx: Arc<watch::Sender<bool>>,
+    /// Latest status of each shard
+    status_tx: Arc<watch::Sender<ShardStatuses>>,
+    /// Intents the shards identify with
+    intents: Intents,
     /// Event type flags for filtering
     event_flags: EventTypeFlags,
+    /// How long to wait for in-flight handler tasks on shutdown
+    shutdown_timeout: Duration,
+    /// Capacity of the event buffer
+    event_buffer: usize,
+    /// What to do when the event buffer is full
+    backpressure: Backpressure,
+    /// Where to send raw dispatches, if anywhere
+    raw_tap: Option<RawTap>,
+}
+
+/// A dispatch as Discord sent it, see [`DiscordGateway::with_raw_dispatches`]
+#[derive(Debug, Clone)]
+pub struct RawDispatch {
+    /// When the shard received it
+    pub received: Instant,
+    /// The whole gateway payload, e.g.
+    /// `{"op":0,"s":42,"t":"MESSAGE_CREATE","d":{...}}`
+    pub payload: String,
+}
+
+/// Where to send raw dispatches, and of which event types
+#[derive(Clone)]
+struct RawTap {
+    sender: mpsc::UnboundedSender<RawDispatch>,
+    event_flags: EventTypeFlags,
+}
+
+/// Handle for stopping a running [`DiscordGateway`] from another task
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    tx: Arc<watch::Sender<bool>>,
+}
+
+impl ShutdownHandle {
+    /// Ask the gateway to stop accepting events and shut down gracefully
+    pub fn shutdown(&self) {
+        self.tx.send_replace(true);
+    }
 }
 
 impl DiscordGateway {
-    /// Create a new Gateway client
+    /// Create a new Gateway client with a single shard
+    ///
+    /// The shard receives guild messages with their content
+    /// ([`DEFAULT_INTENTS`]).
     ///
     /// # Arguments
     ///
@@ -37,151 +131,649 @@ impl DiscordGateway {
     ///
     /// A new `DiscordGateway` instance
     pub fn new(token: String) -> Self {
-        let shutdown_flag = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        Self::with_shards(token, &ShardPlan::single(), DEFAULT_INTENTS)
+    }
 
-        // Create intents for message events
-        let intents = Intents::GUILD_MESSAGES | Intents::MESSAGE_CONTENT;
+    /// Create a Gateway client running the shards in `plan`
+    ///
+    /// Shards share one identify queue, paced by the plan's session start
+    /// limit when it is known.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - Discord bot token
+    /// * `plan` - Which shards to run
+    /// * `intents` - Gateway intents to identify with, e.g. from
+    ///   [`HooksConfig::required_intents`](crate::hooks::HooksConfig::required_intents)
+    pub fn with_shards(token: String, plan: &ShardPlan, intents: Intents) -> Self {
+        let (shutdown_tx, _) = watch::channel(false);
+
+        // Event types to listen for; close frames are always delivered
+        let event_flags =
+            EventTypeFlags::MESSAGE_CREATE | EventTypeFlags::READY | EventTypeFlags::RESUMED;
 
-        // Event types to listen for
-        let event_flags = EventTypeFlags::MESSAGE_CREATE;
+        let mut config = ConfigBuilder::new(token, intents);
+        if let Some(limit) = &plan.session_start_limit {
+            config = config.queue(InMemoryQueue::new(
+                limit.max_concurrency,
+                limit.remaining.min(limit.total),
+                Duration::from_millis(limit.reset_after),
+                limit.total,
+            ));
+        }
+        let shards: Vec<Shard> =
+            create_iterator(plan.numbers.clone(), plan.total, config.build(), |_, builder| {
+                builder.build()
+            })
+            .collect();
 
-        // Create a shard with ID 0 (for small bots, one shard is sufficient)
-        let shard = Shard::new(ShardId::ONE, token.clone(), intents);
+        let statuses = shards
+            .iter()
+            .map(|shard| (shard.id().number(), ShardStatus::Connecting))
+            .collect();
+        let (status_tx, _) = watch::channel(statuses);
 
         Self {
-            shard,
-            token,
-            shutdown_flag,
+            shards,
+            shutdown_tx: Arc::new(shutdown_tx),
+            status_tx: Arc::new(status_tx),
+            intents,
             event_flags,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            event_buffer: DEFAULT_EVENT_BUFFER,
+            backpressure: Backpressure::Block,
+            raw_tap: None,
+        }
+    }
+
+    /// Set which events are passed to the handler
+    ///
+    /// READY and RESUMED are always received, to track shard status; READY
+    /// is passed to the handler too, for the bot's own user.
+    pub fn with_event_types(mut self, event_types: EventTypeFlags) -> Self {
+        self.event_flags = event_types | EventTypeFlags::READY | EventTypeFlags::RESUMED;
+        self
+    }
+
+    /// Also send the raw JSON of received `event_types` dispatches to
+    /// `sender`, e.g. to record them exactly as Discord sent them
+    ///
+    /// Each shard sends a dispatch before passing its event on, so they
+    /// arrive in the order received. Only dispatches of types the gateway
+    /// listens for (see [`DiscordGateway::with_event_types`]) are sent.
+    pub fn with_raw_dispatches(
+        mut self,
+        sender: mpsc::UnboundedSender<RawDispatch>,
+        event_types: EventTypeFlags,
+    ) -> Self {
+        self.raw_tap = Some(RawTap {
+            sender,
+            event_flags: event_types,
+        });
+        self
+    }
+
+    /// Set how long shutdown waits for in-flight handler tasks
+    ///
+    /// Tasks still running after the timeout are aborted.
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Set the size of the event buffer and what happens when it fills up
+    ///
+    /// With [`Backpressure::Block`] a slow consumer slows down reading from
+    /// Discord; with [`Backpressure::DropOldest`] it loses the oldest
+    /// events instead (counted by [`EventStream::dropped`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero
+    pub fn with_event_buffer(mut self, capacity: usize, backpressure: Backpressure) -> Self {
+        assert!(capacity > 0, "event buffer capacity must be at least 1");
+        self.event_buffer = capacity;
+        self.backpressure = backpressure;
+        self
+    }
+
+    /// Get a handle that can stop the gateway while it is listening
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            tx: Arc::clone(&self.shutdown_tx),
         }
     }
 
-    /// Start the gateway and listen for events
+    /// Watch the status of every shard
     ///
-    /// This method starts the WebSocket connection and runs the event loop,
-    /// calling the provided handler for each MESSAGE_CREATE event.
+    /// The receiver is notified whenever any shard changes state.
+    pub fn status(&self) -> watch::Receiver<ShardStatuses> {
+        self.status_tx.subscribe()
+    }
+
+    /// Start the gateway and listen for events until Ctrl+C or SIGTERM
+    ///
+    /// See [`DiscordGateway::listen_until`].
+    pub async fn listen<F, Fut>(self, handler: F) -> Result<()>
+    where
+        F: Fn(Event) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.listen_until(handler, shutdown_signal()).await
+    }
+
+    /// Start the gateway and listen for events until `shutdown` completes
+    ///
+    /// Every shard runs in its own task, and the configured events from all
+    /// of them are passed to `handler`, whose returned future is spawned as
+    /// a task. On shutdown (or via a [`ShutdownHandle`]) the gateway stops
+    /// accepting events, waits up to the shutdown timeout for in-flight
+    /// tasks and closes the shards.
     ///
     /// # Arguments
     ///
-    /// * `handler` - Callback function to handle incoming messages
+    /// * `handler` - Callback that turns an incoming event into a task
+    /// * `shutdown` - Future that resolves when the gateway should stop
     ///
     /// # Returns
     ///
-    /// `Ok(())` if the gateway runs successfully, or an error if connection fails
+    /// `Ok(())` after a graceful shutdown
     ///
     /// # Errors
     ///
-    /// Returns an error if:
-    /// - Failed to connect to the gateway
-    /// - Connection is lost and reconnection fails
-    pub async fn listen<F>(mut self, handler: F) -> Result<()>
+    /// Returns [`DiscliError::GatewayClosed`] if Discord closes any shard
+    /// with a close code that cannot be recovered from
+    pub async fn listen_until<F, Fut, S>(self, handler: F, shutdown: S) -> Result<()>
     where
-        F: Fn(MessageCreate) + Send + Sync + 'static,
+        F: Fn(Event) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+        S: Future<Output = ()>,
     {
-        let handler = Arc::new(handler);
-        let shutdown_flag = self.shutdown_flag.clone();
-
-        // Process events from the shard
-        // The shard auto-connects when we start polling
-        info!("Starting Discord Gateway event loop...");
+        let shutdown_timeout = self.shutdown_timeout;
+        let mut tasks = JoinSet::new();
 
-        while let Some(item) = self.shard.next_event(self.event_flags).await {
-            // Check shutdown flag
-            if shutdown_flag.load(std::sync::atomic::Ordering::Relaxed) {
-                info!("Shutdown requested, stopping event loop");
-                break;
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        let shutdown = async move {
+            tokio::select! {
+                _ = shutdown => {}
+                _ = shutdown_rx.wait_for(|stop| *stop) => {}
             }
+        };
+        tokio::pin!(shutdown);
 
-            let event = match item {
-                Ok(event) => event,
-                Err(source) => {
-                    warn!("Error receiving event: {:?}", source);
-                    continue;
-                }
-            };
-
-            match event {
-                Event::MessageCreate(msg) => {
-                    info!(
-                        "Received message from {} in channel {}",
-                        msg.author.name, msg.channel_id
-                    );
-                    handler(*msg);
-                }
-                Event::Ready(ready) => {
-                    info!(
-                        "Gateway ready: {}#{} ({}), {} servers",
-                        ready.user.name,
-                        ready.user.discriminator,
-                        ready.user.id,
-                        ready.guilds.len()
-                    );
-                }
-                Event::Resumed => {
-                    info!("Gateway resumed");
-                }
-                _ => {
-                    // Ignore other events for efficiency
+        let mut stream = self.events();
+        loop {
+            // Reap finished handler tasks so the set doesn't grow unbounded
+            while tasks.try_join_next().is_some() {}
+
+            tokio::select! {
+                biased;
+                _ = &mut shutdown => {
+                    info!("Shutdown requested, no longer accepting events");
+                    break;
                 }
+                event = stream.recv() => match event {
+                    Some(event) => {
+                        tasks.spawn(handler(event));
+                    }
+                    None => {
+                        info!("All shards have stopped");
+                        break;
+                    }
+                },
+            }
+        }
+
+        // Stop the remaining shards; each closes its own connection
+        stream.shutdown_handle().shutdown();
+
+        if !tasks.is_empty() {
+            info!("Waiting for {} in-flight task(s)...", tasks.len());
+            let drained = tokio::time::timeout(shutdown_timeout, async {
+                while tasks.join_next().await.is_some() {}
+            })
+            .await;
+            if drained.is_err() {
+                warn!(
+                    "Aborting {} task(s) still running after {:.0}s",
+                    tasks.len(),
+                    shutdown_timeout.as_secs_f64()
+                );
+                tasks.shutdown().await;
             }
         }
 
+        let outcome = stream.shutdown().await;
         info!("Gateway event loop ended");
-        Ok(())
+        outcome
     }
 
-    /// Get an event receiver channel for receiving events
+    /// Start the gateway and receive its events through a buffered stream
     ///
-    /// This provides an alternative to the callback-based approach,
-    /// allowing the caller to receive events via a channel.
+    /// Every shard runs in its own task and pushes the configured events
+    /// into one buffer (see [`DiscordGateway::with_event_buffer`]).
+    /// [`EventStream::recv`] returns `None` once every shard has stopped,
+    /// whether through [`EventStream::shutdown_handle`] or because Discord
+    /// closed a shard with a fatal close code; [`EventStream::shutdown`]
+    /// then reports which.
     ///
-    /// # Returns
+    /// Must be called from within a tokio runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use discli::discord::gateway::{Backpressure, DiscordGateway};
     ///
-    /// A receiver for gateway events
-    #[allow(dead_code)]
-    pub fn events(&self) -> mpsc::Receiver<Event> {
-        // Create a channel for events
-        let (_tx, rx) = mpsc::channel(100);
+    /// # async fn example() -> discli::Result<()> {
+    /// let mut events = DiscordGateway::new("token".to_string())
+    ///     .with_event_buffer(1024, Backpressure::DropOldest)
+    ///     .events();
+    /// while let Some(event) = events.recv().await {
+    ///     println!("{:?}", event.kind());
+    /// }
+    /// events.shutdown().await
+    /// # }
+    /// ```
+    pub fn events(self) -> EventStream {
+        let (sender, receiver) = event_channel(self.event_buffer, self.backpressure);
+        let shutdown = self.shutdown_handle();
 
-        // Note: This is a simplified implementation
-        // In production, you'd want to spawn a task that forwards events to the channel
+        info!(
+            "Starting Discord Gateway with {} shard(s)...",
+            self.shards.len()
+        );
+        let mut shard_tasks = JoinSet::new();
+        for shard in self.shards {
+            shard_tasks.spawn(run_shard(
+                shard,
+                self.intents,
+                self.event_flags,
+                sender.clone(),
+                self.raw_tap.clone(),
+                Arc::clone(&self.status_tx),
+                self.shutdown_tx.subscribe(),
+            ));
+        }
+        drop(sender);
+
+        // A fatal error from one shard stops the others
+        let shutdown_tx = self.shutdown_tx;
+        let supervisor = tokio::spawn(async move {
+            let mut outcome = Ok(());
+            while let Some(finished) = shard_tasks.join_next().await {
+                let result = finished
+                    .map_err(|e| DiscliError::Gateway(format!("Shard task failed: {}", e)))
+                    .and_then(|result| result);
+                if let Err(e) = result {
+                    shutdown_tx.send_replace(true);
+                    if outcome.is_ok() {
+                        outcome = Err(e);
+                    }
+                }
+            }
+            outcome
+        });
 
-        rx
+        EventStream {
+            receiver,
+            supervisor,
+            shutdown,
+            dropped: 0,
+        }
     }
 
-    /// Gracefully shutdown the gateway
-    ///
-    /// This stops the shard and closes connections cleanly.
+    /// Get the IDs of the shards run by this gateway
     ///
     /// # Returns
     ///
-    /// `Ok(())` if shutdown succeeds
+    /// The shard IDs, in order
+    pub fn shard_ids(&self) -> Vec<ShardId> {
+        self.shards.iter().map(Shard::id).collect()
+    }
+
+    /// Get the intents the shards identify with
+    pub fn intents(&self) -> Intents {
+        self.intents
+    }
+}
+
+/// Events from a running [`DiscordGateway`], see [`DiscordGateway::events`]
+pub struct EventStream {
+    receiver: EventReceiver,
+    supervisor: JoinHandle<Result<()>>,
+    shutdown: ShutdownHandle,
+    dropped: u64,
+}
+
+impl EventStream {
+    /// Receive the next event
+    ///
+    /// Returns `None` once every shard has stopped.
+    pub async fn recv(&mut self) -> Option<Event> {
+        match &mut self.receiver {
+            EventReceiver::Block(rx) => rx.recv().await,
+            EventReceiver::DropOldest(rx) => loop {
+                match rx.recv().await {
+                    Ok(event) => return Some(event),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Event buffer full, dropped {} oldest event(s)", skipped);
+                        self.dropped += skipped;
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            },
+        }
+    }
+
+    /// Number of events discarded so far because the buffer was full
+    ///
+    /// Always zero with [`Backpressure::Block`].
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Get a handle that stops the shards, ending the stream
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Stop the shards and wait for them to close
     ///
     /// # Errors
     ///
-    /// Returns an error if shutdown fails
-    #[allow(dead_code)]
-    pub async fn shutdown(&self) -> Result<()> {
-        info!("Initiating gateway shutdown...");
+    /// Returns [`DiscliError::GatewayClosed`] if Discord closed a shard
+    /// with a close code that cannot be recovered from
+    pub async fn shutdown(self) -> Result<()> {
+        self.shutdown.shutdown();
+        drop(self.receiver);
+        self.supervisor
+            .await
+            .map_err(|e| DiscliError::Gateway(format!("Gateway task failed: {}", e)))?
+    }
+}
+
+/// Sending half of the event buffer, shared by all shards
+#[derive(Clone)]
+enum EventSender {
+    Block(mpsc::Sender<Event>),
+    DropOldest(broadcast::Sender<Event>),
+}
+
+impl EventSender {
+    /// Buffer an event; returns `false` once the stream has been dropped
+    async fn send(&self, event: Event) -> bool {
+        match self {
+            EventSender::Block(tx) => tx.send(event).await.is_ok(),
+            // A full broadcast channel overwrites its oldest value
+            EventSender::DropOldest(tx) => tx.send(event).is_ok(),
+        }
+    }
+}
 
-        // Set the shutdown flag to stop the event loop
-        self.shutdown_flag.store(true, std::sync::atomic::Ordering::Relaxed);
+enum EventReceiver {
+    Block(mpsc::Receiver<Event>),
+    DropOldest(broadcast::Receiver<Event>),
+}
 
-        // Close the shard connection
-        self.shard.close(CloseFrame::NORMAL);
+fn event_channel(capacity: usize, backpressure: Backpressure) -> (EventSender, EventReceiver) {
+    match backpressure {
+        Backpressure::Block => {
+            let (tx, rx) = mpsc::channel(capacity);
+            (EventSender::Block(tx), EventReceiver::Block(rx))
+        }
+        Backpressure::DropOldest => {
+            let (tx, rx) = broadcast::channel(capacity);
+            (EventSender::DropOldest(tx), EventReceiver::DropOldest(rx))
+        }
+    }
+}
 
-        info!("Gateway shutdown complete");
-        Ok(())
+/// Run one shard until shutdown or a fatal close, forwarding its events
+async fn run_shard(
+    mut shard: Shard,
+    intents: Intents,
+    event_flags: EventTypeFlags,
+    events: EventSender,
+    raw_tap: Option<RawTap>,
+    status: Arc<watch::Sender<ShardStatuses>>,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<()> {
+    let id = shard.id();
+    let label = format!("Shard {}/{}", id.number(), id.total());
+    let set_status = |new: ShardStatus| {
+        info!("{}: {}", label, new);
+        status.send_modify(|statuses| {
+            statuses.insert(id.number(), new);
+        });
+    };
+    let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+
+    loop {
+        let item = tokio::select! {
+            biased;
+            _ = shutdown.wait_for(|stop| *stop) => break,
+            item = next_event(&mut shard, event_flags, raw_tap.is_some()) => item,
+        };
+
+        let Some(item) = item else {
+            break;
+        };
+        let (item, raw) = match item {
+            Ok((event, raw)) => (Ok(event), raw),
+            Err(source) => (Err(source), None),
+        };
+
+        // READY is also passed on, so handlers learn the bot's own user
+        if let Ok(Event::Ready(ready)) = &item {
+            backoff.reset();
+            info!(
+                "{}: logged in as {}#{} ({})",
+                label, ready.user.name, ready.user.discriminator, ready.user.id
+            );
+            set_status(ShardStatus::Ready {
+                guilds: ready.guilds.len(),
+            });
+        }
+
+        let disconnected = match item {
+            Ok(Event::Resumed) => {
+                backoff.reset();
+                set_status(ShardStatus::Resumed);
+                false
+            }
+            Ok(Event::GatewayClose(frame)) => {
+                let code = frame.map(|f| f.code);
+                if let Some(code) = code.filter(|code| is_fatal_close_code(*code)) {
+                    set_status(ShardStatus::Failed { code });
+                    return Err(DiscliError::GatewayClosed {
+                        code,
+                        reason: close_reason(code, intents),
+                    });
+                }
+                warn!("{}: connection closed (code {:?})", label, code);
+                true
+            }
+            Ok(event) if event_flags.contains(EventTypeFlags::from(event.kind())) => {
+                info!("{}: received {:?}", label, event.kind());
+                if let (Some(tap), Some(payload)) = (&raw_tap, raw) {
+                    if tap.event_flags.contains(EventTypeFlags::from(event.kind())) {
+                        // A closed tap only means nobody wants them any more
+                        let _ = tap.sender.send(RawDispatch {
+                            received: Instant::now(),
+                            payload,
+                        });
+                    }
+                }
+                // Waiting for room in a full buffer must not block shutdown
+                let sent = tokio::select! {
+                    biased;
+                    _ = shutdown.wait_for(|stop| *stop) => false,
+                    sent = events.send(event) => sent,
+                };
+                if !sent {
+                    break;
+                }
+                false
+            }
+            Ok(_) => false,
+            Err(source) if matches!(source.kind(), ReceiveMessageErrorType::Reconnect) => {
+                warn!("{}: failed to reconnect: {}", label, source);
+                // Start over with a fresh shard so our backoff, not the
+                // shard's uncapped one, governs the retry delay
+                shard = Shard::with_config(id, shard.config().clone());
+                true
+            }
+            Err(source) => {
+                warn!("{}: ignoring event that could not be processed: {}", label, source);
+                false
+            }
+        };
+
+        if disconnected {
+            let delay = backoff.next_delay();
+            set_status(ShardStatus::Reconnecting {
+                attempt: backoff.attempts,
+            });
+            info!("{}: reconnecting in {:.1}s", label, delay.as_secs_f64());
+            tokio::select! {
+                _ = shutdown.wait_for(|stop| *stop) => break,
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
     }
 
-    /// Get the shard ID
-    ///
-    /// # Returns
-    ///
-    /// The shard ID
-    #[allow(dead_code)]
-    pub fn shard_id(&self) -> ShardId {
-        self.shard.id()
+    close_shard(&mut shard).await;
+    set_status(ShardStatus::Stopped);
+    Ok(())
+}
+
+/// The shard's next wanted event, with its raw JSON if `keep_raw` is set
+///
+/// Like twilight's `next_event`, which drops the JSON once parsed.
+async fn next_event(
+    shard: &mut Shard,
+    event_flags: EventTypeFlags,
+    keep_raw: bool,
+) -> Option<std::result::Result<(Event, Option<String>), ReceiveMessageError>> {
+    loop {
+        let json = match shard.next().await? {
+            Ok(Message::Text(json)) => json,
+            Ok(Message::Close(frame)) => return Some(Ok((Event::GatewayClose(frame), None))),
+            Err(source) => return Some(Err(source)),
+        };
+        let raw = keep_raw.then(|| json.clone());
+        match parse(json, event_flags) {
+            Ok(Some(event)) => return Some(Ok((event.into(), raw))),
+            Ok(None) => {}
+            Err(source) => return Some(Err(source)),
+        }
+    }
+}
+
+/// Close a shard and wait briefly for Discord to acknowledge it
+async fn close_shard(shard: &mut Shard) {
+    if !shard.state().is_identified() {
+        return;
+    }
+
+    shard.close(CloseFrame::NORMAL);
+    let acknowledged = tokio::time::timeout(CLOSE_TIMEOUT, async {
+        while let Some(item) = shard.next_event(EventTypeFlags::empty()).await {
+            if let Ok(Event::GatewayClose(_)) = item {
+                break;
+            }
+        }
+    })
+    .await;
+    if acknowledged.is_err() {
+        warn!("Gateway did not acknowledge close within {:?}", CLOSE_TIMEOUT);
+    }
+}
+
+/// Wait for Ctrl+C, or SIGTERM on Unix
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => info!("Received Ctrl+C"),
+                    _ = sigterm.recv() => info!("Received SIGTERM"),
+                }
+                return;
+            }
+            Err(e) => warn!("Cannot install SIGTERM handler: {}", e),
+        }
+    }
+
+    if tokio::signal::ctrl_c().await.is_ok() {
+        info!("Received Ctrl+C");
+    }
+}
+
+/// Whether a gateway close code means reconnecting would fail again
+///
+/// Unknown codes and codes outside the 4000 range are treated as
+/// recoverable.
+pub fn is_fatal_close_code(code: u16) -> bool {
+    CloseCode::try_from(code).is_ok_and(|code| !code.can_reconnect())
+}
+
+/// Names of the privileged intents in `intents`, as the Developer Portal
+/// labels them
+pub fn privileged_intent_names(intents: Intents) -> Vec<&'static str> {
+    PRIVILEGED_INTENTS
+        .iter()
+        .filter(|(intent, _)| intents.contains(*intent))
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+/// Human-readable name of a gateway close code
+///
+/// For disallowed intents (4014) this names the privileged intents that
+/// were requested, since one of them was refused.
+fn close_reason(code: u16, intents: Intents) -> String {
+    let reason = CloseCode::try_from(code)
+        .map(|code| code.to_string())
+        .unwrap_or_else(|_| "Unknown close code".to_string());
+    let privileged = privileged_intent_names(intents);
+    if CloseCode::try_from(code) == Ok(CloseCode::DisallowedIntents) && !privileged.is_empty() {
+        format!("{}; requested privileged intents: {}", reason, privileged.join(", "))
+    } else {
+        reason
+    }
+}
+
+/// Exponential backoff between reconnection attempts
+#[derive(Debug)]
+struct Backoff {
+    initial: Duration,
+    max: Duration,
+    attempts: u32,
+}
+
+impl Backoff {
+    fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            attempts: 0,
+        }
+    }
+
+    /// Delay before the next attempt: initial, 2x, 4x... capped at max
+    fn next_delay(&mut self) -> Duration {
+        let factor = 2u32.saturating_pow(self.attempts);
+        self.attempts = self.attempts.saturating_add(1);
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+
+    /// Start over after a successful connection
+    fn reset(&mut self) {
+        self.attempts = 0;
     }
 }
 
@@ -194,7 +786,6 @@ impl DiscordGateway {
 /// # Returns
 ///
 /// A new `DiscordGateway` instance
-#[allow(dead_code)]
 pub fn create_gateway(token: String) -> DiscordGateway {
     DiscordGateway::new(token)
 }
@@ -203,17 +794,130 @@ pub fn create_gateway(token: String) -> DiscordGateway {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_gateway_creation() {
+    #[tokio::test]
+    async fn test_gateway_creation() {
         let token = "test_token".to_string();
         let gateway = DiscordGateway::new(token.clone());
-        assert_eq!(gateway.shard_id().number(), 1);
+        assert_eq!(gateway.shard_ids(), vec![ShardId::new(0, 1)]);
     }
 
-    #[test]
-    fn test_create_gateway() {
+    #[tokio::test]
+    async fn test_create_gateway() {
         let token = "test_token".to_string();
         let gateway = create_gateway(token);
-        assert_eq!(gateway.shard_id().number(), 1);
+        assert_eq!(gateway.shard_ids(), vec![ShardId::new(0, 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_gateway_runs_planned_shards() {
+        let plan = ShardPlan {
+            numbers: 2..4,
+            total: 4,
+            session_start_limit: None,
+        };
+        let gateway = DiscordGateway::with_shards("test_token".to_string(), &plan, DEFAULT_INTENTS);
+
+        assert_eq!(
+            gateway.shard_ids(),
+            vec![ShardId::new(2, 4), ShardId::new(3, 4)]
+        );
+        let statuses = gateway.status().borrow().clone();
+        assert_eq!(statuses.keys().copied().collect::<Vec<_>>(), vec![2, 3]);
+        assert!(statuses.values().all(|s| *s == ShardStatus::Connecting));
+    }
+
+    #[tokio::test]
+    async fn test_events_stream_ends_on_shutdown() {
+        let gateway = DiscordGateway::new("test_token".to_string());
+        let mut events = gateway.events();
+        events.shutdown_handle().shutdown();
+
+        assert!(events.recv().await.is_none());
+        assert!(events.shutdown().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_event_buffer_backpressure() {
+        let hello = |interval| Event::GatewayHello(twilight_model::gateway::payload::incoming::Hello {
+            heartbeat_interval: interval,
+        });
+        let received = |event: Option<Event>| match event {
+            Some(Event::GatewayHello(hello)) => hello.heartbeat_interval,
+            other => panic!("unexpected event {:?}", other),
+        };
+
+        // Dropping the oldest keeps the newest events and counts the rest
+        let (tx, receiver) = event_channel(2, Backpressure::DropOldest);
+        for interval in 1..=3 {
+            assert!(tx.send(hello(interval)).await);
+        }
+        drop(tx);
+        let mut stream = EventStream {
+            receiver,
+            supervisor: tokio::spawn(async { Ok(()) }),
+            shutdown: DiscordGateway::new("t".to_string()).shutdown_handle(),
+            dropped: 0,
+        };
+        assert_eq!(received(stream.recv().await), 2);
+        assert_eq!(stream.dropped(), 1);
+        assert_eq!(received(stream.recv().await), 3);
+        assert!(stream.recv().await.is_none());
+
+        // Blocking waits for room instead
+        let (tx, _receiver) = event_channel(1, Backpressure::Block);
+        assert!(tx.send(hello(1)).await);
+        let blocked = tokio::time::timeout(Duration::from_millis(50), tx.send(hello(2))).await;
+        assert!(blocked.is_err());
+    }
+
+    #[test]
+    fn test_fatal_close_codes() {
+        // Authentication failed, disallowed intents
+        assert!(is_fatal_close_code(4004));
+        assert!(is_fatal_close_code(4014));
+        // Unknown error, session timed out, normal closure, unknown code
+        assert!(!is_fatal_close_code(4000));
+        assert!(!is_fatal_close_code(4009));
+        assert!(!is_fatal_close_code(1000));
+        assert!(!is_fatal_close_code(4999));
+    }
+
+    #[test]
+    fn test_disallowed_intents_reason_names_privileged_intents() {
+        let intents = Intents::GUILD_MESSAGES | Intents::MESSAGE_CONTENT | Intents::GUILD_MEMBERS;
+        assert_eq!(
+            privileged_intent_names(intents),
+            vec!["Message Content", "Server Members"]
+        );
+
+        let reason = close_reason(4014, intents);
+        assert!(reason.ends_with("requested privileged intents: Message Content, Server Members"));
+        assert!(!close_reason(4014, Intents::DIRECT_MESSAGES).contains("privileged"));
+        assert!(!close_reason(4004, intents).contains("privileged"));
+    }
+
+    #[test]
+    fn test_backoff_doubles_until_capped() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(10));
+        let delays: Vec<u64> = (0..6).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 10, 10]);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_listen_until_returns_on_shutdown_handle() {
+        let gateway = DiscordGateway::new("test_token".to_string());
+        let handle = gateway.shutdown_handle();
+        let status = gateway.status();
+        handle.shutdown();
+
+        let result = gateway
+            .listen_until(|_| async {}, std::future::pending())
+            .await;
+
+        assert!(result.is_ok());
+        assert!(status.borrow().values().all(|s| *s == ShardStatus::Stopped));
     }
 }
@@ -5,9 +5,12 @@
 
 pub mod api;
 pub mod client;
+pub mod error;
 pub mod gateway;
+pub mod permissions;
+pub mod sharding;
 pub mod types;
 
 pub use client::DiscordClient;
-pub use gateway::{create_gateway, DiscordGateway};
+pub use gateway::{create_gateway, Backpressure, DiscordGateway, EventStream};
 
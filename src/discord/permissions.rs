@@ -0,0 +1,175 @@
+//! Channel permission calculation
+//!
+//! Resolves a member's effective permissions in a channel the same way
+//! Discord does: base permissions from @everyone and the member's roles,
+//! then the channel's @everyone, role and member overwrites in that order.
+
+use crate::discord::types::{ChannelInfo, GuildInfo};
+use twilight_model::guild::Permissions;
+
+/// Overwrite type for roles in `permission_overwrites`
+const OVERWRITE_ROLE: u8 = 0;
+/// Overwrite type for members in `permission_overwrites`
+const OVERWRITE_MEMBER: u8 = 1;
+
+/// Compute the effective permissions of a guild member in a channel
+///
+/// # Arguments
+///
+/// * `guild` - Guild the channel belongs to (with roles)
+/// * `channel` - Channel to compute permissions for
+/// * `user_id` - ID of the member
+/// * `member_roles` - Role IDs assigned to the member
+///
+/// # Returns
+///
+/// The resolved permission set
+pub fn compute_channel_permissions(
+    guild: &GuildInfo,
+    channel: &ChannelInfo,
+    user_id: &str,
+    member_roles: &[String],
+) -> Permissions {
+    if guild.owner_id == user_id {
+        return Permissions::all();
+    }
+
+    // The @everyone role shares its ID with the guild
+    let mut permissions = guild
+        .roles
+        .iter()
+        .filter(|role| role.id == guild.id || member_roles.contains(&role.id))
+        .fold(Permissions::empty(), |acc, role| acc | role.permissions);
+
+    if permissions.contains(Permissions::ADMINISTRATOR) {
+        return Permissions::all();
+    }
+
+    let overwrites = &channel.permission_overwrites;
+
+    if let Some(everyone) = overwrites.iter().find(|o| o.id == guild.id) {
+        permissions.remove(everyone.deny);
+        permissions.insert(everyone.allow);
+    }
+
+    let (allow, deny) = overwrites
+        .iter()
+        .filter(|o| o.kind == OVERWRITE_ROLE && member_roles.contains(&o.id))
+        .fold((Permissions::empty(), Permissions::empty()), |(allow, deny), o| {
+            (allow | o.allow, deny | o.deny)
+        });
+    permissions.remove(deny);
+    permissions.insert(allow);
+
+    if let Some(member) = overwrites
+        .iter()
+        .find(|o| o.kind == OVERWRITE_MEMBER && o.id == user_id)
+    {
+        permissions.remove(member.deny);
+        permissions.insert(member.allow);
+    }
+
+    permissions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::types::{PermissionOverwrite, RoleInfo};
+
+    fn guild(everyone: Permissions, roles: Vec<RoleInfo>) -> GuildInfo {
+        let mut all_roles = vec![RoleInfo {
+            id: "1".to_string(),
+            name: "@everyone".to_string(),
+            permissions: everyone,
+            tags: None,
+        }];
+        all_roles.extend(roles);
+        GuildInfo {
+            id: "1".to_string(),
+            name: "Test Guild".to_string(),
+            owner_id: "99".to_string(),
+            roles: all_roles,
+        }
+    }
+
+    fn channel(overwrites: Vec<PermissionOverwrite>) -> ChannelInfo {
+        ChannelInfo {
+            id: "10".to_string(),
+            kind: 0,
+            guild_id: Some("1".to_string()),
+            name: Some("general".to_string()),
+            parent_id: None,
+            owner_id: None,
+            permission_overwrites: overwrites,
+        }
+    }
+
+    fn overwrite(id: &str, kind: u8, allow: Permissions, deny: Permissions) -> PermissionOverwrite {
+        PermissionOverwrite {
+            id: id.to_string(),
+            kind,
+            allow,
+            deny,
+        }
+    }
+
+    #[test]
+    fn test_owner_has_all_permissions() {
+        let g = guild(Permissions::empty(), vec![]);
+        let perms = compute_channel_permissions(&g, &channel(vec![]), "99", &[]);
+        assert_eq!(perms, Permissions::all());
+    }
+
+    #[test]
+    fn test_role_permissions_are_combined() {
+        let g = guild(
+            Permissions::VIEW_CHANNEL,
+            vec![RoleInfo {
+                id: "2".to_string(),
+                name: "Bot".to_string(),
+                permissions: Permissions::SEND_MESSAGES,
+                tags: None,
+            }],
+        );
+        let perms = compute_channel_permissions(&g, &channel(vec![]), "5", &["2".to_string()]);
+        assert!(perms.contains(Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES));
+        assert!(!perms.contains(Permissions::ATTACH_FILES));
+    }
+
+    #[test]
+    fn test_administrator_bypasses_overwrites() {
+        let g = guild(
+            Permissions::empty(),
+            vec![RoleInfo {
+                id: "2".to_string(),
+                name: "Admin".to_string(),
+                permissions: Permissions::ADMINISTRATOR,
+                tags: None,
+            }],
+        );
+        let c = channel(vec![overwrite("1", 0, Permissions::empty(), Permissions::SEND_MESSAGES)]);
+        let perms = compute_channel_permissions(&g, &c, "5", &["2".to_string()]);
+        assert_eq!(perms, Permissions::all());
+    }
+
+    #[test]
+    fn test_overwrite_order() {
+        let g = guild(Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES, vec![]);
+        let c = channel(vec![
+            // @everyone may not send here...
+            overwrite("1", 0, Permissions::empty(), Permissions::SEND_MESSAGES),
+            // ...unless they have role 2...
+            overwrite("2", 0, Permissions::SEND_MESSAGES, Permissions::empty()),
+            // ...and member 5 may never attach files
+            overwrite("5", 1, Permissions::empty(), Permissions::ATTACH_FILES),
+        ]);
+
+        let plain = compute_channel_permissions(&g, &c, "6", &[]);
+        assert!(!plain.contains(Permissions::SEND_MESSAGES));
+
+        let with_role = compute_channel_permissions(&g, &c, "5", &["2".to_string()]);
+        assert!(with_role.contains(Permissions::SEND_MESSAGES));
+        assert!(!with_role.contains(Permissions::ATTACH_FILES));
+    }
+}
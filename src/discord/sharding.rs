@@ -0,0 +1,223 @@
+//! Shard planning and per-shard status
+//!
+//! Discord splits a bot's guilds across shards once it grows past a few
+//! thousand servers. A [`ShardPlan`] says which shards this process runs,
+//! based on `GET /gateway/bot` or explicit `--shards`/`--shard-range`
+//! options.
+
+use crate::discord::types::{GatewayBotInfo, SessionStartLimit};
+use crate::error::{DiscliError, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Which shards to run, out of how many in total
+#[derive(Debug, Clone)]
+pub struct ShardPlan {
+    /// Shard numbers run by this process
+    pub numbers: Range<u32>,
+    /// Total number of shards across all processes
+    pub total: u32,
+    /// Session start limits, if known, used to pace identifies
+    pub session_start_limit: Option<SessionStartLimit>,
+}
+
+impl ShardPlan {
+    /// A single shard covering every guild
+    pub fn single() -> Self {
+        Self {
+            numbers: 0..1,
+            total: 1,
+            session_start_limit: None,
+        }
+    }
+
+    /// Work out the shards to run
+    ///
+    /// The total is `shards` if given, otherwise Discord's recommendation,
+    /// otherwise 1. Without a `range` this process runs every shard.
+    ///
+    /// # Arguments
+    ///
+    /// * `recommended` - Response from `GET /gateway/bot`, if fetched
+    /// * `shards` - Explicit total shard count (`--shards`)
+    /// * `range` - Shards to run in this process (`--shard-range`)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the total is zero or the range does not fit it
+    pub fn resolve(
+        recommended: Option<&GatewayBotInfo>,
+        shards: Option<u32>,
+        range: Option<ShardRange>,
+    ) -> Result<Self> {
+        let total = shards
+            .or(recommended.map(|info| info.shards))
+            .unwrap_or(1);
+        if total == 0 {
+            return Err(DiscliError::Config("Shard count must be at least 1".into()));
+        }
+
+        let numbers = match range {
+            Some(range) if range.last >= total => {
+                return Err(DiscliError::Config(format!(
+                    "Shard range {} does not fit {} shard(s); shard numbers go from 0 to {}",
+                    range,
+                    total,
+                    total - 1
+                )))
+            }
+            Some(range) => range.first..range.last + 1,
+            None => 0..total,
+        };
+
+        Ok(Self {
+            numbers,
+            total,
+            session_start_limit: recommended.map(|info| info.session_start_limit.clone()),
+        })
+    }
+}
+
+impl fmt::Display for ShardPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.numbers.len() == 1 {
+            write!(f, "shard {} of {}", self.numbers.start, self.total)
+        } else {
+            write!(
+                f,
+                "shards {}-{} of {}",
+                self.numbers.start,
+                self.numbers.end - 1,
+                self.total
+            )
+        }
+    }
+}
+
+/// An inclusive range of shard numbers, written `first-last` (or just `n`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardRange {
+    /// First shard number
+    pub first: u32,
+    /// Last shard number (inclusive)
+    pub last: u32,
+}
+
+impl FromStr for ShardRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<u32>()
+                .map_err(|_| format!("invalid shard number '{}'", n.trim()))
+        };
+        let (first, last) = match s.split_once('-') {
+            Some((first, last)) => (parse(first)?, parse(last)?),
+            None => {
+                let n = parse(s)?;
+                (n, n)
+            }
+        };
+        if first > last {
+            return Err(format!("shard range {}-{} is reversed", first, last));
+        }
+        Ok(Self { first, last })
+    }
+}
+
+impl fmt::Display for ShardRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.first, self.last)
+    }
+}
+
+/// Status of every shard run by a gateway, keyed by shard number
+pub type ShardStatuses = BTreeMap<u32, ShardStatus>;
+
+/// Connection state of one shard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShardStatus {
+    /// Connecting and identifying
+    Connecting,
+    /// Identified and receiving events
+    Ready {
+        /// Number of guilds assigned to the shard
+        guilds: usize,
+    },
+    /// Resumed a previous session
+    Resumed,
+    /// Disconnected; waiting before reconnecting
+    Reconnecting {
+        /// Reconnection attempts since the last successful connection
+        attempt: u32,
+    },
+    /// Closed by Discord with a close code that cannot be recovered from
+    Failed {
+        /// Gateway close code
+        code: u16,
+    },
+    /// Shut down
+    Stopped,
+}
+
+impl fmt::Display for ShardStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShardStatus::Connecting => write!(f, "connecting"),
+            ShardStatus::Ready { guilds } => write!(f, "ready ({} servers)", guilds),
+            ShardStatus::Resumed => write!(f, "resumed"),
+            ShardStatus::Reconnecting { attempt } => write!(f, "reconnecting (attempt {})", attempt),
+            ShardStatus::Failed { code } => write!(f, "failed (close code {})", code),
+            ShardStatus::Stopped => write!(f, "stopped"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recommended(shards: u32) -> GatewayBotInfo {
+        serde_json::from_value(serde_json::json!({
+            "url": "wss://gateway.discord.gg",
+            "shards": shards,
+            "session_start_limit": {
+                "total": 1000, "remaining": 990, "reset_after": 1000, "max_concurrency": 1
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolve_uses_recommendation() {
+        let plan = ShardPlan::resolve(Some(&recommended(4)), None, None).unwrap();
+        assert_eq!(plan.numbers, 0..4);
+        assert_eq!(plan.total, 4);
+        assert_eq!(plan.session_start_limit.unwrap().remaining, 990);
+    }
+
+    #[test]
+    fn test_resolve_explicit_shards_and_range() {
+        let range: ShardRange = "2-3".parse().unwrap();
+        let plan = ShardPlan::resolve(Some(&recommended(2)), Some(8), Some(range)).unwrap();
+        assert_eq!(plan.numbers, 2..4);
+        assert_eq!(plan.total, 8);
+        assert_eq!(plan.to_string(), "shards 2-3 of 8");
+
+        assert!(ShardPlan::resolve(None, Some(2), Some(range)).is_err());
+        assert!(ShardPlan::resolve(None, Some(0), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_shard_range() {
+        assert_eq!(
+            "5".parse::<ShardRange>().unwrap(),
+            ShardRange { first: 5, last: 5 }
+        );
+        assert!("3-1".parse::<ShardRange>().is_err());
+        assert!("a-b".parse::<ShardRange>().is_err());
+    }
+}
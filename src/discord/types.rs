@@ -1,7 +1,8 @@
 //! Discord API type definitions
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use twilight_model::guild::Permissions;
 
 /// Represents a Discord message with optional attachments
 #[derive(Debug, Clone)]
@@ -190,3 +191,130 @@ pub struct Attachment {
     pub description: Option<String>,
     pub filename: Option<String>,
 }
+
+/// The bot's own user, as returned by `GET /users/@me`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CurrentUser {
+    /// User ID
+    pub id: String,
+    /// Username
+    pub username: String,
+    /// Whether the account is a bot account
+    #[serde(default)]
+    pub bot: bool,
+}
+
+/// Channel details, as returned by `GET /channels/{channel.id}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChannelInfo {
+    /// Channel ID
+    pub id: String,
+    /// Channel type (0 = text, 1 = DM, 5 = announcement, ...)
+    #[serde(rename = "type")]
+    pub kind: u8,
+    /// Guild the channel belongs to (absent for DMs)
+    #[serde(default)]
+    pub guild_id: Option<String>,
+    /// Channel name (absent for DMs)
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Parent category (or parent channel for threads)
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// User who started the thread (threads only)
+    #[serde(default)]
+    pub owner_id: Option<String>,
+    /// Explicit permission overwrites for roles and members
+    #[serde(default)]
+    pub permission_overwrites: Vec<PermissionOverwrite>,
+}
+
+impl ChannelInfo {
+    /// Whether this is a thread, whose category and overwrites are its
+    /// parent channel's
+    pub fn is_thread(&self) -> bool {
+        matches!(self.kind, 10..=12)
+    }
+}
+
+/// A channel permission overwrite
+#[derive(Debug, Clone, Deserialize)]
+pub struct PermissionOverwrite {
+    /// Role or user ID
+    pub id: String,
+    /// 0 for a role overwrite, 1 for a member overwrite
+    #[serde(rename = "type")]
+    pub kind: u8,
+    /// Permissions explicitly allowed
+    pub allow: Permissions,
+    /// Permissions explicitly denied
+    pub deny: Permissions,
+}
+
+/// Guild details, as returned by `GET /guilds/{guild.id}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct GuildInfo {
+    /// Guild ID
+    pub id: String,
+    /// Guild name
+    pub name: String,
+    /// User ID of the guild owner
+    pub owner_id: String,
+    /// All roles in the guild, including @everyone (whose ID equals the guild ID)
+    #[serde(default)]
+    pub roles: Vec<RoleInfo>,
+}
+
+/// A guild role
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoleInfo {
+    /// Role ID
+    pub id: String,
+    /// Role name
+    pub name: String,
+    /// Permissions granted by the role
+    pub permissions: Permissions,
+    /// What the role belongs to, for managed roles
+    #[serde(default)]
+    pub tags: Option<RoleTags>,
+}
+
+/// Tags of a managed role
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RoleTags {
+    /// Bot the role was created for
+    #[serde(default)]
+    pub bot_id: Option<String>,
+}
+
+/// A guild member, as returned by `GET /guilds/{guild.id}/members/{user.id}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct GuildMemberInfo {
+    /// Role IDs assigned to the member
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+/// Gateway connection details, as returned by `GET /gateway/bot`
+#[derive(Debug, Clone, Deserialize)]
+pub struct GatewayBotInfo {
+    /// WebSocket URL to connect to
+    pub url: String,
+    /// Recommended number of shards
+    pub shards: u32,
+    /// Limits on how many sessions the bot may start
+    pub session_start_limit: SessionStartLimit,
+}
+
+/// Session start limits for the bot
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionStartLimit {
+    /// Total number of session starts allowed per reset period
+    pub total: u32,
+    /// Session starts remaining in the current period
+    pub remaining: u32,
+    /// Milliseconds until the limit resets
+    pub reset_after: u64,
+    /// Number of shards that may identify at the same time
+    pub max_concurrency: u16,
+}
@@ -1,5 +1,6 @@
 //! Error types and handling for discli
 
+use crate::discord::error::{self as api, DiscordApiError};
 use thiserror::Error;
 
 /// Main error type for discli operations
@@ -11,7 +12,7 @@ pub enum DiscliError {
 
     /// Discord API errors
     #[error("Discord API error: {0}")]
-    DiscordApi(String),
+    DiscordApi(DiscordApiError),
 
     /// Network errors from reqwest
     #[error("Network error: {0}")]
@@ -33,15 +34,176 @@ pub enum DiscliError {
     #[error("MIME type error: {0}")]
     Mime(String),
 
+    /// Hook processor (command or HTTP endpoint) errors
+    #[error("Processor error: {0}")]
+    Processor(String),
+
+    /// A hook command exited unsuccessfully
+    #[error("Processor error: Command failed ({status}): {stderr}")]
+    CommandFailed {
+        /// How the command exited, e.g. "exit status: 1"
+        status: String,
+        /// Everything the command wrote to stderr
+        stderr: String,
+    },
+
     /// Gateway connection errors
     #[error("Gateway error: {0}")]
     Gateway(String),
 
+    /// Discord closed the gateway connection with a fatal close code
+    #[error("Gateway closed by Discord: {reason} (close code {code})")]
+    GatewayClosed {
+        /// Gateway close code, e.g. 4004
+        code: u16,
+        /// Name of the close code
+        reason: String,
+    },
+
+    /// Background listener (daemon) errors
+    #[error("Daemon error: {0}")]
+    Daemon(String),
+
+    /// `discli doctor` found problems
+    #[error("doctor found {count} problem(s)")]
+    ChecksFailed {
+        /// How many checks failed
+        count: usize,
+        /// Class of the first failed check
+        class: ErrorClass,
+    },
+
     /// WebSocket errors
     #[error("WebSocket error: {0}")]
     WebSocket(String),
 }
 
+/// Broad class of an error, used to pick the process exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Anything not covered by a more specific class
+    General,
+    /// Missing or invalid configuration (including unknown channels)
+    Config,
+    /// The token was rejected
+    Auth,
+    /// The bot lacks access or permissions
+    Permission,
+    /// The message or input was rejected as invalid
+    Validation,
+    /// Discord rate limited the request
+    RateLimit,
+    /// Connection problems or Discord server errors
+    Network,
+}
+
+impl ErrorClass {
+    /// Process exit code for this class
+    ///
+    /// Exit code 2 is left to clap for command-line usage errors.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorClass::General => 1,
+            ErrorClass::Config => 3,
+            ErrorClass::Auth => 4,
+            ErrorClass::Permission => 5,
+            ErrorClass::Validation => 6,
+            ErrorClass::RateLimit => 7,
+            ErrorClass::Network => 8,
+        }
+    }
+}
+
+impl DiscliError {
+    /// Classify the error
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            DiscliError::Config(_) => ErrorClass::Config,
+            DiscliError::DiscordApi(e) => classify_api_error(e),
+            DiscliError::GatewayClosed { code, .. } => classify_close_code(*code),
+            DiscliError::ChecksFailed { class, .. } => *class,
+            DiscliError::Network(_) | DiscliError::Gateway(_) | DiscliError::WebSocket(_) => {
+                ErrorClass::Network
+            }
+            DiscliError::Attachment(_) | DiscliError::Validation(_) | DiscliError::Mime(_) => {
+                ErrorClass::Validation
+            }
+            DiscliError::Io(_)
+            | DiscliError::Processor(_)
+            | DiscliError::CommandFailed { .. }
+            | DiscliError::Daemon(_) => ErrorClass::General,
+        }
+    }
+
+    /// Process exit code for this error
+    pub fn exit_code(&self) -> i32 {
+        self.class().exit_code()
+    }
+
+    /// A short suggestion on how to fix the error, if one is known
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            DiscliError::DiscordApi(e) => e.hint(),
+            DiscliError::GatewayClosed { code, .. } => close_code_hint(*code),
+            _ => None,
+        }
+    }
+}
+
+/// Classify a Discord API error by its JSON code, falling back to the HTTP status
+fn classify_api_error(error: &DiscordApiError) -> ErrorClass {
+    match error.code {
+        Some(api::UNAUTHORIZED) => return ErrorClass::Auth,
+        Some(api::MISSING_ACCESS | api::MISSING_PERMISSIONS | api::CANNOT_MESSAGE_USER) => {
+            return ErrorClass::Permission
+        }
+        Some(api::UNKNOWN_CHANNEL | api::UNKNOWN_GUILD | api::UNKNOWN_WEBHOOK) => {
+            return ErrorClass::Config
+        }
+        Some(
+            api::INVALID_FORM_BODY
+            | api::EMPTY_MESSAGE
+            | api::NON_TEXT_CHANNEL
+            | api::REQUEST_TOO_LARGE,
+        ) => return ErrorClass::Validation,
+        _ => {}
+    }
+
+    match error.status {
+        401 => ErrorClass::Auth,
+        403 => ErrorClass::Permission,
+        404 => ErrorClass::Config,
+        400 | 413 => ErrorClass::Validation,
+        429 => ErrorClass::RateLimit,
+        500..=599 => ErrorClass::Network,
+        _ => ErrorClass::General,
+    }
+}
+
+/// Classify a fatal gateway close code
+fn classify_close_code(code: u16) -> ErrorClass {
+    match code {
+        4004 => ErrorClass::Auth,
+        4013 | 4014 => ErrorClass::Permission,
+        4010..=4012 => ErrorClass::Config,
+        _ => ErrorClass::Network,
+    }
+}
+
+/// A short suggestion on how to fix a fatal gateway close
+fn close_code_hint(code: u16) -> Option<&'static str> {
+    let hint = match code {
+        4004 => "Check DISCORD_TOKEN; the token is invalid or was reset in the Developer Portal",
+        4013 => "The requested gateway intents are invalid",
+        4014 => {
+            "Enable the listed intents (such as Message Content) under Bot > Privileged Gateway Intents in the Developer Portal"
+        }
+        4011 => "The bot is in too many servers to run on a single shard",
+        _ => return None,
+    };
+    Some(hint)
+}
+
 /// Result type alias for convenience
 pub type Result<T> = std::result::Result<T, DiscliError>;
 
@@ -61,4 +223,40 @@ mod tests {
         let discli_err: DiscliError = io_err.into();
         assert!(matches!(discli_err, DiscliError::Io(_)));
     }
+
+    #[test]
+    fn test_api_error_exit_codes() {
+        let api_err = |status, body| DiscliError::DiscordApi(DiscordApiError::from_response(status, body));
+
+        assert_eq!(api_err(401, r#"{"message": "401: Unauthorized", "code": 0}"#).exit_code(), 4);
+        assert_eq!(api_err(403, r#"{"message": "Missing Access", "code": 50001}"#).exit_code(), 5);
+        assert_eq!(api_err(404, r#"{"message": "Unknown Channel", "code": 10003}"#).exit_code(), 3);
+        assert_eq!(api_err(400, r#"{"message": "Invalid Form Body", "code": 50035}"#).exit_code(), 6);
+        assert_eq!(api_err(429, r#"{"message": "Rate limited", "retry_after": 1.0}"#).exit_code(), 7);
+        assert_eq!(api_err(503, "upstream connect error").exit_code(), 8);
+    }
+
+    #[test]
+    fn test_local_error_exit_codes() {
+        assert_eq!(DiscliError::Config("x".into()).exit_code(), 3);
+        assert_eq!(DiscliError::Validation("x".into()).exit_code(), 6);
+        assert_eq!(DiscliError::Attachment("x".into()).exit_code(), 6);
+        assert_eq!(DiscliError::Processor("x".into()).exit_code(), 1);
+        let checks = |class| DiscliError::ChecksFailed { count: 2, class };
+        assert_eq!(checks(ErrorClass::Auth).exit_code(), 4);
+        assert_eq!(checks(ErrorClass::Permission).exit_code(), 5);
+    }
+
+    #[test]
+    fn test_gateway_close_exit_codes() {
+        let closed = |code| DiscliError::GatewayClosed {
+            code,
+            reason: String::new(),
+        };
+
+        assert_eq!(closed(4004).exit_code(), 4);
+        assert_eq!(closed(4014).exit_code(), 5);
+        assert!(closed(4014).hint().unwrap().contains("Message Content"));
+        assert_eq!(closed(4011).exit_code(), 3);
+    }
 }
@@ -0,0 +1,277 @@
+//! Concurrency limits for hooks
+//!
+//! A hook with `max_concurrency` runs at most that many times at once. An
+//! event that finds every run busy waits in the hook's queue, is dropped,
+//! or gets a busy reply, as the hook's `on_busy` says. Separately,
+//! `settings.max_processors` caps the processors running at once across
+//! all hooks; processors beyond it wait for one to finish.
+
+use crate::hooks::config::{BusyPolicy, CompiledHookConfig};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Concurrency limiter for hooks
+#[derive(Default)]
+pub struct ConcurrencyLimiter {
+    /// Runs of every hook with `max_concurrency`, by hook ID
+    hooks: Mutex<HashMap<String, Arc<HookSlots>>>,
+    /// `settings.max_processors` and its permits
+    processors: RwLock<Option<(usize, Arc<Semaphore>)>>,
+}
+
+/// Runs of one hook, and the events waiting for one to finish
+struct HookSlots {
+    max: usize,
+    running: Arc<Semaphore>,
+    waiting: AtomicUsize,
+}
+
+/// A place in a hook's runs or among the running processors, given back
+/// when dropped
+pub struct Slot {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+/// Why an event may not run a hook right now
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Busy {
+    /// The hook's `max_concurrency`, all of them running
+    pub running: usize,
+    /// Whether the event found the queue full, rather than the hook not
+    /// queueing at all
+    pub queue_full: bool,
+    /// Busy reply for the event's channel, if the hook sends one
+    pub reply: Option<String>,
+}
+
+impl fmt::Display for Busy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.queue_full {
+            write!(f, "Busy ({} runs in progress, queue full)", self.running)
+        } else {
+            write!(f, "Busy ({} runs in progress)", self.running)
+        }
+    }
+}
+
+/// An event counted as waiting for a hook's run until dropped
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl<'a> Waiting<'a> {
+    /// Join the queue, unless `depth` events already wait
+    fn join(waiting: &'a AtomicUsize, depth: usize) -> Option<Self> {
+        waiting
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < depth).then_some(n + 1))
+            .ok()
+            .map(|_| Waiting(waiting))
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl ConcurrencyLimiter {
+    /// Create a limiter with `settings.max_processors`
+    pub fn new(max_processors: Option<usize>) -> Self {
+        let limiter = Self::default();
+        limiter.set_max_processors(max_processors);
+        limiter
+    }
+
+    /// Change `settings.max_processors`
+    ///
+    /// Processors already running count against the old cap until they
+    /// finish.
+    pub fn set_max_processors(&self, max: Option<usize>) {
+        let mut processors = self.processors.write().unwrap_or_else(|e| e.into_inner());
+        if processors.as_ref().map(|(current, _)| *current) != max {
+            *processors = max.map(|max| (max, Arc::new(Semaphore::new(max))));
+        }
+    }
+
+    /// Forget the runs of hooks that are not in `hooks` or no longer
+    /// limit them, e.g. after a reload
+    ///
+    /// Runs already in progress finish as usual.
+    pub fn retain(&self, hooks: &[CompiledHookConfig]) {
+        let mut slots = self.hooks.lock().unwrap_or_else(|e| e.into_inner());
+        slots.retain(|id, slots| {
+            hooks
+                .iter()
+                .any(|hook| hook.id == *id && hook.max_concurrency == Some(slots.max))
+        });
+    }
+
+    /// Take one of `hook`'s runs, waiting in its queue if `on_busy` says so
+    ///
+    /// Hooks without `max_concurrency` always get one.
+    pub async fn acquire(&self, hook: &CompiledHookConfig) -> Result<Slot, Busy> {
+        let Some(max) = hook.max_concurrency else {
+            return Ok(Slot { _permit: None });
+        };
+        let slots = self.slots(&hook.id, max);
+        if let Ok(permit) = Arc::clone(&slots.running).try_acquire_owned() {
+            return Ok(Slot { _permit: Some(permit) });
+        }
+
+        let busy = Busy {
+            running: max,
+            queue_full: false,
+            reply: None,
+        };
+        match &hook.on_busy {
+            BusyPolicy::Queue { depth } => {
+                let Some(_waiting) = Waiting::join(&slots.waiting, *depth) else {
+                    return Err(Busy {
+                        queue_full: true,
+                        ..busy
+                    });
+                };
+                let permit = Arc::clone(&slots.running)
+                    .acquire_owned()
+                    .await
+                    .expect("hook runs are never closed");
+                Ok(Slot { _permit: Some(permit) })
+            }
+            BusyPolicy::Drop => Err(busy),
+            BusyPolicy::Reply { message } => Err(Busy {
+                reply: Some(message.clone()),
+                ..busy
+            }),
+        }
+    }
+
+    /// Take a place among the running processors, waiting for one if
+    /// `settings.max_processors` are running
+    pub async fn acquire_processor(&self) -> Slot {
+        let processors = self
+            .processors
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|(_, semaphore)| Arc::clone(semaphore));
+        let permit = match processors {
+            Some(semaphore) => Some(
+                semaphore
+                    .acquire_owned()
+                    .await
+                    .expect("processor permits are never closed"),
+            ),
+            None => None,
+        };
+        Slot { _permit: permit }
+    }
+
+    /// The runs of the hook with ID `hook`
+    ///
+    /// A reload that changes the hook's `max_concurrency` starts counting
+    /// afresh; runs already in progress finish on the old count.
+    fn slots(&self, hook: &str, max: usize) -> Arc<HookSlots> {
+        let mut hooks = self.hooks.lock().unwrap_or_else(|e| e.into_inner());
+        match hooks.get(hook) {
+            Some(slots) if slots.max == max => Arc::clone(slots),
+            _ => {
+                let slots = Arc::new(HookSlots {
+                    max,
+                    running: Arc::new(Semaphore::new(max)),
+                    waiting: AtomicUsize::new(0),
+                });
+                hooks.insert(hook.to_string(), Arc::clone(&slots));
+                slots
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::config::HookConfig;
+    use std::time::Duration;
+
+    fn hook(on_busy: &str) -> CompiledHookConfig {
+        let hook: HookConfig = serde_yaml::from_str(&format!(
+            r#"
+id: "ask"
+channels: ["10"]
+prompt_file: "p.txt"
+action: {{ type: "reply" }}
+max_concurrency: 1
+on_busy: {}
+"#,
+            on_busy
+        ))
+        .unwrap();
+        hook.compile().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_busy_policies() {
+        let limiter = ConcurrencyLimiter::default();
+
+        let dropping = hook("{ type: drop }");
+        let running = limiter.acquire(&dropping).await.unwrap();
+        assert_eq!(
+            limiter.acquire(&dropping).await.err().map(|busy| busy.to_string()),
+            Some("Busy (1 runs in progress)".to_string())
+        );
+        drop(running);
+        assert!(limiter.acquire(&dropping).await.is_ok());
+
+        let replying = hook("{ type: reply, message: \"later\" }");
+        let _running = limiter.acquire(&replying).await.unwrap();
+        let busy = limiter.acquire(&replying).await.err().unwrap();
+        assert_eq!(busy.reply.as_deref(), Some("later"));
+    }
+
+    #[tokio::test]
+    async fn test_queue_waits_up_to_depth() {
+        let limiter = Arc::new(ConcurrencyLimiter::default());
+        let queued = Arc::new(hook("{ type: queue, depth: 1 }"));
+        let running = limiter.acquire(&queued).await.unwrap();
+
+        let waiter = {
+            let limiter = Arc::clone(&limiter);
+            let queued = Arc::clone(&queued);
+            tokio::spawn(async move { limiter.acquire(&queued).await.is_ok() })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let overflow = limiter.acquire(&queued).await.err().unwrap();
+        assert!(overflow.queue_full);
+
+        drop(running);
+        assert!(waiter.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_retain_forgets_dropped_hooks() {
+        let limiter = ConcurrencyLimiter::default();
+        let ask = hook("{ type: drop }");
+        let running = limiter.acquire(&ask).await.unwrap();
+
+        limiter.retain(std::slice::from_ref(&ask));
+        assert_eq!(limiter.hooks.lock().unwrap().len(), 1);
+        limiter.retain(&[]);
+        assert!(limiter.hooks.lock().unwrap().is_empty());
+        drop(running);
+    }
+
+    #[tokio::test]
+    async fn test_processor_cap() {
+        let limiter = ConcurrencyLimiter::new(Some(1));
+        let first = limiter.acquire_processor().await;
+        let second = tokio::time::timeout(Duration::from_millis(20), limiter.acquire_processor());
+        assert!(second.await.is_err());
+
+        drop(first);
+        let _second = limiter.acquire_processor().await;
+        limiter.set_max_processors(None);
+        let _third = limiter.acquire_processor().await;
+    }
+}
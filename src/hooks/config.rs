@@ -1,9 +1,20 @@
 //! Hook configuration types and loading
 
 use crate::error::{DiscliError, Result};
+use crate::hooks::event::HookEvent;
+use glob::Pattern;
 use regex::Regex;
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use serde::de::{self, DeserializeSeed, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use twilight_gateway::EventTypeFlags;
+use twilight_model::gateway::Intents;
+use twilight_model::guild::Permissions;
+
+/// Channel selector that matches direct messages to the bot
+pub const DM_CHANNEL: &str = "dm";
 
 /// Main hooks configuration container
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -30,37 +41,82 @@ pub struct Settings {
     /// Error handling strategy
     #[serde(default)]
     pub on_error: ErrorStrategy,
+    /// Where `on_error: notify` posts error reports
+    #[serde(default)]
+    pub notify: Option<NotifyTarget>,
     /// Rate limiting configuration
     #[serde(default)]
     pub rate_limit: RateLimitConfig,
+    /// Most processors running at once across all hooks; unlimited if
+    /// unset
+    #[serde(default)]
+    pub max_processors: Option<usize>,
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
             on_error: ErrorStrategy::Log,
+            notify: None,
             rate_limit: RateLimitConfig::default(),
+            max_processors: None,
         }
     }
 }
 
 /// Error handling strategy
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ErrorStrategy {
+    /// Print failed hooks to stderr
+    #[default]
     Log,
+    /// Drop failures silently
     Ignore,
+    /// Print failed hooks and post a report to `settings.notify`
     Notify,
 }
 
-impl Default for ErrorStrategy {
-    fn default() -> Self {
-        ErrorStrategy::Log
-    }
+/// Admin channel or webhook that receives hook error reports
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum NotifyTarget {
+    /// Post to a channel the bot can write in
+    Channel { channel_id: String },
+    /// Post through a webhook
+    Webhook { url: String },
+}
+
+/// What the triggering user sees when a hook fails
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FailureFeedback {
+    /// Nothing
+    #[default]
+    None,
+    /// React to the triggering message
+    React {
+        #[serde(default = "default_failure_emoji")]
+        emoji: String,
+    },
+    /// Reply in the event's channel; the message is a template with the
+    /// event's variables plus `{{hook_id}}` and `{{error}}`
+    Reply {
+        #[serde(default = "default_failure_message")]
+        message: String,
+    },
+}
+
+fn default_failure_emoji() -> String {
+    "❌".to_string()
+}
+
+fn default_failure_message() -> String {
+    "Sorry, something went wrong while handling that. Please try again later.".to_string()
 }
 
 /// Rate limiting configuration
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct RateLimitConfig {
     /// Maximum triggers per user
     #[serde(default = "default_rate_limit")]
@@ -68,9 +124,15 @@ pub struct RateLimitConfig {
     /// Maximum triggers per channel
     #[serde(default = "default_rate_limit")]
     pub per_channel: u32,
+    /// Maximum triggers per server; unlimited if unset
+    #[serde(default)]
+    pub per_guild: Option<u32>,
     /// Time window in seconds
     #[serde(default = "default_window")]
     pub window_seconds: u64,
+    /// What to do when an event is throttled
+    #[serde(default)]
+    pub on_limit: ThrottleResponse,
 }
 
 impl Default for RateLimitConfig {
@@ -78,7 +140,9 @@ impl Default for RateLimitConfig {
         Self {
             per_user: 5,
             per_channel: 10,
+            per_guild: None,
             window_seconds: 60,
+            on_limit: ThrottleResponse::default(),
         }
     }
 }
@@ -86,6 +150,108 @@ impl Default for RateLimitConfig {
 fn default_rate_limit() -> u32 { 5 }
 fn default_window() -> u64 { 60 }
 
+/// Per-hook rate limits
+///
+/// The hook's triggers are counted on their own rather than against the
+/// global limits; fields left unset use the value from
+/// `settings.rate_limit`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct HookRateLimit {
+    /// Maximum triggers of this hook per user
+    #[serde(default)]
+    pub per_user: Option<u32>,
+    /// Maximum triggers of this hook per channel
+    #[serde(default)]
+    pub per_channel: Option<u32>,
+    /// Maximum triggers of this hook per server
+    #[serde(default)]
+    pub per_guild: Option<u32>,
+    /// Time window in seconds
+    #[serde(default)]
+    pub window_seconds: Option<u64>,
+    /// What to do when this hook is throttled
+    #[serde(default)]
+    pub on_limit: Option<ThrottleResponse>,
+}
+
+impl HookRateLimit {
+    /// These limits, with unset fields taken from `global`
+    pub fn resolve(&self, global: &RateLimitConfig) -> RateLimitConfig {
+        RateLimitConfig {
+            per_user: self.per_user.unwrap_or(global.per_user),
+            per_channel: self.per_channel.unwrap_or(global.per_channel),
+            per_guild: self.per_guild.or(global.per_guild),
+            window_seconds: self.window_seconds.unwrap_or(global.window_seconds),
+            on_limit: self.on_limit.clone().unwrap_or_else(|| global.on_limit.clone()),
+        }
+    }
+}
+
+/// Response to a throttled event
+///
+/// Sent at most once per limit and window, so a user hammering a command
+/// gets one notice rather than one per message.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ThrottleResponse {
+    /// Drop the event without telling anyone
+    #[default]
+    Silent,
+    /// React to the triggering message
+    React {
+        #[serde(default = "default_throttle_emoji")]
+        emoji: String,
+    },
+    /// Tell only the throttled user, by DM; bots can't send ephemeral
+    /// messages outside of interactions
+    Ephemeral {
+        /// Notice text; `{{retry_after}}` becomes the seconds to wait
+        #[serde(default = "default_throttle_message")]
+        message: String,
+    },
+}
+
+fn default_throttle_emoji() -> String {
+    "⏳".to_string()
+}
+
+fn default_throttle_message() -> String {
+    "You're doing that too often; try again in {{retry_after}} seconds.".to_string()
+}
+
+/// What happens to an event that arrives while a hook already runs
+/// `max_concurrency` times
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BusyPolicy {
+    /// Wait for a run to finish; events beyond `depth` waiting are dropped
+    Queue {
+        #[serde(default = "default_queue_depth")]
+        depth: usize,
+    },
+    /// Drop the event without telling anyone
+    Drop,
+    /// Reply in the event's channel and drop the event
+    Reply {
+        #[serde(default = "default_busy_message")]
+        message: String,
+    },
+}
+
+impl Default for BusyPolicy {
+    fn default() -> Self {
+        BusyPolicy::Queue {
+            depth: default_queue_depth(),
+        }
+    }
+}
+
+fn default_queue_depth() -> usize { 20 }
+
+fn default_busy_message() -> String {
+    "I'm busy with other requests right now; try again in a moment.".to_string()
+}
+
 /// Individual hook configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct HookConfig {
@@ -97,9 +263,16 @@ pub struct HookConfig {
     /// Whether this hook is active
     #[serde(default = "default_enabled")]
     pub enabled: bool,
-    /// Channel IDs to listen on (as strings for flexibility)
+    /// Gateway event the hook runs on
+    #[serde(default)]
+    pub event: HookEvent,
+    /// Channel IDs to listen on (as strings for flexibility), or `"dm"`
+    /// for direct messages; required for message events, otherwise empty
+    /// means every channel
+    #[serde(default)]
     pub channels: Vec<String>,
     /// Trigger configuration
+    #[serde(default)]
     pub trigger: TriggerConfig,
     /// Path to prompt file (relative to prompts_dir or absolute)
     pub prompt_file: PathBuf,
@@ -111,15 +284,55 @@ pub struct HookConfig {
     /// Processing configuration
     #[serde(default)]
     pub processing: ProcessingConfig,
+    /// Rate limits for this hook alone, instead of the global ones
+    #[serde(default)]
+    pub rate_limit: Option<HookRateLimit>,
+    /// Minimum seconds between two triggers of this hook by the same user
+    #[serde(default)]
+    pub cooldown_seconds: u64,
+    /// What the triggering user sees when the hook fails
+    #[serde(default)]
+    pub on_failure: FailureFeedback,
+    /// Hooks with a higher priority are checked first; equal priorities
+    /// keep file order
+    #[serde(default)]
+    pub priority: i32,
+    /// Whether lower-priority hooks are still checked once this one
+    /// triggers
+    #[serde(rename = "continue", default = "default_continue")]
+    pub continues: bool,
+    /// Only run when no other hook triggered on the event
+    #[serde(default)]
+    pub fallback: bool,
+    /// Most runs of this hook at once; unlimited if unset
+    #[serde(default)]
+    pub max_concurrency: Option<usize>,
+    /// What happens to events while `max_concurrency` runs are busy
+    #[serde(default)]
+    pub on_busy: BusyPolicy,
 }
 
 fn default_enabled() -> bool { true }
 
+fn default_continue() -> bool { true }
+
 /// Trigger configuration
-#[derive(Debug, Clone, Deserialize, Serialize)]
-#[serde(tag = "type", rename_all = "lowercase")]
+///
+/// Text triggers match the event's text: message content, reaction emoji,
+/// thread name or member username. Triggers are tagged by `type`, except
+/// `all`, `any` and `not`, which combine other triggers:
+///
+/// ```yaml
+/// trigger:
+///   all:
+///     - { type: prefix, prefix: "!ask" }
+///     - not: { type: contains, substring: "ignore" }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(remote = "Self", tag = "type", rename_all = "lowercase")]
 pub enum TriggerConfig {
     /// Trigger on any message
+    #[default]
     Any,
     /// Trigger when message starts with prefix
     Prefix { prefix: String },
@@ -127,19 +340,346 @@ pub enum TriggerConfig {
     Contains { substring: String },
     /// Trigger when regex matches
     Regex { pattern: String },
-    /// Trigger when bot is mentioned
+    /// Trigger when the bot, or its managed role, is mentioned
     Mention,
+    /// Trigger on replies to the bot's messages
+    #[serde(rename = "reply_to_bot")]
+    ReplyToBot,
+    /// Trigger on messages in threads the bot started
+    #[serde(rename = "in_bot_thread")]
+    InBotThread,
+    /// Trigger on messages with an attachment whose filename or MIME type
+    /// matches one of the globs, e.g. `*.log` or `image/*`; any attachment
+    /// if there are none
+    #[serde(rename = "has_attachment")]
+    HasAttachment {
+        #[serde(default)]
+        patterns: Vec<String>,
+    },
+    /// Trigger on messages with an embed
+    #[serde(rename = "has_embed")]
+    HasEmbed,
+    /// Trigger on messages with a link to one of the domains (or their
+    /// subdomains); any link if there are none
+    #[serde(rename = "has_link")]
+    HasLink {
+        #[serde(default)]
+        domains: Vec<String>,
+    },
+    /// Trigger when the text is at least this many characters long
+    #[serde(rename = "min_length")]
+    MinLength { length: usize },
+    /// Trigger when the text is at most this many characters long
+    #[serde(rename = "max_length")]
+    MaxLength { length: usize },
+    /// Trigger on messages with a fenced code block, in the language if
+    /// one is given
+    #[serde(rename = "has_code_block")]
+    HasCodeBlock {
+        #[serde(default)]
+        language: Option<String>,
+    },
+    /// Trigger on replies to any message
+    #[serde(rename = "is_reply")]
+    IsReply,
+    /// Trigger when every one of the triggers matches
+    #[serde(skip)]
+    All(Vec<TriggerConfig>),
+    /// Trigger when at least one of the triggers matches
+    #[serde(skip)]
+    AnyOf(Vec<TriggerConfig>),
+    /// Trigger when the trigger doesn't match
+    #[serde(skip)]
+    Not(Box<TriggerConfig>),
+}
+
+/// Values of `type` for triggers that aren't combinations
+const TRIGGER_TYPES: &[&str] = &[
+    "any",
+    "prefix",
+    "contains",
+    "regex",
+    "mention",
+    "reply_to_bot",
+    "in_bot_thread",
+    "has_attachment",
+    "has_embed",
+    "has_link",
+    "min_length",
+    "max_length",
+    "has_code_block",
+    "is_reply",
+];
+
+impl TriggerConfig {
+    /// Whether the trigger needs to know who the bot is
+    pub fn uses_bot_identity(&self) -> bool {
+        self.leaves().iter().any(|leaf| {
+            matches!(
+                leaf,
+                TriggerConfig::Mention | TriggerConfig::ReplyToBot | TriggerConfig::InBotThread
+            )
+        })
+    }
+
+    /// Whether the trigger looks at more of a message than its text, so
+    /// only message events can match it
+    pub fn reads_message(&self) -> bool {
+        self.uses_bot_identity()
+            || self.leaves().iter().any(|leaf| {
+                matches!(
+                    leaf,
+                    TriggerConfig::HasAttachment { .. }
+                        | TriggerConfig::HasEmbed
+                        | TriggerConfig::HasLink { .. }
+                        | TriggerConfig::HasCodeBlock { .. }
+                        | TriggerConfig::IsReply
+                )
+            })
+    }
+
+    /// The `type` of a trigger that isn't a combination
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            TriggerConfig::Any => "any",
+            TriggerConfig::Prefix { .. } => "prefix",
+            TriggerConfig::Contains { .. } => "contains",
+            TriggerConfig::Regex { .. } => "regex",
+            TriggerConfig::Mention => "mention",
+            TriggerConfig::ReplyToBot => "reply_to_bot",
+            TriggerConfig::InBotThread => "in_bot_thread",
+            TriggerConfig::HasAttachment { .. } => "has_attachment",
+            TriggerConfig::HasEmbed => "has_embed",
+            TriggerConfig::HasLink { .. } => "has_link",
+            TriggerConfig::MinLength { .. } => "min_length",
+            TriggerConfig::MaxLength { .. } => "max_length",
+            TriggerConfig::HasCodeBlock { .. } => "has_code_block",
+            TriggerConfig::IsReply => "is_reply",
+            TriggerConfig::All(_) => "all",
+            TriggerConfig::AnyOf(_) => "any",
+            TriggerConfig::Not(_) => "not",
+        }
+    }
+
+    /// The triggers this one combines, or itself if it combines none
+    pub fn leaves(&self) -> Vec<&TriggerConfig> {
+        match self {
+            TriggerConfig::All(triggers) | TriggerConfig::AnyOf(triggers) => {
+                triggers.iter().flat_map(TriggerConfig::leaves).collect()
+            }
+            TriggerConfig::Not(trigger) => trigger.leaves(),
+            leaf => vec![leaf],
+        }
+    }
+
+    /// Whether every message the trigger matches mentions the bot
+    fn implies_mention(&self) -> bool {
+        match self {
+            TriggerConfig::Mention => true,
+            TriggerConfig::All(triggers) => triggers.iter().any(TriggerConfig::implies_mention),
+            TriggerConfig::AnyOf(triggers) => {
+                !triggers.is_empty() && triggers.iter().all(TriggerConfig::implies_mention)
+            }
+            _ => false,
+        }
+    }
+
+    /// Check that no `all` or `any` is empty
+    fn validate(&self) -> std::result::Result<(), String> {
+        match self {
+            TriggerConfig::All(triggers) | TriggerConfig::AnyOf(triggers) => {
+                if triggers.is_empty() {
+                    return Err(format!("has an empty '{}' trigger", self.type_name()));
+                }
+                triggers.iter().try_for_each(TriggerConfig::validate)
+            }
+            TriggerConfig::Not(trigger) => trigger.validate(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TriggerConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_map(TriggerVisitor)
+    }
+}
+
+impl Serialize for TriggerConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = match self {
+            TriggerConfig::All(_) | TriggerConfig::AnyOf(_) | TriggerConfig::Not(_) => {
+                serializer.serialize_map(Some(1))?
+            }
+            leaf => return TriggerConfig::serialize(leaf, serializer),
+        };
+        match self {
+            TriggerConfig::All(triggers) => map.serialize_entry("all", triggers)?,
+            TriggerConfig::AnyOf(triggers) => map.serialize_entry("any", triggers)?,
+            TriggerConfig::Not(trigger) => map.serialize_entry("not", trigger)?,
+            _ => unreachable!(),
+        }
+        map.end()
+    }
+}
+
+/// Reads a trigger, either a combination or one tagged by `type`
+///
+/// Combinations are read in place, so errors inside them keep their path
+/// (e.g. `trigger.all[1].type`); the other keys are collected and handed
+/// to the derived, tagged deserializer.
+struct TriggerVisitor;
+
+impl<'de> Visitor<'de> for TriggerVisitor {
+    type Value = TriggerConfig;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a trigger with a `type`, or an `all`, `any` or `not` of triggers")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Self::Value, A::Error> {
+        let mut fields = serde_yaml::Mapping::new();
+        let mut combination = None;
+        while let Some(key) = map.next_key::<String>()? {
+            let node = match key.as_str() {
+                "all" => TriggerConfig::All(map.next_value()?),
+                "any" => TriggerConfig::AnyOf(map.next_value()?),
+                "not" => TriggerConfig::Not(map.next_value()?),
+                "type" => {
+                    let type_name = map.next_value_seed(TriggerType)?;
+                    fields.insert(key.into(), type_name.into());
+                    continue;
+                }
+                _ => {
+                    let value: serde_yaml::Value = map.next_value()?;
+                    fields.insert(key.into(), value);
+                    continue;
+                }
+            };
+            if combination.replace(node).is_some() {
+                return Err(de::Error::custom(
+                    "a trigger can only have one of `all`, `any` and `not`",
+                ));
+            }
+        }
+        match combination {
+            Some(node) if fields.is_empty() => Ok(node),
+            Some(node) => Err(de::Error::custom(format!(
+                "`{}` can't be combined with other trigger keys; nest them inside it",
+                node.type_name()
+            ))),
+            None => TriggerConfig::deserialize(serde_yaml::Value::Mapping(fields))
+                .map_err(de::Error::custom),
+        }
+    }
+}
+
+/// Reads a trigger's `type`, rejecting unknown ones where they're written
+struct TriggerType;
+
+impl<'de> DeserializeSeed<'de> for TriggerType {
+    type Value = String;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<String, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> Visitor<'de> for TriggerType {
+    type Value = String;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a trigger type")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> std::result::Result<String, E> {
+        if TRIGGER_TYPES.contains(&value) {
+            Ok(value.to_string())
+        } else {
+            Err(E::unknown_variant(value, TRIGGER_TYPES))
+        }
+    }
 }
 
 /// Filter for specific users/roles
-#[derive(Debug, Clone, Deserialize, Serialize)]
+///
+/// Every list that is set must match. Filters that can't be decided for an
+/// event, such as roles when the member is unknown, don't match.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct FilterConfig {
+    /// Only trigger in these servers
+    #[serde(default)]
+    pub guilds: Vec<String>,
+    /// Only trigger in channels under these category IDs
+    #[serde(default)]
+    pub categories: Vec<String>,
     /// Only trigger for these user IDs
     #[serde(default)]
     pub users: Vec<String>,
-    /// Only trigger for users with these role IDs
+    /// Never trigger for these user IDs
+    #[serde(default)]
+    pub exclude_users: Vec<String>,
+    /// Only trigger for users with at least one of these role IDs
     #[serde(default)]
     pub roles: Vec<String>,
+    /// Never trigger for users with any of these role IDs
+    #[serde(default)]
+    pub exclude_roles: Vec<String>,
+    /// Whether bot accounts may trigger the hook
+    #[serde(default)]
+    pub bots: BotPolicy,
+    /// Only trigger for users with all of these permissions in the
+    /// channel, e.g. `MANAGE_MESSAGES`
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    /// Only trigger for these emoji (reaction events): the emoji itself or
+    /// a custom emoji's name
+    #[serde(default)]
+    pub emojis: Vec<String>,
+    /// Only trigger for these statuses (presence updates): online, idle,
+    /// dnd or offline
+    #[serde(default)]
+    pub statuses: Vec<String>,
+}
+
+impl FilterConfig {
+    /// Whether the filter checks the member's roles
+    pub fn uses_roles(&self) -> bool {
+        !self.roles.is_empty() || !self.exclude_roles.is_empty()
+    }
+
+    /// The `permissions` list as a permission set
+    ///
+    /// # Errors
+    ///
+    /// Returns a config error naming the first unknown permission
+    pub fn required_permissions(&self) -> Result<Permissions> {
+        parse_permissions(&self.permissions)
+    }
+}
+
+/// Parse permission names such as `MANAGE_MESSAGES` into a permission set
+///
+/// # Errors
+///
+/// Returns a config error naming the first unknown permission
+pub fn parse_permissions(names: &[String]) -> Result<Permissions> {
+    names.iter().try_fold(Permissions::empty(), |acc, name| {
+        Permissions::from_name(&name.to_uppercase())
+            .map(|permission| acc | permission)
+            .ok_or_else(|| DiscliError::Config(format!("unknown permission '{}'", name)))
+    })
+}
+
+/// Whether bot accounts may trigger a hook
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BotPolicy {
+    /// Bots trigger hooks like anyone else
+    #[default]
+    Allow,
+    /// Ignore events caused by bots
+    Deny,
 }
 
 /// Action to perform when hook triggers
@@ -149,6 +689,7 @@ pub enum HookAction {
     /// Reply in the channel
     Reply,
     /// Send DM to the user
+    #[serde(rename = "send_dm", alias = "senddm")]
     SendDm,
     /// Forward to another channel
     Forward { channel_id: String },
@@ -156,6 +697,29 @@ pub enum HookAction {
     Webhook { url: String },
 }
 
+impl HookAction {
+    /// Action type as written in `hooks.yaml`
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookAction::Reply => "reply",
+            HookAction::SendDm => "send_dm",
+            HookAction::Forward { .. } => "forward",
+            HookAction::Webhook { .. } => "webhook",
+        }
+    }
+
+    /// Every action type, as written in `hooks.yaml`
+    pub fn names() -> [&'static str; 4] {
+        [
+            HookAction::Reply,
+            HookAction::SendDm,
+            HookAction::Forward { channel_id: String::new() },
+            HookAction::Webhook { url: String::new() },
+        ]
+        .map(|action| action.name())
+    }
+}
+
 /// Processing configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ProcessingConfig {
@@ -202,31 +766,6 @@ pub enum Processor {
     },
 }
 
-impl Processor {
-    pub fn from_yaml(type_str: &str, map: &serde_yaml::Value) -> Self {
-        eprintln!("[DEBUG] Processor::from_yaml type={}, map={:?}", type_str, map);
-        match type_str {
-            "command" => {
-                if let Some(cmd) = map.get("cmd") {
-                    if let Ok(cmd_vec) = serde_yaml::from_value::<Vec<String>>(cmd.clone()) {
-                        return Processor::Command { cmd: cmd_vec };
-                    }
-                }
-                Processor::Command { cmd: vec![] }
-            }
-            "http" => {
-                if let Some(url) = map.get("url") {
-                    if let Ok(url_str) = serde_yaml::from_value::<String>(url.clone()) {
-                        return Processor::Http { url: url_str };
-                    }
-                }
-                Processor::Http { url: String::new() }
-            }
-            _ => Processor::Command { cmd: vec![] },
-        }
-    }
-}
-
 impl Default for Processor {
     fn default() -> Self {
         Processor::Command { cmd: vec![] }
@@ -235,7 +774,7 @@ impl Default for Processor {
 
 impl HooksConfig {
     /// Load hooks configuration from a YAML file
-    pub fn load(path: &PathBuf) -> Result<Self> {
+    pub fn load(path: &Path) -> Result<Self> {
         let content = std::fs::read_to_string(path)
             .map_err(|e| DiscliError::Config(format!("Failed to read hooks file: {}", e)))?;
         
@@ -252,18 +791,58 @@ impl HooksConfig {
     pub fn enabled_hooks(&self) -> Vec<&HookConfig> {
         self.hooks.iter().filter(|h| h.enabled).collect()
     }
+
+    /// Compile every enabled hook
+    ///
+    /// # Errors
+    ///
+    /// Returns a configuration error naming the first hook that fails
+    pub fn compile_enabled(&self) -> Result<Vec<CompiledHookConfig>> {
+        self.enabled_hooks()
+            .into_iter()
+            .map(|hook| {
+                hook.compile().map_err(|e| {
+                    DiscliError::Config(format!("Failed to compile hook {}: {}", hook.id, e))
+                })
+            })
+            .collect()
+    }
+
+    /// Gateway event types the enabled hooks run on
+    pub fn required_event_types(&self) -> EventTypeFlags {
+        self.enabled_hooks()
+            .into_iter()
+            .fold(EventTypeFlags::empty(), |flags, hook| flags | hook.event.event_type())
+    }
+
+    /// Gateway intents needed to deliver events to the enabled hooks
+    ///
+    /// Only what the hooks use is requested, so a bot whose hooks never
+    /// look at guild message content does not need the privileged
+    /// Message Content intent.
+    pub fn required_intents(&self) -> Intents {
+        self.enabled_hooks()
+            .into_iter()
+            .fold(Intents::empty(), |intents, hook| intents | hook.required_intents())
+    }
     
     fn validate(&self) -> std::result::Result<(), DiscliError> {
         if self.hooks.is_empty() {
             return Err(DiscliError::Config("No hooks defined".into()));
         }
+        if self.settings.on_error == ErrorStrategy::Notify && self.settings.notify.is_none() {
+            return Err(DiscliError::Config(
+                "settings.on_error is 'notify', but settings.notify names no channel or webhook".into(),
+            ));
+        }
+        if self.settings.max_processors == Some(0) {
+            return Err(DiscliError::Config(
+                "settings.max_processors is 0, so no hook could run".into(),
+            ));
+        }
         
         for hook in &self.hooks {
-            if hook.channels.is_empty() {
-                return Err(DiscliError::Config(format!(
-                    "Hook '{}' has no channels defined", hook.id
-                )));
-            }
+            hook.validate()?;
         }
         
         Ok(())
@@ -271,25 +850,124 @@ impl HooksConfig {
 }
 
 impl HookConfig {
-    /// Compile regex patterns if needed
-    pub fn compile(&self) -> Result<CompiledHookConfig> {
-        let trigger = match &self.trigger {
-            TriggerConfig::Regex { pattern } => {
-                CompiledTrigger::Regex(Regex::new(pattern)
-                    .map_err(|e| DiscliError::Config(format!("Invalid regex: {}", e)))?)
+    /// Gateway intents needed to deliver this hook's events
+    ///
+    /// Direct messages and messages mentioning the bot always carry their
+    /// content, so Message Content is only needed for guild channels with
+    /// a trigger that reads it.
+    pub fn required_intents(&self) -> Intents {
+        let dm = self.channels.iter().any(|c| c == DM_CHANNEL);
+        let guild = self.channels.is_empty() || self.channels.iter().any(|c| c != DM_CHANNEL);
+        let either = |dm_intent: Intents, guild_intent: Intents| {
+            let mut intents = Intents::empty();
+            if dm {
+                intents |= dm_intent;
+            }
+            if guild {
+                intents |= guild_intent;
             }
-            other => CompiledTrigger::from(other.clone()),
+            intents
         };
-        
+
+        match self.event {
+            HookEvent::MessageCreate | HookEvent::MessageUpdate => {
+                let mut intents = either(Intents::DIRECT_MESSAGES, Intents::GUILD_MESSAGES);
+                if guild && !self.trigger.implies_mention() {
+                    intents |= Intents::MESSAGE_CONTENT;
+                }
+                intents
+            }
+            HookEvent::MessageDelete => either(Intents::DIRECT_MESSAGES, Intents::GUILD_MESSAGES),
+            HookEvent::ReactionAdd | HookEvent::ReactionRemove => either(
+                Intents::DIRECT_MESSAGE_REACTIONS,
+                Intents::GUILD_MESSAGE_REACTIONS,
+            ),
+            HookEvent::MemberJoin | HookEvent::MemberLeave => Intents::GUILD_MEMBERS,
+            HookEvent::ThreadCreate => Intents::GUILDS,
+            HookEvent::PresenceUpdate => Intents::GUILD_PRESENCES,
+            HookEvent::VoiceStateUpdate => Intents::GUILD_VOICE_STATES,
+        }
+    }
+
+    /// Check that the channels, trigger, filter and action fit the event
+    pub(crate) fn validate(&self) -> Result<()> {
+        let invalid = |problem: String| {
+            Err(DiscliError::Config(format!("Hook '{}' {}", self.id, problem)))
+        };
+
+        if self.channels.is_empty() && self.event.is_message() {
+            return invalid("has no channels defined".into());
+        }
+        if let Err(problem) = self.trigger.validate() {
+            return invalid(problem);
+        }
+        if self.max_concurrency == Some(0) {
+            return invalid("has max_concurrency 0, so it could never run".into());
+        }
+        for leaf in self.trigger.leaves() {
+            let trigger = leaf.type_name();
+            if leaf.reads_message() {
+                if !matches!(self.event, HookEvent::MessageCreate | HookEvent::MessageUpdate) {
+                    return invalid(format!(
+                        "uses a '{}' trigger, which only message events can match",
+                        trigger
+                    ));
+                }
+            } else if !matches!(leaf, TriggerConfig::Any) && !self.event.has_text() {
+                return invalid(format!(
+                    "uses a '{}' trigger, but {} events have no text to match; use 'any'",
+                    trigger, self.event
+                ));
+            }
+        }
+        if let Some(filter) = &self.filter {
+            if !filter.emojis.is_empty()
+                && !matches!(self.event, HookEvent::ReactionAdd | HookEvent::ReactionRemove)
+            {
+                return invalid("filters on emojis, which only reaction events have".into());
+            }
+            if !filter.statuses.is_empty() && self.event != HookEvent::PresenceUpdate {
+                return invalid("filters on statuses, which only presence_update events have".into());
+            }
+            if let Err(DiscliError::Config(e)) = filter.required_permissions() {
+                return invalid(format!("filters on an {}", e));
+            }
+        }
+        match self.action {
+            HookAction::Reply if !self.event.has_reply_channel() => invalid(format!(
+                "replies, but {} events have no channel to reply in; use 'forward' or 'send_dm'",
+                self.event
+            )),
+            HookAction::SendDm if !self.event.has_user() => invalid(format!(
+                "sends a DM, but {} events do not say who caused them",
+                self.event
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Compile regex patterns if needed
+    pub fn compile(&self) -> Result<CompiledHookConfig> {
+        let trigger = CompiledTrigger::compile(&self.trigger)?;
+
         Ok(CompiledHookConfig {
             id: self.id.clone(),
             name: self.name.clone(),
+            event: self.event,
             channels: self.channels.clone(),
             trigger,
             prompt_file: self.prompt_file.clone(),
             filter: self.filter.clone(),
             action: self.action.clone(),
             processing: self.processing.clone(),
+            rate_limit: self.rate_limit.clone(),
+            cooldown_seconds: self.cooldown_seconds,
+            on_failure: self.on_failure.clone(),
+            priority: self.priority,
+            continues: self.continues,
+            fallback: self.fallback,
+            max_concurrency: self.max_concurrency,
+            on_busy: self.on_busy.clone(),
         })
     }
 }
@@ -298,12 +976,21 @@ impl HookConfig {
 pub struct CompiledHookConfig {
     pub id: String,
     pub name: String,
+    pub event: HookEvent,
     pub channels: Vec<String>,
     pub trigger: CompiledTrigger,
     pub prompt_file: PathBuf,
     pub filter: Option<FilterConfig>,
     pub action: HookAction,
     pub processing: ProcessingConfig,
+    pub rate_limit: Option<HookRateLimit>,
+    pub cooldown_seconds: u64,
+    pub on_failure: FailureFeedback,
+    pub priority: i32,
+    pub continues: bool,
+    pub fallback: bool,
+    pub max_concurrency: Option<usize>,
+    pub on_busy: BusyPolicy,
 }
 
 /// Trigger with compiled regex
@@ -314,19 +1001,278 @@ pub enum CompiledTrigger {
     Contains(String),
     Regex(Regex),
     Mention,
+    ReplyToBot,
+    InBotThread,
+    HasAttachment(Vec<Pattern>),
+    HasEmbed,
+    HasLink(Vec<String>),
+    MinLength(usize),
+    MaxLength(usize),
+    HasCodeBlock(Option<String>),
+    IsReply,
+    All(Vec<CompiledTrigger>),
+    AnyOf(Vec<CompiledTrigger>),
+    Not(Box<CompiledTrigger>),
 }
 
-impl From<TriggerConfig> for CompiledTrigger {
-    fn from(config: TriggerConfig) -> Self {
-        match config {
+impl CompiledTrigger {
+    /// Compile a trigger and every trigger it combines
+    pub fn compile(config: &TriggerConfig) -> Result<Self> {
+        let compile_all = |triggers: &[TriggerConfig]| {
+            triggers.iter().map(CompiledTrigger::compile).collect::<Result<Vec<_>>>()
+        };
+        Ok(match config {
+            TriggerConfig::Regex { pattern } => CompiledTrigger::Regex(
+                Regex::new(pattern)
+                    .map_err(|e| DiscliError::Config(format!("Invalid regex: {}", e)))?,
+            ),
+            TriggerConfig::HasAttachment { patterns } => CompiledTrigger::HasAttachment(
+                patterns
+                    .iter()
+                    .map(|p| {
+                        Pattern::new(p).map_err(|e| {
+                            DiscliError::Config(format!("Invalid attachment pattern '{}': {}", p, e))
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            TriggerConfig::All(triggers) => CompiledTrigger::All(compile_all(triggers)?),
+            TriggerConfig::AnyOf(triggers) => CompiledTrigger::AnyOf(compile_all(triggers)?),
+            TriggerConfig::Not(trigger) => CompiledTrigger::Not(Box::new(Self::compile(trigger)?)),
             TriggerConfig::Any => CompiledTrigger::Any,
-            TriggerConfig::Prefix { prefix } => CompiledTrigger::Prefix(prefix),
-            TriggerConfig::Contains { substring } => CompiledTrigger::Contains(substring),
-            TriggerConfig::Regex { pattern } => {
-                // Note: This will panic if regex is invalid - use compile() instead
-                CompiledTrigger::Regex(Regex::new(&pattern).unwrap())
-            }
+            TriggerConfig::Prefix { prefix } => CompiledTrigger::Prefix(prefix.clone()),
+            TriggerConfig::Contains { substring } => CompiledTrigger::Contains(substring.clone()),
             TriggerConfig::Mention => CompiledTrigger::Mention,
+            TriggerConfig::ReplyToBot => CompiledTrigger::ReplyToBot,
+            TriggerConfig::InBotThread => CompiledTrigger::InBotThread,
+            TriggerConfig::HasEmbed => CompiledTrigger::HasEmbed,
+            TriggerConfig::HasLink { domains } => CompiledTrigger::HasLink(domains.clone()),
+            TriggerConfig::MinLength { length } => CompiledTrigger::MinLength(*length),
+            TriggerConfig::MaxLength { length } => CompiledTrigger::MaxLength(*length),
+            TriggerConfig::HasCodeBlock { language } => {
+                CompiledTrigger::HasCodeBlock(language.clone())
+            }
+            TriggerConfig::IsReply => CompiledTrigger::IsReply,
+        })
+    }
+
+    /// The triggers this one combines, if it is a combination
+    pub fn branches(&self) -> &[CompiledTrigger] {
+        match self {
+            CompiledTrigger::All(triggers) | CompiledTrigger::AnyOf(triggers) => triggers,
+            CompiledTrigger::Not(trigger) => std::slice::from_ref(trigger),
+            _ => &[],
         }
     }
 }
+
+impl fmt::Display for CompiledTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let combination = match self {
+            CompiledTrigger::Any => return f.write_str("any"),
+            CompiledTrigger::Prefix(prefix) => return write!(f, "prefix {:?}", prefix),
+            CompiledTrigger::Contains(substring) => return write!(f, "contains {:?}", substring),
+            CompiledTrigger::Regex(re) => return write!(f, "regex /{}/", re.as_str()),
+            CompiledTrigger::Mention => return f.write_str("mention"),
+            CompiledTrigger::ReplyToBot => return f.write_str("reply_to_bot"),
+            CompiledTrigger::InBotThread => return f.write_str("in_bot_thread"),
+            CompiledTrigger::HasAttachment(patterns) if patterns.is_empty() => {
+                return f.write_str("has_attachment")
+            }
+            CompiledTrigger::HasAttachment(patterns) => {
+                let patterns: Vec<&str> = patterns.iter().map(Pattern::as_str).collect();
+                return write!(f, "has_attachment {:?}", patterns);
+            }
+            CompiledTrigger::HasEmbed => return f.write_str("has_embed"),
+            CompiledTrigger::HasLink(domains) if domains.is_empty() => return f.write_str("has_link"),
+            CompiledTrigger::HasLink(domains) => return write!(f, "has_link {:?}", domains),
+            CompiledTrigger::MinLength(length) => return write!(f, "min_length {}", length),
+            CompiledTrigger::MaxLength(length) => return write!(f, "max_length {}", length),
+            CompiledTrigger::HasCodeBlock(None) => return f.write_str("has_code_block"),
+            CompiledTrigger::HasCodeBlock(Some(language)) => {
+                return write!(f, "has_code_block {:?}", language)
+            }
+            CompiledTrigger::IsReply => return f.write_str("is_reply"),
+            CompiledTrigger::All(_) => "all",
+            CompiledTrigger::AnyOf(_) => "any",
+            CompiledTrigger::Not(_) => "not",
+        };
+        write!(f, "{}(", combination)?;
+        for (i, branch) in self.branches().iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", branch)?;
+        }
+        f.write_str(")")
+    }
+}
+
+impl TryFrom<&TriggerConfig> for CompiledTrigger {
+    type Error = DiscliError;
+
+    fn try_from(config: &TriggerConfig) -> Result<Self> {
+        Self::compile(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hooks(yaml: &str) -> HooksConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_action_names_parse() {
+        for name in HookAction::names() {
+            let yaml = format!("{{ type: {}, channel_id: \"1\", url: \"https://x.test\" }}", name);
+            let action: HookAction = serde_yaml::from_str(&yaml).unwrap();
+            assert_eq!(action.name(), name);
+        }
+    }
+
+    #[test]
+    fn test_required_intents_guild_and_dm() {
+        let config = hooks(
+            r#"
+version: "1.0"
+hooks:
+  - id: "guild"
+    channels: ["123"]
+    trigger: { type: "prefix", prefix: "!" }
+    prompt_file: "p.txt"
+    action: { type: "reply" }
+  - id: "dm"
+    channels: ["dm"]
+    trigger: { type: "any" }
+    prompt_file: "p.txt"
+    action: { type: "send_dm" }
+"#,
+        );
+
+        assert_eq!(
+            config.required_intents(),
+            Intents::GUILD_MESSAGES | Intents::MESSAGE_CONTENT | Intents::DIRECT_MESSAGES
+        );
+        assert!(matches!(config.hooks[1].action, HookAction::SendDm));
+    }
+
+    #[test]
+    fn test_required_intents_skip_disabled_and_mentions() {
+        let config = hooks(
+            r#"
+version: "1.0"
+hooks:
+  - id: "mention"
+    channels: ["123"]
+    trigger: { type: "mention" }
+    prompt_file: "p.txt"
+    action: { type: "reply" }
+  - id: "disabled"
+    enabled: false
+    channels: ["dm"]
+    trigger: { type: "any" }
+    prompt_file: "p.txt"
+    action: { type: "reply" }
+"#,
+        );
+
+        assert_eq!(config.required_intents(), Intents::GUILD_MESSAGES);
+    }
+
+    #[test]
+    fn test_event_hooks_intents_and_event_types() {
+        let config = hooks(
+            r#"
+version: "1.0"
+hooks:
+  - id: "welcome"
+    event: "member_join"
+    prompt_file: "welcome.txt"
+    action: { type: "forward", channel_id: "123" }
+  - id: "approve"
+    event: "reaction_add"
+    channels: ["123", "dm"]
+    filter: { emojis: ["✅"] }
+    prompt_file: "p.txt"
+    action: { type: "reply" }
+"#,
+        );
+        config.validate().unwrap();
+
+        assert_eq!(
+            config.required_intents(),
+            Intents::GUILD_MEMBERS
+                | Intents::GUILD_MESSAGE_REACTIONS
+                | Intents::DIRECT_MESSAGE_REACTIONS
+        );
+        assert_eq!(
+            config.required_event_types(),
+            EventTypeFlags::MEMBER_ADD | EventTypeFlags::REACTION_ADD
+        );
+    }
+
+    #[test]
+    fn test_combined_trigger_yaml() {
+        let parse = |yaml: &str| serde_yaml::from_str::<TriggerConfig>(yaml).map_err(|e| e.to_string());
+
+        let trigger = parse(r#"{ all: [{ type: mention }, { not: { any: [{ type: regex, pattern: "^!" }] } }] }"#)
+            .unwrap();
+        assert!(trigger.uses_bot_identity());
+        assert!(trigger.implies_mention());
+        assert_eq!(trigger.leaves().len(), 2);
+        let yaml = serde_yaml::to_string(&trigger).unwrap();
+        assert_eq!(serde_yaml::to_string(&parse(&yaml).unwrap()).unwrap(), yaml);
+        assert!(yaml.starts_with("all:\n- type: mention\n- not:\n    any:\n"));
+
+        assert!(parse("{ all: [{ type: prefixx }] }")
+            .unwrap_err()
+            .starts_with("all[0].type: unknown variant `prefixx`"));
+        assert!(parse("{ type: prefix, all: [] }").unwrap_err().contains("`all` can't be combined"));
+        assert!(parse("{ all: [], not: { type: any } }").unwrap_err().contains("only have one of"));
+        assert_eq!(parse("{ type: prefix }").unwrap_err(), "missing field `prefix`");
+    }
+
+    #[test]
+    fn test_validate_rejects_mismatched_event_settings() {
+        let error = |hook: &str| {
+            hooks(&format!("version: \"1.0\"\nhooks:\n  - {}", hook))
+                .validate()
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(error(r#"{ id: "a", prompt_file: "p", action: { type: "reply" } }"#)
+            .contains("no channels"));
+        assert!(error(
+            r#"{ id: "b", event: "member_join", prompt_file: "p", action: { type: "reply" } }"#
+        )
+        .contains("no channel to reply in"));
+        assert!(error(
+            r#"{ id: "c", event: "presence_update", trigger: { type: "prefix", prefix: "!" }, prompt_file: "p", action: { type: "send_dm" } }"#
+        )
+        .contains("no text to match"));
+        assert!(error(
+            r#"{ id: "d", event: "message_delete", channels: ["1"], prompt_file: "p", action: { type: "send_dm" } }"#
+        )
+        .contains("do not say who caused them"));
+        assert!(error(
+            r#"{ id: "e", channels: ["1"], filter: { statuses: ["online"] }, prompt_file: "p", action: { type: "reply" } }"#
+        )
+        .contains("statuses"));
+        assert!(error(
+            r#"{ id: "f", channels: ["1"], trigger: { any: [] }, prompt_file: "p", action: { type: "reply" } }"#
+        )
+        .contains("empty 'any' trigger"));
+        assert!(error(
+            r#"{ id: "g", event: "reaction_add", channels: ["1"], trigger: { not: { type: "mention" } }, prompt_file: "p", action: { type: "reply" } }"#
+        )
+        .contains("'mention' trigger"));
+        assert!(error(
+            r#"{ id: "h", channels: ["1"], max_concurrency: 0, prompt_file: "p", action: { type: "reply" } }"#
+        )
+        .contains("max_concurrency 0"));
+    }
+}
@@ -0,0 +1,364 @@
+//! Guild context for hook filters
+//!
+//! Gateway events don't carry everything filters and triggers can check:
+//! the channel's category, the member's permissions, for some events the
+//! member's roles, and who the bot itself is. [`GuildCache`] looks these
+//! up over REST and caches them, so a busy channel doesn't cost a request
+//! per message; the bot's user comes from the gateway's READY event.
+
+use crate::discord::permissions::compute_channel_permissions;
+use crate::discord::types::{ChannelInfo, GuildInfo};
+use crate::discord::DiscordClient;
+use crate::error::Result;
+use crate::hooks::config::{CompiledHookConfig, CompiledTrigger};
+use crate::hooks::event::IncomingEvent;
+use crate::hooks::trigger::matches_channels;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use twilight_model::guild::Permissions;
+
+/// How long channels and guilds (with their roles) are cached
+const GUILD_TTL: Duration = Duration::from_secs(300);
+
+/// How long a member's roles are cached
+const MEMBER_TTL: Duration = Duration::from_secs(60);
+
+/// Facts about an event that filters need but the event doesn't carry
+///
+/// Fields are `None` when unknown; filters and triggers that need them
+/// then don't match.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventContext {
+    /// Role IDs of the member who caused the event
+    pub roles: Option<Vec<String>>,
+    /// Category the event's channel is in
+    pub category: Option<String>,
+    /// The member's effective permissions in the event's channel
+    pub permissions: Option<Permissions>,
+    /// The bot itself
+    pub bot: Option<BotIdentity>,
+    /// User who started the thread the event happened in
+    pub thread_owner: Option<String>,
+}
+
+/// Who the bot is, for mention and reply triggers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotIdentity {
+    /// The bot's user ID
+    pub user_id: String,
+    /// The bot's managed role in the event's server, if known
+    pub role_id: Option<String>,
+}
+
+/// Which parts of an [`EventContext`] the hooks for an event need
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextNeeds {
+    /// Member roles, if the event doesn't include them
+    pub roles: bool,
+    /// The channel's category
+    pub category: bool,
+    /// The member's channel permissions
+    pub permissions: bool,
+    /// Who the bot is
+    pub bot: bool,
+    /// Who started the event's thread
+    pub thread_owner: bool,
+}
+
+impl ContextNeeds {
+    /// What the hooks that could run on `event` need to look up
+    pub fn for_event<'a>(
+        hooks: impl IntoIterator<Item = &'a CompiledHookConfig>,
+        event: &IncomingEvent,
+    ) -> Self {
+        let mut needs = Self::default();
+        let candidates: Vec<&CompiledHookConfig> = hooks
+            .into_iter()
+            .filter(|hook| hook.event == event.kind() && matches_channels(event, &hook.channels))
+            .collect();
+        for hook in &candidates {
+            needs.add_trigger(&hook.trigger);
+        }
+        for filter in candidates.iter().filter_map(|hook| hook.filter.as_ref()) {
+            needs.roles |= filter.uses_roles() && event.member_roles().is_none();
+            needs.category |= !filter.categories.is_empty();
+            needs.permissions |= !filter.permissions.is_empty();
+        }
+        needs
+    }
+
+    /// Add what `trigger`, or any trigger it combines, needs
+    fn add_trigger(&mut self, trigger: &CompiledTrigger) {
+        match trigger {
+            CompiledTrigger::Mention | CompiledTrigger::ReplyToBot => self.bot = true,
+            CompiledTrigger::InBotThread => {
+                self.bot = true;
+                self.thread_owner = true;
+            }
+            combination => {
+                for branch in combination.branches() {
+                    self.add_trigger(branch);
+                }
+            }
+        }
+    }
+
+    /// Whether anything needs looking up
+    pub fn any(&self) -> bool {
+        self.roles || self.category || self.permissions || self.bot || self.thread_owner
+    }
+}
+
+/// A cached value and when it was fetched
+struct Cached<T> {
+    value: T,
+    fetched: Instant,
+}
+
+/// Map from key to cached value, with expiry
+struct TtlCache<K, V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<K, Cached<V>>>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> TtlCache<K, V> {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The cached value for `key`, fetching it if missing or expired
+    async fn get_or_fetch<F>(&self, key: K, fetch: F) -> Result<V>
+    where
+        F: Future<Output = Result<V>>,
+    {
+        {
+            let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(cached) = entries.get(&key) {
+                if cached.fetched.elapsed() < self.ttl {
+                    return Ok(cached.value.clone());
+                }
+            }
+        }
+        let value = fetch.await?;
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, cached| cached.fetched.elapsed() < self.ttl);
+        entries.insert(
+            key,
+            Cached {
+                value: value.clone(),
+                fetched: Instant::now(),
+            },
+        );
+        Ok(value)
+    }
+}
+
+/// Looks up and caches channels, guilds and member roles over REST
+pub struct GuildCache {
+    client: DiscordClient,
+    bot_user: RwLock<Option<String>>,
+    channels: TtlCache<String, ChannelInfo>,
+    guilds: TtlCache<String, GuildInfo>,
+    members: TtlCache<(String, String), Vec<String>>,
+}
+
+impl GuildCache {
+    /// Create a cache that fetches through `client`
+    pub fn new(client: DiscordClient) -> Self {
+        Self {
+            client,
+            bot_user: RwLock::new(None),
+            channels: TtlCache::new(GUILD_TTL),
+            guilds: TtlCache::new(GUILD_TTL),
+            members: TtlCache::new(MEMBER_TTL),
+        }
+    }
+
+    /// Remember the bot's user ID, from the gateway's READY event
+    pub fn set_bot_user(&self, user_id: String) {
+        *self.bot_user.write().unwrap_or_else(|e| e.into_inner()) = Some(user_id);
+    }
+
+    /// The bot's user ID, once READY has arrived
+    pub fn bot_user(&self) -> Option<String> {
+        self.bot_user.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Look up what `needs` asks for about `event`
+    ///
+    /// Lookups that fail are warned about and left unknown.
+    pub async fn resolve(&self, event: &IncomingEvent, needs: ContextNeeds) -> EventContext {
+        let mut context = EventContext::default();
+        if needs.bot {
+            context.bot = self.bot_user().map(|user_id| BotIdentity {
+                user_id,
+                role_id: None,
+            });
+        }
+        let (Some(guild_id), Some(channel_id)) = (event.guild_id(), event.channel_id()) else {
+            // Direct messages have no roles, categories, permissions or
+            // managed roles
+            return context;
+        };
+        let guild_id = guild_id.to_string();
+
+        // Only role mentions need the bot's managed role
+        let role_mentioned = event.message().is_some_and(|m| !m.mention_roles.is_empty());
+        if let Some(bot) = context.bot.as_mut().filter(|_| role_mentioned) {
+            if let Some(guild) = self.guild(&guild_id).await {
+                bot.role_id = guild
+                    .roles
+                    .iter()
+                    .find(|role| {
+                        role.tags.as_ref().and_then(|t| t.bot_id.as_ref()) == Some(&bot.user_id)
+                    })
+                    .map(|role| role.id.clone());
+            }
+        }
+
+        let roles = match event.member_roles() {
+            Some(roles) => Some(roles),
+            None if needs.roles || needs.permissions => match event.user_id() {
+                Some(user_id) => self.member_roles(&guild_id, &user_id.to_string()).await,
+                None => None,
+            },
+            None => None,
+        };
+        if needs.roles {
+            context.roles = roles.clone();
+        }
+
+        if !needs.category && !needs.permissions && !needs.thread_owner {
+            return context;
+        }
+        // Threads are filed under, and take overwrites from, their parent
+        let mut channel = self.channel(&channel_id.to_string()).await;
+        if let Some(thread) = channel.as_ref().filter(|c| c.is_thread()) {
+            if needs.thread_owner {
+                context.thread_owner = thread.owner_id.clone();
+            }
+            channel = match &thread.parent_id {
+                Some(parent_id) => self.channel(parent_id).await,
+                None => None,
+            };
+        }
+        let Some(channel) = channel else {
+            return context;
+        };
+        if needs.category {
+            context.category = channel.parent_id.clone();
+        }
+        if needs.permissions {
+            if let (Some(guild), Some(roles), Some(user_id)) =
+                (self.guild(&guild_id).await, &roles, event.user_id())
+            {
+                context.permissions = Some(compute_channel_permissions(
+                    &guild,
+                    &channel,
+                    &user_id.to_string(),
+                    roles,
+                ));
+            }
+        }
+        context
+    }
+
+    async fn channel(&self, channel_id: &str) -> Option<ChannelInfo> {
+        let fetched = self
+            .channels
+            .get_or_fetch(channel_id.to_string(), self.client.get_channel(channel_id))
+            .await;
+        warn_on_error(fetched, || format!("channel {}", channel_id))
+    }
+
+    async fn guild(&self, guild_id: &str) -> Option<GuildInfo> {
+        let fetched = self
+            .guilds
+            .get_or_fetch(guild_id.to_string(), self.client.get_guild(guild_id))
+            .await;
+        warn_on_error(fetched, || format!("server {}", guild_id))
+    }
+
+    async fn member_roles(&self, guild_id: &str, user_id: &str) -> Option<Vec<String>> {
+        let fetch = async {
+            let member = self.client.get_guild_member(guild_id, user_id).await?;
+            Ok(member.roles)
+        };
+        let fetched = self
+            .members
+            .get_or_fetch((guild_id.to_string(), user_id.to_string()), fetch)
+            .await;
+        warn_on_error(fetched, || format!("member {} of server {}", user_id, guild_id))
+    }
+}
+
+fn warn_on_error<T>(result: Result<T>, what: impl FnOnce() -> String) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("Warning: Failed to look up {} for hook filters: {}", what(), e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::config::HooksConfig;
+    use crate::hooks::testing::test_message;
+
+    #[test]
+    fn test_needs_only_what_matching_hooks_use() {
+        let config: HooksConfig = serde_yaml::from_str(
+            r#"
+version: "1.0"
+hooks:
+  - id: "mods"
+    channels: ["10"]
+    prompt_file: "p.txt"
+    action: { type: "reply" }
+    filter: { roles: ["5"], categories: ["7"] }
+  - id: "elsewhere"
+    channels: ["11"]
+    prompt_file: "p.txt"
+    action: { type: "reply" }
+    filter: { permissions: ["MANAGE_MESSAGES"] }
+"#,
+        )
+        .unwrap();
+        let hooks = config.compile_enabled().unwrap();
+        let message = |guild: Option<&str>| test_message("10", guild, "hi").to_event().unwrap();
+
+        // Guild messages carry the member's roles
+        let needs = ContextNeeds::for_event(&hooks, &message(Some("3")));
+        assert_eq!(
+            needs,
+            ContextNeeds {
+                category: true,
+                ..ContextNeeds::default()
+            }
+        );
+
+        let needs = ContextNeeds::for_event(&hooks, &message(None));
+        assert!(needs.roles);
+        assert!(!needs.permissions);
+    }
+
+    #[tokio::test]
+    async fn test_ttl_cache_fetches_once() {
+        let cache: TtlCache<&str, u32> = TtlCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get_or_fetch("a", async { Ok(1) }).await.unwrap(), 1);
+        assert_eq!(cache.get_or_fetch("a", async { Ok(2) }).await.unwrap(), 1);
+
+        let expired: TtlCache<&str, u32> = TtlCache::new(Duration::ZERO);
+        expired.get_or_fetch("a", async { Ok(1) }).await.unwrap();
+        assert_eq!(expired.get_or_fetch("a", async { Ok(2) }).await.unwrap(), 2);
+    }
+}
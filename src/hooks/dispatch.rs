@@ -0,0 +1,475 @@
+//! Finding the hooks an event could trigger
+//!
+//! Checking every hook against every event gets slow with hundreds of
+//! hooks on busy servers. [`HookDispatcher`] indexes hooks by event and
+//! channel when they are loaded, and merges the text triggers of each
+//! channel's hooks into one Aho-Corasick automaton (for `prefix` and
+//! `contains`) and one `RegexSet` (for `regex`), so one pass over the text
+//! rules out every hook whose trigger can't match.
+//!
+//! The dispatcher only narrows the hooks down; [`check_trigger_in`] still
+//! decides, so a hook triggers exactly when it would without the index.
+//!
+//! Hooks run in priority order. [`decide`] applies `continue: false`, which
+//! stops checking after a hook triggers, and `fallback`, for hooks that only
+//! run when nothing else triggered.
+//!
+//! [`check_trigger_in`]: crate::hooks::trigger::check_trigger_in
+
+use crate::hooks::config::{CompiledHookConfig, CompiledTrigger, DM_CHANNEL};
+use crate::hooks::context::EventContext;
+use crate::hooks::event::{HookEvent, IncomingEvent};
+use crate::hooks::trigger::{check_trigger_in, Mismatch};
+use aho_corasick::AhoCorasick;
+use regex::RegexSet;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Position of a hook in the dispatcher's hook list
+type HookIdx = usize;
+
+/// Compiled hooks, indexed by the events and channels they run on
+pub struct HookDispatcher {
+    hooks: Vec<CompiledHookConfig>,
+    events: HashMap<HookEvent, EventHooks>,
+}
+
+/// Hooks for one kind of event
+#[derive(Default)]
+struct EventHooks {
+    /// Hooks listing the channel ID
+    by_channel: HashMap<u64, HookGroup>,
+    /// Hooks with the `dm` selector
+    dm: HookGroup,
+    /// Hooks without channels, which run everywhere
+    everywhere: HookGroup,
+}
+
+/// Hooks sharing a channel, with their text triggers merged
+#[derive(Default)]
+struct HookGroup {
+    /// The hooks, in file order
+    hooks: Vec<HookIdx>,
+    /// Text that must appear for each hook to trigger, by position in
+    /// `hooks`; `None` for hooks that any text may trigger
+    needs: Vec<Option<Vec<Needle>>>,
+    /// Every `prefix` and `contains` string in the group
+    literals: Option<AhoCorasick>,
+    /// Every `regex` pattern in the group
+    regexes: Option<RegexSet>,
+}
+
+/// One text condition a hook's trigger can't match without
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TextNeed {
+    Prefix(String),
+    Contains(String),
+    Regex(String),
+}
+
+/// A [`TextNeed`], as an index into its group's automaton or regex set
+#[derive(Debug, Clone, Copy)]
+enum Needle {
+    Prefix(usize),
+    Contains(usize),
+    Regex(usize),
+}
+
+impl HookDispatcher {
+    /// Index compiled hooks
+    pub fn new(mut hooks: Vec<CompiledHookConfig>) -> Self {
+        // Indices follow run order, so candidates come out in it
+        hooks.sort_by_key(|hook| Reverse(hook.priority));
+        let mut members: HashMap<HookEvent, Members> = HashMap::new();
+        for (idx, hook) in hooks.iter().enumerate() {
+            let members = members.entry(hook.event).or_default();
+            if hook.channels.is_empty() {
+                members.everywhere.push(idx);
+            }
+            for channel in &hook.channels {
+                if channel == DM_CHANNEL {
+                    members.dm.push(idx);
+                } else if let Ok(id) = channel.parse::<u64>() {
+                    members.by_channel.entry(id).or_default().push(idx);
+                }
+                // Anything else never matches a channel ID; validation
+                // reports it
+            }
+        }
+
+        let events = members
+            .into_iter()
+            .map(|(event, members)| {
+                let group = |mut indices: Vec<HookIdx>| {
+                    // A hook listing a channel twice is still one hook
+                    indices.dedup();
+                    HookGroup::new(&hooks, indices)
+                };
+                let by_channel = members
+                    .by_channel
+                    .into_iter()
+                    .map(|(id, indices)| (id, group(indices)))
+                    .collect();
+                let hooks = EventHooks {
+                    by_channel,
+                    dm: group(members.dm),
+                    everywhere: group(members.everywhere),
+                };
+                (event, hooks)
+            })
+            .collect();
+        Self { hooks, events }
+    }
+
+    /// Every hook, in the order they run
+    pub fn hooks(&self) -> &[CompiledHookConfig] {
+        &self.hooks
+    }
+
+    /// Hooks that may trigger on `event`, in the order they run
+    ///
+    /// These run on the event's kind and channel, and their text triggers
+    /// can match its text; filters and other triggers are left to
+    /// [`check_trigger_in`](crate::hooks::trigger::check_trigger_in).
+    pub fn candidates(&self, event: &IncomingEvent) -> Vec<&CompiledHookConfig> {
+        let Some(hooks) = self.events.get(&event.kind()) else {
+            return Vec::new();
+        };
+        let text = event.text();
+        let mut candidates = hooks.everywhere.candidates(text);
+        if let Some(group) = event.channel_id().and_then(|id| hooks.by_channel.get(&id.get())) {
+            candidates.extend(group.candidates(text));
+        }
+        if event.is_direct_message() {
+            candidates.extend(hooks.dm.candidates(text));
+        }
+        // A hook can be in more than one group, e.g. a DM channel listed
+        // by ID as well as with `dm`
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates.into_iter().map(|idx| &self.hooks[idx]).collect()
+    }
+}
+
+/// Why a hook did not run on an event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Skipped {
+    /// The hook did not trigger
+    Mismatch(Mismatch),
+    /// A hook before it triggered and has `continue: false`
+    Stopped {
+        /// ID of the hook that stopped the checks
+        by: String,
+    },
+    /// The hook is a fallback, and another hook triggered
+    Fallback,
+}
+
+impl fmt::Display for Skipped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Skipped::Mismatch(mismatch) => mismatch.fmt(f),
+            Skipped::Stopped { by } => {
+                write!(f, "not checked; '{}' triggered first with continue: false", by)
+            }
+            Skipped::Fallback => f.write_str("fallback not needed; another hook triggered"),
+        }
+    }
+}
+
+/// Hooks in the order they run: highest priority first, then file order
+pub fn run_order<'a>(
+    hooks: impl IntoIterator<Item = &'a CompiledHookConfig>,
+) -> Vec<&'a CompiledHookConfig> {
+    let mut hooks: Vec<_> = hooks.into_iter().collect();
+    hooks.sort_by_key(|hook| Reverse(hook.priority));
+    hooks
+}
+
+/// Decide which of `hooks`, given in run order, run on `event`
+///
+/// Hooks are checked in order until one with `continue: false` triggers.
+/// Fallback hooks are only checked if no other hook triggered, in the same
+/// way. Returns every hook with whether it runs, in order.
+pub fn decide<'a>(
+    hooks: &[&'a CompiledHookConfig],
+    event: &IncomingEvent,
+    context: &EventContext,
+) -> Vec<(&'a CompiledHookConfig, Result<(), Skipped>)> {
+    let (fallbacks, regular): (Vec<_>, Vec<_>) = hooks.iter().copied().partition(|hook| hook.fallback);
+    let mut decisions = Vec::with_capacity(hooks.len());
+    let mut triggered = false;
+    let mut stopped_by: Option<&str> = None;
+    for (is_fallback, hook) in regular
+        .into_iter()
+        .map(|hook| (false, hook))
+        .chain(fallbacks.into_iter().map(|hook| (true, hook)))
+    {
+        let outcome = if is_fallback && triggered {
+            Err(Skipped::Fallback)
+        } else if let Some(by) = stopped_by {
+            Err(Skipped::Stopped { by: by.to_string() })
+        } else {
+            check_trigger_in(hook, event, context).map_err(Skipped::Mismatch)
+        };
+        if outcome.is_ok() {
+            triggered = true;
+            if !hook.continues {
+                stopped_by = Some(&hook.id);
+            }
+        }
+        decisions.push((hook, outcome));
+    }
+    decisions
+}
+
+/// The hooks, given in run order, that run on `event`
+pub fn select<'a>(
+    hooks: &[&'a CompiledHookConfig],
+    event: &IncomingEvent,
+    context: &EventContext,
+) -> Vec<&'a CompiledHookConfig> {
+    decide(hooks, event, context)
+        .into_iter()
+        .filter_map(|(hook, outcome)| outcome.ok().map(|()| hook))
+        .collect()
+}
+
+/// Hook indices per group, while indexing
+#[derive(Default)]
+struct Members {
+    by_channel: HashMap<u64, Vec<HookIdx>>,
+    dm: Vec<HookIdx>,
+    everywhere: Vec<HookIdx>,
+}
+
+impl HookGroup {
+    fn new(all: &[CompiledHookConfig], hooks: Vec<HookIdx>) -> Self {
+        let mut literals: Vec<String> = Vec::new();
+        let mut regexes: Vec<String> = Vec::new();
+        let mut needs = Vec::with_capacity(hooks.len());
+        for &idx in &hooks {
+            let needles = text_needs(&all[idx].trigger).map(|hook_needs| {
+                hook_needs
+                    .into_iter()
+                    .map(|need| match need {
+                        TextNeed::Prefix(prefix) => Needle::Prefix(intern(&mut literals, prefix)),
+                        TextNeed::Contains(substring) => {
+                            Needle::Contains(intern(&mut literals, substring))
+                        }
+                        TextNeed::Regex(pattern) => Needle::Regex(intern(&mut regexes, pattern)),
+                    })
+                    .collect()
+            });
+            needs.push(needles);
+        }
+
+        // If either fails to build (e.g. too big), the hooks that needed it
+        // are always candidates instead
+        let literals = (!literals.is_empty())
+            .then(|| AhoCorasick::new(&literals).ok())
+            .flatten();
+        let regexes = (!regexes.is_empty())
+            .then(|| RegexSet::new(&regexes).ok())
+            .flatten();
+        Self {
+            hooks,
+            needs,
+            literals,
+            regexes,
+        }
+    }
+
+    /// The group's hooks that may trigger on `text`
+    fn candidates(&self, text: &str) -> Vec<HookIdx> {
+        if self.hooks.is_empty() {
+            return Vec::new();
+        }
+        let literal_count = self.literals.as_ref().map_or(0, AhoCorasick::patterns_len);
+        let mut prefixes = vec![false; literal_count];
+        let mut contained = vec![false; literal_count];
+        if let Some(literals) = &self.literals {
+            for found in literals.find_overlapping_iter(text) {
+                let pattern = found.pattern().as_usize();
+                contained[pattern] = true;
+                prefixes[pattern] |= found.start() == 0;
+            }
+        }
+        let matched_regexes = self.regexes.as_ref().map(|set| set.matches(text));
+
+        let found = |needle: &Needle| match needle {
+            Needle::Prefix(i) => self.literals.is_none() || prefixes[*i],
+            Needle::Contains(i) => self.literals.is_none() || contained[*i],
+            Needle::Regex(i) => matched_regexes.as_ref().is_none_or(|set| set.matched(*i)),
+        };
+        self.hooks
+            .iter()
+            .zip(&self.needs)
+            .filter(|(_, needs)| match needs {
+                Some(needles) => needles.iter().any(found),
+                None => true,
+            })
+            .map(|(&idx, _)| idx)
+            .collect()
+    }
+}
+
+/// Position of `value` in `values`, adding it if missing
+fn intern(values: &mut Vec<String>, value: String) -> usize {
+    match values.iter().position(|v| *v == value) {
+        Some(i) => i,
+        None => {
+            values.push(value);
+            values.len() - 1
+        }
+    }
+}
+
+/// Text conditions of which `trigger` needs at least one to match
+///
+/// `None` if the trigger can match without any of them (e.g. `any`, or a
+/// `not`), so the hook is always a candidate.
+fn text_needs(trigger: &CompiledTrigger) -> Option<Vec<TextNeed>> {
+    match trigger {
+        CompiledTrigger::Prefix(prefix) => Some(vec![TextNeed::Prefix(prefix.clone())]),
+        CompiledTrigger::Contains(substring) => Some(vec![TextNeed::Contains(substring.clone())]),
+        CompiledTrigger::Regex(re) => Some(vec![TextNeed::Regex(re.as_str().to_string())]),
+        // Every branch must match, so any one branch's needs will do
+        CompiledTrigger::All(triggers) => triggers.iter().find_map(text_needs),
+        // Some branch must match, so together they need one of their needs
+        CompiledTrigger::AnyOf(triggers) if !triggers.is_empty() => triggers
+            .iter()
+            .map(text_needs)
+            .collect::<Option<Vec<_>>>()
+            .map(|needs| needs.into_iter().flatten().collect()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::config::HooksConfig;
+    use crate::hooks::context::EventContext;
+    use crate::hooks::testing::test_message;
+    use crate::hooks::trigger::should_trigger_in;
+
+    fn message(channel: &str, guild: Option<&str>, content: &str) -> IncomingEvent {
+        test_message(channel, guild, content).to_event().unwrap()
+    }
+
+    #[test]
+    fn test_candidates_narrow_without_changing_matches() {
+        let config: HooksConfig = serde_yaml::from_str(
+            r#"
+version: "1.0"
+hooks:
+  - { id: ask, channels: ["10"], trigger: { type: prefix, prefix: "!ask" }, prompt_file: p, action: { type: reply } }
+  - { id: deploy, channels: ["10", "11"], trigger: { type: regex, pattern: "(?i)^deploy (prod|staging)$" }, prompt_file: p, action: { type: reply } }
+  - { id: thanks, channels: ["11", "dm"], trigger: { type: contains, substring: "thanks" }, prompt_file: p, action: { type: reply } }
+  - { id: dm-any, channels: ["dm"], prompt_file: p, action: { type: reply } }
+  - id: combined
+    channels: ["10"]
+    trigger:
+      any:
+        - all: [{ type: mention }, { type: contains, substring: "help" }]
+        - { type: prefix, prefix: "?" }
+    prompt_file: p
+    action: { type: reply }
+  - { id: not-spam, channels: ["10"], trigger: { not: { type: contains, substring: "spam" } }, prompt_file: p, action: { type: reply } }
+  - { id: joins, event: member_join, prompt_file: p, action: { type: send_dm } }
+"#,
+        )
+        .unwrap();
+        let hooks = config.compile_enabled().unwrap();
+        let dispatcher = HookDispatcher::new(config.compile_enabled().unwrap());
+        let ids = |event: &IncomingEvent| -> Vec<&str> {
+            dispatcher.candidates(event).iter().map(|h| h.id.as_str()).collect()
+        };
+
+        assert_eq!(ids(&message("10", Some("1"), "!ask why")), vec!["ask", "not-spam"]);
+        assert_eq!(ids(&message("10", Some("1"), "DEPLOY prod")), vec!["deploy", "not-spam"]);
+        assert_eq!(ids(&message("10", Some("1"), "help me")), vec!["combined", "not-spam"]);
+        assert_eq!(ids(&message("11", Some("1"), "hello")), Vec::<&str>::new());
+        assert_eq!(ids(&message("12", None, "thanks!")), vec!["thanks", "dm-any"]);
+        assert_eq!(ids(&message("12", Some("1"), "thanks!")), Vec::<&str>::new());
+
+        // Every hook that triggers is a candidate
+        for (channel, guild, content) in [
+            ("10", Some("1"), "!ask deploy prod thanks"),
+            ("10", Some("1"), "? spam"),
+            ("11", Some("1"), "deploy staging"),
+            ("11", None, "many thanks"),
+            ("13", None, "hi"),
+        ] {
+            let event = message(channel, guild, content);
+            let context = EventContext::default();
+            let triggered: Vec<&str> = hooks
+                .iter()
+                .filter(|hook| should_trigger_in(hook, &event, &context))
+                .map(|hook| hook.id.as_str())
+                .collect();
+            let candidates = ids(&event);
+            assert!(
+                triggered.iter().all(|id| candidates.contains(id)),
+                "{:?} triggered {:?}, candidates {:?}",
+                content,
+                triggered,
+                candidates
+            );
+        }
+    }
+
+    #[test]
+    fn test_priorities_stop_and_fallback() {
+        let config: HooksConfig = serde_yaml::from_str(
+            r#"
+version: "1.0"
+hooks:
+  - { id: log, channels: ["10"], prompt_file: p, action: { type: reply } }
+  - { id: help, channels: ["10"], fallback: true, prompt_file: p, action: { type: reply } }
+  - { id: ask, channels: ["10"], priority: 10, continue: false, trigger: { type: prefix, prefix: "!ask" }, prompt_file: p, action: { type: reply } }
+  - { id: urgent, channels: ["10"], priority: 20, trigger: { type: contains, substring: "urgent" }, prompt_file: p, action: { type: reply } }
+  - { id: quiet, channels: ["11"], trigger: { type: prefix, prefix: "!" }, prompt_file: p, action: { type: reply } }
+  - { id: help-11, channels: ["11"], fallback: true, continue: false, prompt_file: p, action: { type: reply } }
+  - { id: also-help-11, channels: ["11"], fallback: true, prompt_file: p, action: { type: reply } }
+"#,
+        )
+        .unwrap();
+        let dispatcher = HookDispatcher::new(config.compile_enabled().unwrap());
+        let ids: Vec<&str> = dispatcher.hooks().iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, vec!["urgent", "ask", "log", "help", "quiet", "help-11", "also-help-11"]);
+
+        let context = EventContext::default();
+        let run = |channel: &str, content: &str| -> Vec<&str> {
+            let event = message(channel, Some("1"), content);
+            select(&dispatcher.candidates(&event), &event, &context)
+                .into_iter()
+                .map(|hook| hook.id.as_str())
+                .collect()
+        };
+        assert_eq!(run("10", "urgent: !ask"), vec!["urgent", "log"]);
+        assert_eq!(run("10", "!ask urgent"), vec!["urgent", "ask"]);
+        assert_eq!(run("10", "hello"), vec!["log"]);
+        assert_eq!(run("11", "!go"), vec!["quiet"]);
+        assert_eq!(run("11", "hello"), vec!["help-11"]);
+
+        let event = message("10", Some("1"), "!ask urgent");
+        let hooks = run_order(dispatcher.hooks());
+        let skipped: Vec<(&str, Skipped)> = decide(&hooks, &event, &context)
+            .into_iter()
+            .filter_map(|(hook, outcome)| outcome.err().map(|skip| (hook.id.as_str(), skip)))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                ("log", Skipped::Stopped { by: "ask".to_string() }),
+                ("quiet", Skipped::Stopped { by: "ask".to_string() }),
+                ("help", Skipped::Fallback),
+                ("help-11", Skipped::Fallback),
+                ("also-help-11", Skipped::Fallback),
+            ]
+        );
+    }
+}
@@ -0,0 +1,713 @@
+//! Gateway events that hooks can run on
+//!
+//! A hook's `event` key picks a [`HookEvent`]; at runtime the gateway's
+//! events are wrapped in an [`IncomingEvent`], which gives triggers,
+//! filters and prompt templates a uniform view of them.
+
+use crate::error::{DiscliError, Result};
+use crate::hooks::context::EventContext;
+use crate::hooks::trigger::strip_bot_mentions;
+use crate::prompt::variables::{EventVariables, MessageVariables};
+use serde::de::DeserializeSeed;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use twilight_gateway::{Event, EventTypeFlags};
+use twilight_model::channel::message::EmojiReactionType;
+use twilight_model::channel::Message;
+use twilight_model::gateway::event::GatewayEventDeserializer;
+use twilight_model::gateway::payload::incoming::{
+    MemberAdd, MemberRemove, MessageCreate, MessageDelete, MessageUpdate, PresenceUpdate,
+    ReactionAdd, ReactionRemove, ThreadCreate, VoiceStateUpdate,
+};
+use twilight_model::gateway::presence::{Status, UserOrId};
+use twilight_model::gateway::GatewayReaction;
+use twilight_model::id::marker::{ChannelMarker, GuildMarker, UserMarker};
+use twilight_model::id::Id;
+
+/// Kind of gateway event a hook runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    /// A message was sent
+    #[default]
+    #[serde(alias = "message")]
+    MessageCreate,
+    /// A message was edited
+    MessageUpdate,
+    /// A message was deleted
+    MessageDelete,
+    /// A reaction was added to a message
+    ReactionAdd,
+    /// A reaction was removed from a message
+    ReactionRemove,
+    /// A member joined a server
+    MemberJoin,
+    /// A member left (or was removed from) a server
+    MemberLeave,
+    /// A thread was created
+    ThreadCreate,
+    /// A member's online status changed
+    PresenceUpdate,
+    /// A member joined, left or changed state in a voice channel
+    VoiceStateUpdate,
+}
+
+impl HookEvent {
+    /// Name as written in `hooks.yaml`
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::MessageCreate => "message_create",
+            HookEvent::MessageUpdate => "message_update",
+            HookEvent::MessageDelete => "message_delete",
+            HookEvent::ReactionAdd => "reaction_add",
+            HookEvent::ReactionRemove => "reaction_remove",
+            HookEvent::MemberJoin => "member_join",
+            HookEvent::MemberLeave => "member_leave",
+            HookEvent::ThreadCreate => "thread_create",
+            HookEvent::PresenceUpdate => "presence_update",
+            HookEvent::VoiceStateUpdate => "voice_state_update",
+        }
+    }
+
+    /// Event type name in gateway dispatches, e.g. `MESSAGE_REACTION_ADD`
+    pub fn dispatch_name(self) -> &'static str {
+        match self {
+            HookEvent::MessageCreate => "MESSAGE_CREATE",
+            HookEvent::MessageUpdate => "MESSAGE_UPDATE",
+            HookEvent::MessageDelete => "MESSAGE_DELETE",
+            HookEvent::ReactionAdd => "MESSAGE_REACTION_ADD",
+            HookEvent::ReactionRemove => "MESSAGE_REACTION_REMOVE",
+            HookEvent::MemberJoin => "GUILD_MEMBER_ADD",
+            HookEvent::MemberLeave => "GUILD_MEMBER_REMOVE",
+            HookEvent::ThreadCreate => "THREAD_CREATE",
+            HookEvent::PresenceUpdate => "PRESENCE_UPDATE",
+            HookEvent::VoiceStateUpdate => "VOICE_STATE_UPDATE",
+        }
+    }
+
+    /// Gateway event type that delivers this event
+    pub fn event_type(self) -> EventTypeFlags {
+        match self {
+            HookEvent::MessageCreate => EventTypeFlags::MESSAGE_CREATE,
+            HookEvent::MessageUpdate => EventTypeFlags::MESSAGE_UPDATE,
+            HookEvent::MessageDelete => EventTypeFlags::MESSAGE_DELETE,
+            HookEvent::ReactionAdd => EventTypeFlags::REACTION_ADD,
+            HookEvent::ReactionRemove => EventTypeFlags::REACTION_REMOVE,
+            HookEvent::MemberJoin => EventTypeFlags::MEMBER_ADD,
+            HookEvent::MemberLeave => EventTypeFlags::MEMBER_REMOVE,
+            HookEvent::ThreadCreate => EventTypeFlags::THREAD_CREATE,
+            HookEvent::PresenceUpdate => EventTypeFlags::PRESENCE_UPDATE,
+            HookEvent::VoiceStateUpdate => EventTypeFlags::VOICE_STATE_UPDATE,
+        }
+    }
+
+    /// Whether this is a message event, which must name its channels
+    pub fn is_message(self) -> bool {
+        matches!(
+            self,
+            HookEvent::MessageCreate | HookEvent::MessageUpdate | HookEvent::MessageDelete
+        )
+    }
+
+    /// Whether the event has text for triggers other than `any` to match
+    ///
+    /// See [`IncomingEvent::text`].
+    pub fn has_text(self) -> bool {
+        !matches!(
+            self,
+            HookEvent::MessageDelete | HookEvent::PresenceUpdate | HookEvent::VoiceStateUpdate
+        )
+    }
+
+    /// Whether the event happens in a text channel a `reply` can go to
+    pub fn has_reply_channel(self) -> bool {
+        !matches!(
+            self,
+            HookEvent::MemberJoin
+                | HookEvent::MemberLeave
+                | HookEvent::PresenceUpdate
+                | HookEvent::VoiceStateUpdate
+        )
+    }
+
+    /// Whether the event names the user who caused it
+    pub fn has_user(self) -> bool {
+        self != HookEvent::MessageDelete
+    }
+
+    /// Template variables prompts for this event can use
+    ///
+    /// Matches the names [`IncomingEvent::variables`] fills in.
+    pub fn variables(self) -> Vec<&'static str> {
+        const MESSAGE: &[&str] = &[
+            "content",
+            "raw_content",
+            "author_id",
+            "author_name",
+            "message_id",
+            "timestamp",
+            "attachments",
+            "embed_count",
+        ];
+        let mut names = vec!["event", "guild_id", "channel_id", "user_id", "user_name"];
+        names.extend_from_slice(match self {
+            HookEvent::MessageCreate => MESSAGE,
+            HookEvent::MessageUpdate => &[],
+            HookEvent::MessageDelete => &["message_id"],
+            HookEvent::ReactionAdd | HookEvent::ReactionRemove => {
+                &["emoji", "message_id", "message_author_id"]
+            }
+            HookEvent::MemberJoin => &["joined_at", "nick"],
+            HookEvent::MemberLeave => &[],
+            HookEvent::ThreadCreate => &["thread_id", "thread_name"],
+            HookEvent::PresenceUpdate => &["status"],
+            HookEvent::VoiceStateUpdate => &["connected", "self_mute", "self_deaf"],
+        });
+        if self == HookEvent::MessageUpdate {
+            names.extend_from_slice(MESSAGE);
+            names.push("edited_timestamp");
+        }
+        names
+    }
+}
+
+impl fmt::Display for HookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A gateway event that hooks can run on
+#[derive(Debug, Clone)]
+pub enum IncomingEvent {
+    MessageCreate(Box<MessageCreate>),
+    MessageUpdate(Box<MessageUpdate>),
+    MessageDelete(MessageDelete),
+    ReactionAdd(Box<ReactionAdd>),
+    ReactionRemove(Box<ReactionRemove>),
+    MemberJoin(Box<MemberAdd>),
+    MemberLeave(Box<MemberRemove>),
+    ThreadCreate(Box<ThreadCreate>),
+    PresenceUpdate(Box<PresenceUpdate>),
+    VoiceStateUpdate(Box<VoiceStateUpdate>),
+}
+
+impl From<MessageCreate> for IncomingEvent {
+    fn from(message: MessageCreate) -> Self {
+        IncomingEvent::MessageCreate(Box::new(message))
+    }
+}
+
+impl IncomingEvent {
+    /// Wrap a gateway event, or `None` if no hook can run on it
+    pub fn from_gateway(event: Event) -> Option<Self> {
+        let event = match event {
+            Event::MessageCreate(e) => IncomingEvent::MessageCreate(e),
+            Event::MessageUpdate(e) => IncomingEvent::MessageUpdate(e),
+            Event::MessageDelete(e) => IncomingEvent::MessageDelete(e),
+            Event::ReactionAdd(e) => IncomingEvent::ReactionAdd(e),
+            Event::ReactionRemove(e) => IncomingEvent::ReactionRemove(e),
+            Event::MemberAdd(e) => IncomingEvent::MemberJoin(e),
+            Event::MemberRemove(e) => IncomingEvent::MemberLeave(Box::new(e)),
+            Event::ThreadCreate(e) => IncomingEvent::ThreadCreate(e),
+            Event::PresenceUpdate(e) => IncomingEvent::PresenceUpdate(e),
+            Event::VoiceStateUpdate(e) => IncomingEvent::VoiceStateUpdate(e),
+            _ => return None,
+        };
+        Some(event)
+    }
+
+    /// Parse an event from a gateway dispatch's type (`t`) and payload (`d`)
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if the payload doesn't parse or no hook
+    /// can run on the event type
+    pub fn from_dispatch(event_type: &str, data: &serde_json::Value) -> Result<Self> {
+        let payload = serde_json::json!({ "op": 0, "s": 0, "t": event_type, "d": data }).to_string();
+        let deserializer = GatewayEventDeserializer::from_json(&payload)
+            .ok_or_else(|| DiscliError::Validation("malformed gateway dispatch".into()))?;
+        let mut json = serde_json::Deserializer::from_str(&payload);
+        let event = deserializer
+            .deserialize(&mut json)
+            .map_err(|e| DiscliError::Validation(format!("invalid {} payload: {}", event_type, e)))?;
+
+        Self::from_gateway(Event::from(event)).ok_or_else(|| {
+            DiscliError::Validation(format!("no hook can run on {} events", event_type))
+        })
+    }
+
+    /// Which kind of event this is
+    pub fn kind(&self) -> HookEvent {
+        match self {
+            IncomingEvent::MessageCreate(_) => HookEvent::MessageCreate,
+            IncomingEvent::MessageUpdate(_) => HookEvent::MessageUpdate,
+            IncomingEvent::MessageDelete(_) => HookEvent::MessageDelete,
+            IncomingEvent::ReactionAdd(_) => HookEvent::ReactionAdd,
+            IncomingEvent::ReactionRemove(_) => HookEvent::ReactionRemove,
+            IncomingEvent::MemberJoin(_) => HookEvent::MemberJoin,
+            IncomingEvent::MemberLeave(_) => HookEvent::MemberLeave,
+            IncomingEvent::ThreadCreate(_) => HookEvent::ThreadCreate,
+            IncomingEvent::PresenceUpdate(_) => HookEvent::PresenceUpdate,
+            IncomingEvent::VoiceStateUpdate(_) => HookEvent::VoiceStateUpdate,
+        }
+    }
+
+    /// The message, for message create and update events
+    pub fn message(&self) -> Option<&Message> {
+        match self {
+            IncomingEvent::MessageCreate(e) => Some(&e.0),
+            IncomingEvent::MessageUpdate(e) => Some(&e.0),
+            _ => None,
+        }
+    }
+
+    /// The reaction, for reaction events
+    pub fn reaction(&self) -> Option<&GatewayReaction> {
+        match self {
+            IncomingEvent::ReactionAdd(e) => Some(&e.0),
+            IncomingEvent::ReactionRemove(e) => Some(&e.0),
+            _ => None,
+        }
+    }
+
+    /// Server the event happened in; `None` for direct messages
+    pub fn guild_id(&self) -> Option<Id<GuildMarker>> {
+        match self {
+            IncomingEvent::MessageCreate(e) => e.guild_id,
+            IncomingEvent::MessageUpdate(e) => e.guild_id,
+            IncomingEvent::MessageDelete(e) => e.guild_id,
+            IncomingEvent::ReactionAdd(e) => e.guild_id,
+            IncomingEvent::ReactionRemove(e) => e.guild_id,
+            IncomingEvent::MemberJoin(e) => Some(e.guild_id),
+            IncomingEvent::MemberLeave(e) => Some(e.guild_id),
+            IncomingEvent::ThreadCreate(e) => e.guild_id,
+            IncomingEvent::PresenceUpdate(e) => Some(e.guild_id),
+            IncomingEvent::VoiceStateUpdate(e) => e.guild_id,
+        }
+    }
+
+    /// Channel the event happened in, matched against a hook's `channels`
+    ///
+    /// For a new thread this is its parent channel; for voice state updates
+    /// it is the voice channel joined (`None` when leaving voice).
+    pub fn channel_id(&self) -> Option<Id<ChannelMarker>> {
+        match self {
+            IncomingEvent::MessageCreate(e) => Some(e.channel_id),
+            IncomingEvent::MessageUpdate(e) => Some(e.channel_id),
+            IncomingEvent::MessageDelete(e) => Some(e.channel_id),
+            IncomingEvent::ReactionAdd(e) => Some(e.channel_id),
+            IncomingEvent::ReactionRemove(e) => Some(e.channel_id),
+            IncomingEvent::ThreadCreate(e) => e.parent_id,
+            IncomingEvent::VoiceStateUpdate(e) => e.channel_id,
+            IncomingEvent::MemberJoin(_)
+            | IncomingEvent::MemberLeave(_)
+            | IncomingEvent::PresenceUpdate(_) => None,
+        }
+    }
+
+    /// Channel a `reply` action posts to; a new thread is replied in itself
+    pub fn reply_channel_id(&self) -> Option<Id<ChannelMarker>> {
+        match self {
+            IncomingEvent::ThreadCreate(e) => Some(e.id),
+            IncomingEvent::VoiceStateUpdate(_) => None,
+            _ => self.channel_id(),
+        }
+    }
+
+    /// Whether the event happened in a direct message with the bot
+    pub fn is_direct_message(&self) -> bool {
+        (self.message().is_some()
+            || self.reaction().is_some()
+            || matches!(self, IncomingEvent::MessageDelete(_)))
+            && self.guild_id().is_none()
+    }
+
+    /// User who caused the event, if known
+    pub fn user_id(&self) -> Option<Id<UserMarker>> {
+        match self {
+            IncomingEvent::MessageCreate(e) => Some(e.author.id),
+            IncomingEvent::MessageUpdate(e) => Some(e.author.id),
+            IncomingEvent::MessageDelete(_) => None,
+            IncomingEvent::ReactionAdd(e) => Some(e.user_id),
+            IncomingEvent::ReactionRemove(e) => Some(e.user_id),
+            IncomingEvent::MemberJoin(e) => Some(e.member.user.id),
+            IncomingEvent::MemberLeave(e) => Some(e.user.id),
+            IncomingEvent::ThreadCreate(e) => e.owner_id,
+            IncomingEvent::PresenceUpdate(e) => Some(e.user.id()),
+            IncomingEvent::VoiceStateUpdate(e) => Some(e.user_id),
+        }
+    }
+
+    /// Username of the user who caused the event, if the event carries it
+    pub fn user_name(&self) -> Option<&str> {
+        match self {
+            IncomingEvent::MessageCreate(e) => Some(&e.author.name),
+            IncomingEvent::MessageUpdate(e) => Some(&e.author.name),
+            IncomingEvent::ReactionAdd(e) => e.member.as_ref().map(|m| m.user.name.as_str()),
+            IncomingEvent::ReactionRemove(e) => e.member.as_ref().map(|m| m.user.name.as_str()),
+            IncomingEvent::MemberJoin(e) => Some(&e.member.user.name),
+            IncomingEvent::MemberLeave(e) => Some(&e.user.name),
+            IncomingEvent::PresenceUpdate(e) => match &e.user {
+                UserOrId::User(user) => Some(&user.name),
+                UserOrId::UserId { .. } => None,
+            },
+            IncomingEvent::VoiceStateUpdate(e) => e.member.as_ref().map(|m| m.user.name.as_str()),
+            IncomingEvent::MessageDelete(_) | IncomingEvent::ThreadCreate(_) => None,
+        }
+    }
+
+    /// Role IDs of the member who caused the event, if the event carries
+    /// them
+    ///
+    /// Guild messages, guild reactions, member joins and voice state
+    /// updates include the member; other events need a lookup.
+    pub fn member_roles(&self) -> Option<Vec<String>> {
+        let roles = match self {
+            IncomingEvent::MessageCreate(_) | IncomingEvent::MessageUpdate(_) => {
+                &self.message()?.member.as_ref()?.roles
+            }
+            IncomingEvent::ReactionAdd(_) | IncomingEvent::ReactionRemove(_) => {
+                &self.reaction()?.member.as_ref()?.roles
+            }
+            IncomingEvent::MemberJoin(e) => &e.member.roles,
+            IncomingEvent::VoiceStateUpdate(e) => &e.member.as_ref()?.roles,
+            IncomingEvent::MessageDelete(_)
+            | IncomingEvent::MemberLeave(_)
+            | IncomingEvent::ThreadCreate(_)
+            | IncomingEvent::PresenceUpdate(_) => return None,
+        };
+        Some(roles.iter().map(|id| id.to_string()).collect())
+    }
+
+    /// Whether a bot account caused the event, if the event says
+    pub fn is_bot(&self) -> Option<bool> {
+        match self {
+            IncomingEvent::MessageCreate(e) => Some(e.author.bot),
+            IncomingEvent::MessageUpdate(e) => Some(e.author.bot),
+            IncomingEvent::ReactionAdd(e) => e.member.as_ref().map(|m| m.user.bot),
+            IncomingEvent::ReactionRemove(e) => e.member.as_ref().map(|m| m.user.bot),
+            IncomingEvent::MemberJoin(e) => Some(e.member.user.bot),
+            IncomingEvent::MemberLeave(e) => Some(e.user.bot),
+            IncomingEvent::PresenceUpdate(e) => match &e.user {
+                UserOrId::User(user) => Some(user.bot),
+                UserOrId::UserId { .. } => None,
+            },
+            IncomingEvent::VoiceStateUpdate(e) => e.member.as_ref().map(|m| m.user.bot),
+            IncomingEvent::MessageDelete(_) | IncomingEvent::ThreadCreate(_) => None,
+        }
+    }
+
+    /// Text that triggers match against
+    ///
+    /// The message content for messages, the emoji for reactions, the
+    /// thread name for new threads and the username for members joining
+    /// or leaving. Other events have no text.
+    pub fn text(&self) -> &str {
+        match self {
+            IncomingEvent::MessageCreate(_) | IncomingEvent::MessageUpdate(_) => {
+                self.message().map(|m| m.content.as_str()).unwrap_or_default()
+            }
+            IncomingEvent::ReactionAdd(_) | IncomingEvent::ReactionRemove(_) => {
+                self.reaction().map(|r| emoji_name(&r.emoji)).unwrap_or_default()
+            }
+            IncomingEvent::ThreadCreate(e) => e.name.as_deref().unwrap_or_default(),
+            IncomingEvent::MemberJoin(_) | IncomingEvent::MemberLeave(_) => {
+                self.user_name().unwrap_or_default()
+            }
+            IncomingEvent::MessageDelete(_)
+            | IncomingEvent::PresenceUpdate(_)
+            | IncomingEvent::VoiceStateUpdate(_) => "",
+        }
+    }
+
+    /// Online status, for presence updates
+    pub fn status(&self) -> Option<&'static str> {
+        match self {
+            IncomingEvent::PresenceUpdate(e) => Some(status_name(e.status)),
+            _ => None,
+        }
+    }
+
+    /// Template variables for this event
+    ///
+    /// Every event has `event`, `guild_id`, `channel_id`, `user_id` and
+    /// `user_name` (empty when not applicable); each kind adds its own.
+    pub fn variables(&self) -> EventVariables {
+        let mut vars = match self {
+            IncomingEvent::MessageCreate(e) => {
+                EventVariables::from(&MessageVariables::from_message(e))
+            }
+            IncomingEvent::MessageUpdate(e) => {
+                let message = MessageCreate(e.0.clone());
+                let mut vars = EventVariables::from(&MessageVariables::from_message(&message));
+                vars.insert(
+                    "edited_timestamp",
+                    e.edited_timestamp
+                        .map(|t| t.iso_8601().to_string())
+                        .unwrap_or_default(),
+                );
+                vars
+            }
+            IncomingEvent::MessageDelete(e) => {
+                let mut vars = EventVariables::new();
+                vars.insert("message_id", e.id.to_string());
+                vars
+            }
+            IncomingEvent::ReactionAdd(_) | IncomingEvent::ReactionRemove(_) => {
+                let mut vars = EventVariables::new();
+                if let Some(reaction) = self.reaction() {
+                    vars.insert("emoji", emoji_name(&reaction.emoji).to_string());
+                    vars.insert("message_id", reaction.message_id.to_string());
+                    vars.insert(
+                        "message_author_id",
+                        reaction
+                            .message_author_id
+                            .map(|id| id.to_string())
+                            .unwrap_or_default(),
+                    );
+                }
+                vars
+            }
+            IncomingEvent::MemberJoin(e) => {
+                let mut vars = EventVariables::new();
+                vars.insert(
+                    "joined_at",
+                    e.member
+                        .joined_at
+                        .map(|t| t.iso_8601().to_string())
+                        .unwrap_or_default(),
+                );
+                vars.insert("nick", e.member.nick.clone().unwrap_or_default());
+                vars
+            }
+            IncomingEvent::MemberLeave(_) => EventVariables::new(),
+            IncomingEvent::ThreadCreate(e) => {
+                let mut vars = EventVariables::new();
+                vars.insert("thread_id", e.id.to_string());
+                vars.insert("thread_name", e.name.clone().unwrap_or_default());
+                vars
+            }
+            IncomingEvent::PresenceUpdate(e) => {
+                let mut vars = EventVariables::new();
+                vars.insert("status", status_name(e.status).to_string());
+                vars
+            }
+            IncomingEvent::VoiceStateUpdate(e) => {
+                let mut vars = EventVariables::new();
+                vars.insert("connected", e.channel_id.is_some().to_string());
+                vars.insert("self_mute", e.self_mute.to_string());
+                vars.insert("self_deaf", e.self_deaf.to_string());
+                vars
+            }
+        };
+
+        let id = |id: Option<String>| id.unwrap_or_default();
+        vars.insert("event", self.kind().name().to_string());
+        vars.insert("guild_id", id(self.guild_id().map(|i| i.to_string())));
+        vars.insert("channel_id", id(self.channel_id().map(|i| i.to_string())));
+        vars.insert("user_id", id(self.user_id().map(|i| i.to_string())));
+        vars.insert("user_name", self.user_name().unwrap_or_default().to_string());
+        if let Some(message) = self.message() {
+            vars.insert("raw_content", message.content.clone());
+        }
+        vars
+    }
+
+    /// Template variables for this event, with what `context` knows
+    ///
+    /// Once the bot is known, its mentions are stripped from `content`;
+    /// `raw_content` keeps the message as sent.
+    pub fn variables_in(&self, context: &EventContext) -> EventVariables {
+        let mut vars = self.variables();
+        if let (Some(message), Some(bot)) = (self.message(), &context.bot) {
+            vars.insert("content", strip_bot_mentions(&message.content, bot));
+        }
+        vars
+    }
+}
+
+/// Emoji as written in a filter: the character, or a custom emoji's name
+fn emoji_name(emoji: &EmojiReactionType) -> &str {
+    match emoji {
+        EmojiReactionType::Unicode { name } => name,
+        EmojiReactionType::Custom { name, .. } => name.as_deref().unwrap_or_default(),
+    }
+}
+
+/// Status as written in a filter
+fn status_name(status: Status) -> &'static str {
+    match status {
+        Status::DoNotDisturb => "dnd",
+        Status::Idle => "idle",
+        Status::Invisible => "invisible",
+        Status::Offline => "offline",
+        Status::Online => "online",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::variables::TemplateVariables;
+    use crate::hooks::testing::message_create;
+
+    fn event(event_type: &str, data: serde_json::Value) -> IncomingEvent {
+        IncomingEvent::from_dispatch(event_type, &data).unwrap()
+    }
+
+    fn user(id: &str, name: &str) -> serde_json::Value {
+        serde_json::json!({ "id": id, "username": name, "discriminator": "0" })
+    }
+
+    #[test]
+    fn test_reaction_add() {
+        let event = event(
+            "MESSAGE_REACTION_ADD",
+            serde_json::json!({
+                "user_id": "2000",
+                "channel_id": "10",
+                "message_id": "1000",
+                "guild_id": "20",
+                "message_author_id": "3000",
+                "burst": false,
+                "type": 0,
+                "emoji": { "id": null, "name": "👍" },
+                "member": {
+                    "user": user("2000", "tester"),
+                    "roles": [],
+                    "joined_at": "2024-01-01T00:00:00.000000+00:00",
+                    "deaf": false, "mute": false, "flags": 0
+                }
+            }),
+        );
+
+        assert_eq!(event.kind(), HookEvent::ReactionAdd);
+        assert_eq!(event.text(), "👍");
+        assert_eq!(event.channel_id().unwrap().get(), 10);
+        assert!(!event.is_direct_message());
+
+        let vars = event.variables().to_map();
+        assert_eq!(vars["emoji"], "👍");
+        assert_eq!(vars["message_author_id"], "3000");
+        assert_eq!(vars["user_name"], "tester");
+        assert_eq!(vars["event"], "reaction_add");
+    }
+
+    #[test]
+    fn test_member_join_and_leave() {
+        let join = event(
+            "GUILD_MEMBER_ADD",
+            serde_json::json!({
+                "guild_id": "20",
+                "user": user("2000", "newcomer"),
+                "roles": [],
+                "joined_at": "2024-01-01T00:00:00.000000+00:00",
+                "deaf": false, "mute": false, "flags": 0
+            }),
+        );
+        assert_eq!(join.kind(), HookEvent::MemberJoin);
+        assert_eq!(join.text(), "newcomer");
+        assert!(join.channel_id().is_none());
+        assert_eq!(join.variables().to_map()["guild_id"], "20");
+
+        let leave = event(
+            "GUILD_MEMBER_REMOVE",
+            serde_json::json!({ "guild_id": "20", "user": user("2000", "leaver") }),
+        );
+        assert_eq!(leave.kind(), HookEvent::MemberLeave);
+        assert_eq!(leave.user_id().unwrap().get(), 2000);
+    }
+
+    #[test]
+    fn test_voice_state_and_presence() {
+        let voice = event(
+            "VOICE_STATE_UPDATE",
+            serde_json::json!({
+                "guild_id": "20",
+                "channel_id": null,
+                "user_id": "2000",
+                "session_id": "abc",
+                "deaf": false, "mute": false,
+                "self_deaf": false, "self_mute": true,
+                "self_video": false, "suppress": false,
+                "request_to_speak_timestamp": null
+            }),
+        );
+        assert!(voice.channel_id().is_none());
+        let vars = voice.variables().to_map();
+        assert_eq!(vars["connected"], "false");
+        assert_eq!(vars["self_mute"], "true");
+
+        let presence = event(
+            "PRESENCE_UPDATE",
+            serde_json::json!({
+                "guild_id": "20",
+                "user": { "id": "2000" },
+                "status": "dnd",
+                "activities": [],
+                "client_status": {}
+            }),
+        );
+        assert_eq!(presence.status(), Some("dnd"));
+        assert_eq!(presence.text(), "");
+    }
+
+    #[test]
+    fn test_message_delete_in_dm() {
+        let delete = event(
+            "MESSAGE_DELETE",
+            serde_json::json!({ "id": "1000", "channel_id": "10" }),
+        );
+        assert!(delete.is_direct_message());
+        assert!(delete.user_id().is_none());
+        assert_eq!(delete.variables().to_map()["message_id"], "1000");
+    }
+
+    #[test]
+    fn test_declared_variables_match_events() {
+        let declared = |event: &IncomingEvent| {
+            let mut names = event.kind().variables();
+            names.sort_unstable();
+            names
+        };
+        let filled = |event: &IncomingEvent| {
+            let mut names: Vec<String> = event.variables().to_map().into_keys().collect();
+            names.sort_unstable();
+            names
+        };
+
+        for event in [
+            message_create(serde_json::json!({ "content": "hi" })),
+            event(
+                "MESSAGE_DELETE",
+                serde_json::json!({ "id": "1000", "channel_id": "10" }),
+            ),
+            event(
+                "GUILD_MEMBER_REMOVE",
+                serde_json::json!({ "guild_id": "20", "user": user("2000", "leaver") }),
+            ),
+            event(
+                "PRESENCE_UPDATE",
+                serde_json::json!({
+                    "guild_id": "20",
+                    "user": { "id": "2000" },
+                    "status": "idle",
+                    "activities": [],
+                    "client_status": {}
+                }),
+            ),
+        ] {
+            assert_eq!(declared(&event), filled(&event), "{}", event.kind());
+        }
+    }
+
+    #[test]
+    fn test_hook_event_names() {
+        let event: HookEvent = serde_yaml::from_str("member_join").unwrap();
+        assert_eq!(event, HookEvent::MemberJoin);
+        let event: HookEvent = serde_yaml::from_str("message").unwrap();
+        assert_eq!(event, HookEvent::MessageCreate);
+        assert_eq!(HookEvent::VoiceStateUpdate.to_string(), "voice_state_update");
+    }
+}
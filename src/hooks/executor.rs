@@ -3,168 +3,222 @@
 //! This module handles executing hook actions and processing prompts.
 
 use crate::config::Config;
-use crate::discord::DiscordClient;
 use crate::discord::types::DiscordMessage;
-use crate::hooks::config::{CompiledHookConfig, HookAction, ProcessingConfig};
+use crate::hooks::concurrency::{Busy, ConcurrencyLimiter};
+use crate::hooks::config::{
+    CompiledHookConfig, ErrorStrategy, FailureFeedback, HookAction, NotifyTarget,
+    ProcessingConfig, RateLimitConfig, Settings, ThrottleResponse,
+};
+use crate::hooks::context::EventContext;
+use crate::hooks::event::IncomingEvent;
+use crate::hooks::failure::{error_report, failure_reply, first_chars};
+use crate::hooks::rate_limit::{RateLimiter, Throttled};
 use crate::processing::{CommandProcessor, HttpProcessor};
-use crate::prompt::variables::MessageVariables;
 use crate::prompt::registry::PromptRegistry;
-use crate::error::Result;
-use tokio::sync::RwLock;
-use std::collections::VecDeque;
-use std::time::{Duration, Instant};
-use twilight_model::gateway::payload::incoming::MessageCreate;
-
-/// Rate limiter for hooks
-pub struct RateLimiter {
-    per_user: u32,
-    per_channel: u32,
-    window: Duration,
-    user_history: RwLock<VecDeque<(String, Instant)>>,
-    channel_history: RwLock<VecDeque<(String, Instant)>>,
-}
-
-impl RateLimiter {
-    pub fn new(per_user: u32, per_channel: u32, window_secs: u64) -> Self {
-        Self {
-            per_user,
-            per_channel,
-            window: Duration::from_secs(window_secs),
-            user_history: RwLock::new(VecDeque::new()),
-            channel_history: RwLock::new(VecDeque::new()),
-        }
-    }
-    
-    /// Check if user is rate limited
-    pub async fn check_user(&self, user_id: &str) -> bool {
-        let mut history = self.user_history.write().await;
-        let now = Instant::now();
-        
-        // Remove old entries
-        while history.front().map(|(_, t)| now.duration_since(*t) > self.window).unwrap_or(false) {
-            history.pop_front();
-        }
-        
-        // Count recent from this user
-        let count = history.iter()
-            .filter(|(id, _)| id == user_id)
-            .count();
-        
-        if count >= self.per_user as usize {
-            return false; // Rate limited
-        }
-        
-        history.push_back((user_id.to_string(), now));
-        true
-    }
-    
-    /// Check if channel is rate limited
-    pub async fn check_channel(&self, channel_id: &str) -> bool {
-        let mut history = self.channel_history.write().await;
-        let now = Instant::now();
-        
-        // Remove old entries
-        while history.front().map(|(_, t)| now.duration_since(*t) > self.window).unwrap_or(false) {
-            history.pop_front();
-        }
-        
-        // Count recent from this channel
-        let count = history.iter()
-            .filter(|(id, _)| id == channel_id)
-            .count();
-        
-        if count >= self.per_channel as usize {
-            return false; // Rate limited
-        }
-        
-        history.push_back((channel_id.to_string(), now));
-        true
-    }
-}
+use crate::error::{DiscliError, Result};
+use crate::sink::MessageSink;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
 
 /// Result of hook execution
 #[derive(Debug)]
 pub struct HookResult {
     /// Whether the hook was executed
     pub executed: bool,
+    /// The rendered prompt (if the hook got that far)
+    pub prompt: Option<String>,
     /// The response message (if any)
     pub response: Option<String>,
     /// Error message if failed
     pub error: Option<String>,
 }
 
+/// Stand-in for hook processors, so hooks can run without their scripts
+/// or endpoints
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessorStub {
+    /// Respond with the rendered prompt
+    Echo,
+    /// Respond with fixed text
+    Output(String),
+}
+
 /// Hook executor
+///
+/// Executes through `&self`, so one executor can run many hooks at once;
+/// the state runs share (prompt cache, rate limits, concurrency limits)
+/// keeps its own locks, none of them held across a processor run or a
+/// send.
 pub struct HookExecutor {
-    config: Config,
-    prompt_registry: PromptRegistry,
+    prompt_registry: Mutex<PromptRegistry>,
     rate_limiter: RateLimiter,
+    concurrency: ConcurrencyLimiter,
+    errors: RwLock<ErrorHandling>,
+    sink: Arc<dyn MessageSink>,
+    processor_stub: Option<ProcessorStub>,
+}
+
+/// `settings.on_error` and `settings.notify`
+#[derive(Default)]
+struct ErrorHandling {
+    on_error: ErrorStrategy,
+    notify: Option<NotifyTarget>,
 }
 
 impl HookExecutor {
-    pub fn new(config: Config) -> Self {
-        let prompts_dir = config.prompts_dir.clone();
-        let rate_limiter = RateLimiter::new(
-            5, // per_user
-            10, // per_channel
-            60, // window
-        );
-        
+    /// Create an executor that sends hook responses through `sink`
+    pub fn new(config: Config, sink: Arc<dyn MessageSink>) -> Self {
+        Self::with_prompts_dir(config.prompts_dir, sink)
+    }
+
+    /// Create an executor that loads prompts from `prompts_dir`
+    pub fn with_prompts_dir(prompts_dir: PathBuf, sink: Arc<dyn MessageSink>) -> Self {
         Self {
-            config,
-            prompt_registry: PromptRegistry::new(prompts_dir),
-            rate_limiter,
+            prompt_registry: Mutex::new(PromptRegistry::new(prompts_dir)),
+            rate_limiter: RateLimiter::from_config(RateLimitConfig::default()),
+            concurrency: ConcurrencyLimiter::default(),
+            errors: RwLock::new(ErrorHandling::default()),
+            sink,
+            processor_stub: None,
         }
     }
+
+    /// Apply the `settings` block of the hooks file: rate limits, the
+    /// processor cap and error handling
+    pub fn with_settings(self, settings: Settings) -> Self {
+        self.set_settings(settings);
+        self
+    }
+
+    /// Change the settings, e.g. after the hooks file is reloaded;
+    /// triggers counted so far and runs in progress still count
+    pub fn set_settings(&self, settings: Settings) {
+        self.rate_limiter.set_settings(settings.rate_limit);
+        self.concurrency.set_max_processors(settings.max_processors);
+        *self.errors.write().unwrap_or_else(|e| e.into_inner()) = ErrorHandling {
+            on_error: settings.on_error,
+            notify: settings.notify,
+        };
+    }
+
+    /// Drop the concurrency limits of hooks not in `hooks`, e.g. after a
+    /// reload
+    pub fn retain_hooks(&self, hooks: &[CompiledHookConfig]) {
+        self.concurrency.retain(hooks);
+    }
+
+    /// How failed hooks are handled (`settings.on_error`)
+    pub fn error_strategy(&self) -> ErrorStrategy {
+        self.errors.read().unwrap_or_else(|e| e.into_inner()).on_error
+    }
+
+    /// Replace every hook's processor with `stub`
+    pub fn with_processor_stub(mut self, stub: ProcessorStub) -> Self {
+        self.processor_stub = Some(stub);
+        self
+    }
     
-    /// Execute a hook for a message
+    /// Execute a hook for an event
+    ///
+    /// If the prompt, the processor or the response fails, the user gets
+    /// the hook's `on_failure` feedback and, with `on_error: notify`, an
+    /// error report goes to `settings.notify` before the error is returned.
     pub async fn execute(
-        &mut self,
+        &self,
         hook: &CompiledHookConfig,
-        message: &MessageCreate,
+        event: &IncomingEvent,
     ) -> Result<HookResult> {
-        // Check rate limits
-        let user_id = message.0.author.id.to_string();
-        let channel_id = message.0.channel_id.to_string();
-        
-        if !self.rate_limiter.check_user(&user_id).await {
-            return Ok(HookResult {
-                executed: false,
-                response: None,
-                error: Some("Rate limited (user)".to_string()),
-            });
-        }
-        
-        if !self.rate_limiter.check_channel(&channel_id).await {
+        self.execute_in(hook, event, &EventContext::default()).await
+    }
+
+    /// Execute a hook for an event, with looked-up context for the
+    /// prompt's variables
+    ///
+    /// Throttled events are turned away first; the rest wait for one of
+    /// the hook's runs (`max_concurrency`) and then for a place among the
+    /// running processors (`settings.max_processors`).
+    pub async fn execute_in(
+        &self,
+        hook: &CompiledHookConfig,
+        event: &IncomingEvent,
+        context: &EventContext,
+    ) -> Result<HookResult> {
+        // Check rate limits and the hook's cooldown
+        if let Err(throttled) = self.rate_limiter.check(hook, event) {
+            let mut error = throttled.to_string();
+            if throttled.notify {
+                if let Err(e) = self.send_throttle_response(&throttled, event).await {
+                    error = format!("{}; failed to tell the user: {}", error, e);
+                }
+            }
             return Ok(HookResult {
                 executed: false,
+                prompt: None,
                 response: None,
-                error: Some("Rate limited (channel)".to_string()),
+                error: Some(error),
             });
         }
+
+        // Wait for one of the hook's runs, or give up if it is busy
+        let _run = match self.concurrency.acquire(hook).await {
+            Ok(run) => run,
+            Err(busy) => {
+                let mut error = busy.to_string();
+                if let Err(e) = self.send_busy_reply(&busy, event).await {
+                    error = format!("{}; failed to tell the user: {}", error, e);
+                }
+                return Ok(HookResult {
+                    executed: false,
+                    prompt: None,
+                    response: None,
+                    error: Some(error),
+                });
+            }
+        };
         
-        // Extract variables from message
-        let vars = MessageVariables::from_message(message);
+        // Extract variables from the event
+        let vars = event.variables_in(context);
         
         // Render prompt with variables
-        let prompt = match self.prompt_registry.render(&hook.prompt_file, &vars) {
+        let rendered = self
+            .prompt_registry
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .render(&hook.prompt_file, &vars);
+        let prompt = match rendered {
             Ok(p) => p,
             Err(e) => {
-                return Ok(HookResult {
-                    executed: false,
-                    response: None,
-                    error: Some(format!("Failed to load prompt: {}", e)),
-                });
+                let e = DiscliError::Config(format!(
+                    "Failed to load prompt {}: {}",
+                    hook.prompt_file.display(),
+                    e
+                ));
+                self.handle_failure(hook, event, &e).await;
+                return Err(e);
             }
         };
         
-        // Execute processor (placeholder - Phase 6)
-        let response = self.execute_processor(&hook.processing, &prompt).await?;
+        // Execute processor
+        let processor = self.concurrency.acquire_processor().await;
+        let processed = self.execute_processor(&hook.processing, &prompt).await;
+        drop(processor);
+        let response = match processed {
+            Ok(response) => response,
+            Err(e) => {
+                self.handle_failure(hook, event, &e).await;
+                return Err(e);
+            }
+        };
         
         // Send response based on action
-        self.send_response(&hook.action, &response, message).await?;
+        if let Err(e) = self.send_response(&hook.action, &response, event).await {
+            self.handle_failure(hook, event, &e).await;
+            return Err(e);
+        }
         
         Ok(HookResult {
             executed: true,
+            prompt: Some(prompt),
             response: Some(response),
             error: None,
         })
@@ -176,75 +230,184 @@ impl HookExecutor {
         processing: &ProcessingConfig,
         prompt: &str,
     ) -> Result<String> {
+        match &self.processor_stub {
+            Some(ProcessorStub::Echo) => return Ok(prompt.to_string()),
+            Some(ProcessorStub::Output(output)) => return Ok(output.clone()),
+            None => {}
+        }
+
         match processing.processor_type.as_str() {
             "command" => {
                 if processing.cmd.is_empty() {
-                    return Err(crate::error::DiscliError::Config("No command configured".into()));
+                    return Err(DiscliError::Config("No command configured".into()));
                 }
                 let processor = CommandProcessor::new(processing.timeout_seconds);
                 processor.execute(&processing.cmd, prompt).await
             }
             "http" => {
                 if processing.url.is_empty() {
-                    return Err(crate::error::DiscliError::Config("No URL configured".into()));
+                    return Err(DiscliError::Config("No URL configured".into()));
                 }
                 let processor = HttpProcessor::new(processing.timeout_seconds);
                 processor.execute(&processing.url, prompt, None).await
             }
-            _ => Err(crate::error::DiscliError::Config(
+            _ => Err(DiscliError::Config(
                 format!("Unknown processor type: {}", processing.processor_type)
-            ).into())
+            ))
         }
     }
     
+    /// Give the user the hook's failure feedback and report the failure
+    ///
+    /// Neither may hide the original error, so their own failures are
+    /// only warned about.
+    async fn handle_failure(&self, hook: &CompiledHookConfig, event: &IncomingEvent, error: &DiscliError) {
+        if let Err(e) = self.send_failure_feedback(hook, event, error).await {
+            eprintln!("Warning: Failed to tell the user hook {} failed: {}", hook.id, e);
+        }
+        let notify = {
+            let errors = self.errors.read().unwrap_or_else(|e| e.into_inner());
+            if errors.on_error != ErrorStrategy::Notify {
+                return;
+            }
+            errors.notify.clone()
+        };
+        if let Some(target) = &notify {
+            let report = error_report(&hook.id, event, error);
+            let sent = match target {
+                NotifyTarget::Channel { channel_id } => self.sink.send_message(channel_id, &report).await,
+                NotifyTarget::Webhook { url } => self.sink.send_webhook(url, &report).await,
+            };
+            if let Err(e) = sent {
+                eprintln!("Warning: Failed to report hook {} failure: {}", hook.id, e);
+            }
+        }
+    }
+
+    /// Show the triggering user the hook's `on_failure` feedback
+    ///
+    /// Reactions need a message to react to and replies a channel;
+    /// events without one get no feedback.
+    async fn send_failure_feedback(
+        &self,
+        hook: &CompiledHookConfig,
+        event: &IncomingEvent,
+        error: &DiscliError,
+    ) -> Result<()> {
+        match &hook.on_failure {
+            FailureFeedback::None => Ok(()),
+            FailureFeedback::React { emoji } => match event.message() {
+                Some(message) => {
+                    self.sink
+                        .add_reaction(&message.channel_id.to_string(), &message.id.to_string(), emoji)
+                        .await
+                }
+                None => Ok(()),
+            },
+            FailureFeedback::Reply { message } => match event.reply_channel_id() {
+                Some(channel_id) => {
+                    let msg = DiscordMessage::Simple {
+                        content: failure_reply(message, &hook.id, event, error),
+                    };
+                    self.sink.send_message(&channel_id.to_string(), &msg).await
+                }
+                None => Ok(()),
+            },
+        }
+    }
+
+    /// Let the user know they were throttled, as `settings.rate_limit.on_limit` says
+    ///
+    /// Reactions need a message to react to and notices a user to DM;
+    /// events without one are throttled silently.
+    async fn send_throttle_response(&self, throttled: &Throttled, event: &IncomingEvent) -> Result<()> {
+        match &throttled.response {
+            ThrottleResponse::Silent => Ok(()),
+            ThrottleResponse::React { emoji } => match event.message() {
+                Some(message) => {
+                    self.sink
+                        .add_reaction(&message.channel_id.to_string(), &message.id.to_string(), emoji)
+                        .await
+                }
+                None => Ok(()),
+            },
+            ThrottleResponse::Ephemeral { message } => match event.user_id() {
+                Some(user_id) => {
+                    let msg = DiscordMessage::Simple {
+                        content: message
+                            .replace("{{retry_after}}", &throttled.retry_after_secs().to_string()),
+                    };
+                    self.sink.send_dm(&user_id.to_string(), &msg).await
+                }
+                None => Ok(()),
+            },
+        }
+    }
+
+    /// Reply to an event turned away by a busy hook with `on_busy: reply`
+    ///
+    /// Events without a channel to reply in are dropped silently.
+    async fn send_busy_reply(&self, busy: &Busy, event: &IncomingEvent) -> Result<()> {
+        match (&busy.reply, event.reply_channel_id()) {
+            (Some(message), Some(channel_id)) => {
+                let msg = DiscordMessage::Simple {
+                    content: message.clone(),
+                };
+                self.sink.send_message(&channel_id.to_string(), &msg).await
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Send response based on action
     async fn send_response(
         &self,
         action: &HookAction,
         response: &str,
-        message: &MessageCreate,
+        event: &IncomingEvent,
     ) -> Result<()> {
         match action {
             HookAction::Reply => {
-                // Create Discord client and send message to channel
-                let client = DiscordClient::new(self.config.discord_token.clone());
-                
                 let msg = DiscordMessage::Simple {
                     content: response.to_string(),
                 };
                 
-                let channel_id = message.0.channel_id.to_string();
-                client.send_message(&channel_id, &msg).await?;
+                let channel_id = event.reply_channel_id().ok_or_else(|| {
+                    DiscliError::Config(format!("{} events have no channel to reply in", event.kind()))
+                })?.to_string();
+                self.sink.send_message(&channel_id, &msg).await?;
                 
-                println!("[HOOK] Replied to channel {}: {}", channel_id, &response[..response.len().min(50)]);
+                println!("[HOOK] Replied to channel {}: {}", channel_id, first_chars(response, 50));
             }
             HookAction::SendDm => {
-                // Would need to create DM channel first via Discord API
-                // For now, just log
-                println!("[HOOK] Would DM user: {}", response);
+                let msg = DiscordMessage::Simple {
+                    content: response.to_string(),
+                };
+                
+                let user_id = event.user_id().ok_or_else(|| {
+                    DiscliError::Config(format!("{} events have no user to DM", event.kind()))
+                })?.to_string();
+                self.sink.send_dm(&user_id, &msg).await?;
+                
+                println!("[HOOK] Sent DM to user {}: {}", user_id, first_chars(response, 50));
             }
             HookAction::Forward { channel_id } => {
-                let client = DiscordClient::new(self.config.discord_token.clone());
-                
                 let msg = DiscordMessage::Simple {
                     content: response.to_string(),
                 };
                 
-                client.send_message(channel_id, &msg).await?;
+                self.sink.send_message(channel_id, &msg).await?;
                 
-                println!("[HOOK] Forwarded to {}: {}", channel_id, &response[..response.len().min(50)]);
+                println!("[HOOK] Forwarded to {}: {}", channel_id, first_chars(response, 50));
             }
             HookAction::Webhook { url } => {
-                let client = reqwest::Client::new();
+                let msg = DiscordMessage::Simple {
+                    content: response.to_string(),
+                };
                 
-                let _ = client.post(url)
-                    .json(&serde_json::json!({
-                        "content": response,
-                    }))
-                    .send()
-                    .await;
+                self.sink.send_webhook(url, &msg).await?;
                 
-                println!("[HOOK] Sent to webhook: {}", &response[..response.len().min(50)]);
+                println!("[HOOK] Sent to webhook: {}", first_chars(response, 50));
             }
         }
         Ok(())
@@ -254,7 +417,295 @@ impl HookExecutor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hooks::config::HookConfig;
+    use crate::sink::{MemorySink, SinkTarget};
+    use tempfile::TempDir;
+    use crate::hooks::testing::message_create;
+
+    fn message(channel_id: &str, content: &str) -> IncomingEvent {
+        message_create(serde_json::json!({ "channel_id": channel_id, "content": content }))
+    }
+
+    fn test_config(prompts_dir: &std::path::Path) -> Config {
+        Config {
+            discord_token: "token".to_string(),
+            channel_id: "10".to_string(),
+            api_base: "http://127.0.0.1:0".to_string(),
+            hook_enabled: true,
+            hooks_file: "hooks.yaml".into(),
+            prompts_dir: prompts_dir.to_path_buf(),
+            log_level: "info".to_string(),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_reply_through_sink() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("echo.txt"), "Echo: {{content}}").unwrap();
+
+        let hook: HookConfig = serde_yaml::from_str(
+            r#"
+id: "echo"
+channels: ["10"]
+trigger: { type: "prefix", prefix: "!echo" }
+prompt_file: "echo.txt"
+action: { type: "reply" }
+processing: { processor_type: "command", cmd: ["cat"] }
+"#,
+        )
+        .unwrap();
+        let hook = hook.compile().unwrap();
+
+        let sink = Arc::new(MemorySink::new());
+        let executor = HookExecutor::new(test_config(temp_dir.path()), sink.clone());
+        let result = executor.execute(&hook, &message("10", "!echo hi")).await.unwrap();
+
+        assert!(result.executed);
+        let records = sink.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].target,
+            SinkTarget::Channel {
+                channel_id: "10".to_string()
+            }
+        );
+        assert_eq!(records[0].content.as_deref(), Some("Echo: !echo hi"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_send_dm_through_sink() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("echo.txt"), "{{content}}").unwrap();
+
+        let hook: HookConfig = serde_yaml::from_str(
+            r#"
+id: "dm-echo"
+channels: ["dm"]
+trigger: { type: "any" }
+prompt_file: "echo.txt"
+action: { type: "send_dm" }
+processing: { processor_type: "command", cmd: ["cat"] }
+"#,
+        )
+        .unwrap();
+        let hook = hook.compile().unwrap();
+
+        let sink = Arc::new(MemorySink::new());
+        let executor = HookExecutor::new(test_config(temp_dir.path()), sink.clone());
+        let result = executor.execute(&hook, &message("10", "hello")).await.unwrap();
+
+        assert!(result.executed);
+        assert_eq!(
+            sink.records()[0].target,
+            SinkTarget::User {
+                user_id: "2000".to_string()
+            }
+        );
+    }
     
+    #[tokio::test]
+    async fn test_throttled_user_gets_one_reaction() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("echo.txt"), "{{content}}").unwrap();
+
+        let hook: HookConfig = serde_yaml::from_str(
+            r#"
+id: "echo"
+channels: ["10"]
+prompt_file: "echo.txt"
+action: { type: "reply" }
+rate_limit: { per_user: 1, on_limit: { type: "react" } }
+"#,
+        )
+        .unwrap();
+        let hook = hook.compile().unwrap();
+
+        let sink = Arc::new(MemorySink::new());
+        let executor = HookExecutor::new(test_config(temp_dir.path()), sink.clone())
+            .with_processor_stub(ProcessorStub::Echo);
+        for _ in 0..3 {
+            executor.execute(&hook, &message("10", "hi")).await.unwrap();
+        }
+        let result = executor.execute(&hook, &message("10", "hi")).await.unwrap();
+
+        assert!(!result.executed);
+        assert_eq!(result.error.as_deref(), Some("Rate limited (user)"));
+        let records = sink.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[1].target,
+            SinkTarget::Reaction {
+                channel_id: "10".to_string(),
+                message_id: "1000".to_string()
+            }
+        );
+        assert_eq!(records[1].content.as_deref(), Some("⏳"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failed_hook_reacts_and_notifies() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("ask.txt"), "{{content}}").unwrap();
+
+        let hook: HookConfig = serde_yaml::from_str(
+            r#"
+id: "ask"
+channels: ["10"]
+prompt_file: "ask.txt"
+action: { type: "reply" }
+processing: { processor_type: "command", cmd: ["sh", "-c", "echo 'KeyError: x' >&2; exit 3"] }
+on_failure: { type: "react" }
+"#,
+        )
+        .unwrap();
+        let hook = hook.compile().unwrap();
+        let settings: Settings = serde_yaml::from_str(
+            "on_error: notify\nnotify: { type: channel, channel_id: \"99\" }\n",
+        )
+        .unwrap();
+
+        let sink = Arc::new(MemorySink::new());
+        let executor =
+            HookExecutor::new(test_config(temp_dir.path()), sink.clone()).with_settings(settings);
+        let error = executor.execute(&hook, &message("10", "why")).await.unwrap_err();
+
+        assert!(matches!(error, DiscliError::CommandFailed { .. }));
+        let records = sink.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].content.as_deref(), Some("❌"));
+        assert_eq!(
+            records[1].target,
+            SinkTarget::Channel {
+                channel_id: "99".to_string()
+            }
+        );
+        let fields = records[1].embeds[0]["fields"].to_string();
+        assert!(fields.contains("https://discord.com/channels/@me/10/1000"));
+        assert!(fields.contains("KeyError: x"));
+    }
+
+    #[tokio::test]
+    async fn test_logs_long_non_ascii_responses() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("p.txt"), "{{content}}").unwrap();
+        // Byte 50 falls inside a three-byte character
+        let response = "€".repeat(30);
+
+        let sink = Arc::new(MemorySink::new());
+        let executor = HookExecutor::new(test_config(temp_dir.path()), sink.clone())
+            .with_processor_stub(ProcessorStub::Output(response.clone()));
+        for action in [
+            "{ type: reply }",
+            "{ type: send_dm }",
+            "{ type: forward, channel_id: \"20\" }",
+            "{ type: webhook, url: \"https://example.com/hook\" }",
+        ] {
+            let hook: HookConfig = serde_yaml::from_str(&format!(
+                "id: \"euro\"\nchannels: [\"10\"]\nprompt_file: \"p.txt\"\naction: {}\n",
+                action
+            ))
+            .unwrap();
+            let result = executor.execute(&hook.compile().unwrap(), &message("10", "hi")).await;
+            assert!(result.unwrap().executed, "{}", action);
+        }
+        assert!(sink
+            .records()
+            .iter()
+            .all(|record| record.content.as_deref() == Some(response.as_str())));
+    }
+
+    #[tokio::test]
+    async fn test_missing_prompt_is_a_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        let hook: HookConfig = serde_yaml::from_str(
+            r#"
+id: "ask"
+channels: ["10"]
+prompt_file: "missing.txt"
+action: { type: "reply" }
+on_failure: { type: "react" }
+"#,
+        )
+        .unwrap();
+        let hook = hook.compile().unwrap();
+        let settings: Settings = serde_yaml::from_str(
+            "on_error: notify\nnotify: { type: channel, channel_id: \"99\" }\n",
+        )
+        .unwrap();
+
+        let sink = Arc::new(MemorySink::new());
+        let executor =
+            HookExecutor::new(test_config(temp_dir.path()), sink.clone()).with_settings(settings);
+        let error = executor.execute(&hook, &message("10", "why")).await.unwrap_err();
+
+        assert!(error.to_string().contains("Failed to load prompt missing.txt"), "{}", error);
+        let records = sink.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].content.as_deref(), Some("❌"));
+        assert!(records[1].embeds[0]["fields"].to_string().contains("missing.txt"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_busy_hook_replies_while_other_hooks_run() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("p.txt"), "{{content}}").unwrap();
+
+        // The slow hook's processor runs until the release file exists
+        let release = temp_dir.path().join("release");
+
+        let config: crate::hooks::config::HooksConfig = serde_yaml::from_str(
+            &r#"
+version: "1.0"
+hooks:
+  - id: "slow"
+    channels: ["10"]
+    prompt_file: "p.txt"
+    action: { type: "reply" }
+    processing: { processor_type: "command", cmd: ["sh", "-c", "until [ -e \"$0\" ]; do sleep 0.01; done; cat", "{release}"] }
+    max_concurrency: 1
+    on_busy: { type: "reply", message: "busy" }
+  - id: "fast"
+    channels: ["10"]
+    prompt_file: "p.txt"
+    action: { type: "forward", channel_id: "20" }
+    processing: { processor_type: "command", cmd: ["cat"] }
+"#
+            .replace("{release}", &release.display().to_string()),
+        )
+        .unwrap();
+        let hooks = config.compile_enabled().unwrap();
+        let (slow, fast) = (&hooks[0], &hooks[1]);
+
+        let sink = Arc::new(MemorySink::new());
+        let executor = HookExecutor::new(test_config(temp_dir.path()), sink.clone());
+        let events = [message("10", "one"), message("10", "two"), message("10", "three")];
+        // join! polls the first run first, so it holds the slow hook's
+        // only run before the others start
+        let others = async {
+            let second = executor.execute(slow, &events[1]).await;
+            let other = executor.execute(fast, &events[2]).await;
+            std::fs::write(&release, "").unwrap();
+            (second, other)
+        };
+        let (first, (second, other)) = tokio::join!(executor.execute(slow, &events[0]), others);
+
+        assert!(first.unwrap().executed);
+        let second = second.unwrap();
+        assert!(!second.executed);
+        assert_eq!(second.error.as_deref(), Some("Busy (1 runs in progress)"));
+        assert!(other.unwrap().executed);
+        let contents: Vec<Option<String>> =
+            sink.records().into_iter().map(|record| record.content).collect();
+        assert_eq!(
+            contents,
+            vec![Some("busy".to_string()), Some("three".to_string()), Some("one".to_string())]
+        );
+    }
+
     #[tokio::test]
     async fn test_rate_limiter_user() {
         let limiter = RateLimiter::new(2, 10, 60);
@@ -289,6 +740,7 @@ mod tests {
     fn test_hook_result_default() {
         let result = HookResult {
             executed: false,
+            prompt: None,
             response: None,
             error: None,
         };
@@ -302,7 +754,7 @@ mod tests {
     fn test_hook_action_reply() {
         let action = HookAction::Reply;
         match action {
-            HookAction::Reply => assert!(true),
+            HookAction::Reply => {}
             _ => panic!("Expected Reply variant"),
         }
     }
@@ -311,7 +763,7 @@ mod tests {
     fn test_hook_action_send_dm() {
         let action = HookAction::SendDm;
         match action {
-            HookAction::SendDm => assert!(true),
+            HookAction::SendDm => {}
             _ => panic!("Expected SendDm variant"),
         }
     }
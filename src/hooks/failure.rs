@@ -0,0 +1,201 @@
+//! Hook failure reports and user feedback
+//!
+//! When a hook's processor or response fails, `settings.on_error: notify`
+//! posts an [`error_report`] to the admin channel or webhook, and the
+//! hook's `on_failure` decides what the triggering user sees.
+
+use crate::discord::types::{DiscordMessage, Embed, EmbedField};
+use crate::error::DiscliError;
+use crate::hooks::event::IncomingEvent;
+use crate::prompt::substitute_variables;
+
+/// Sidebar colour of error reports (Discord's red)
+const REPORT_COLOR: u32 = 0xED4245;
+
+/// Longest embed field value Discord accepts
+const FIELD_LIMIT: usize = 1024;
+
+/// Lines of stderr kept in a report
+const STDERR_LINES: usize = 20;
+
+/// Link to the message that triggered the event, if there is one
+///
+/// Messages and reactions link to the message; other events have none.
+pub fn message_link(event: &IncomingEvent) -> Option<String> {
+    let (channel_id, message_id) = match (event.message(), event.reaction()) {
+        (Some(message), _) => (message.channel_id, message.id),
+        (None, Some(reaction)) => (reaction.channel_id, reaction.message_id),
+        (None, None) => return None,
+    };
+    let guild = event
+        .guild_id()
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| "@me".to_string());
+    Some(format!(
+        "https://discord.com/channels/{}/{}/{}",
+        guild, channel_id, message_id
+    ))
+}
+
+/// The last lines a failed command wrote to stderr
+///
+/// `None` for other errors, or when the command wrote nothing.
+pub fn stderr_tail(error: &DiscliError) -> Option<String> {
+    let DiscliError::CommandFailed { stderr, .. } = error else {
+        return None;
+    };
+    let lines: Vec<&str> = stderr.trim_end().lines().collect();
+    if lines.iter().all(|line| line.trim().is_empty()) {
+        return None;
+    }
+    let tail = lines[lines.len().saturating_sub(STDERR_LINES)..].join("\n");
+    Some(last_chars(&tail, FIELD_LIMIT - 8).to_string())
+}
+
+/// Error report for admins, as an embed
+pub fn error_report(hook_id: &str, event: &IncomingEvent, error: &DiscliError) -> DiscordMessage {
+    let field = |name: &str, value: String, inline: bool| EmbedField {
+        name: name.to_string(),
+        value,
+        inline,
+    };
+
+    let mut fields = vec![
+        field("Hook", format!("`{}`", hook_id), true),
+        field("Event", event.kind().to_string(), true),
+    ];
+    if let Some(user_id) = event.user_id() {
+        fields.push(field("User", format!("<@{}>", user_id), true));
+    }
+    if let Some(link) = message_link(event) {
+        fields.push(field("Message", link, false));
+    }
+    let summary = match error {
+        DiscliError::CommandFailed { status, .. } => format!("Command failed ({})", status),
+        other => other.to_string(),
+    };
+    fields.push(field("Error", first_chars(&summary, FIELD_LIMIT).to_string(), false));
+    if let Some(stderr) = stderr_tail(error) {
+        // Keep the tail from closing the code block early
+        let stderr = stderr.replace("```", "`\u{200b}``");
+        fields.push(field(
+            "Stderr",
+            format!("```\n{}\n```", last_chars(&stderr, FIELD_LIMIT - 8)),
+            false,
+        ));
+    }
+
+    DiscordMessage::WithEmbeds {
+        content: None,
+        embeds: vec![Embed {
+            title: Some(format!("Hook {} failed", hook_id)),
+            color: Some(REPORT_COLOR),
+            fields,
+            ..Default::default()
+        }],
+    }
+}
+
+/// Render an `on_failure` reply template
+///
+/// The template sees the event's variables plus `{{hook_id}}` and
+/// `{{error}}`.
+pub fn failure_reply(template: &str, hook_id: &str, event: &IncomingEvent, error: &DiscliError) -> String {
+    let mut vars = event.variables();
+    vars.insert("hook_id", hook_id.to_string());
+    vars.insert("error", error.to_string());
+    substitute_variables(template, &vars)
+}
+
+/// At most `max` characters from the start of `s`
+pub(crate) fn first_chars(s: &str, max: usize) -> &str {
+    match s.char_indices().nth(max) {
+        Some((end, _)) => &s[..end],
+        None => s,
+    }
+}
+
+/// At most `max` characters from the end of `s`
+fn last_chars(s: &str, max: usize) -> &str {
+    let count = s.chars().count();
+    match s.char_indices().nth(count.saturating_sub(max)) {
+        Some((start, _)) => &s[start..],
+        None => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::testing::{test_message, TestMessage};
+
+    fn message(guild: Option<&str>) -> IncomingEvent {
+        TestMessage {
+            author: "20".to_string(),
+            author_name: "tester".to_string(),
+            ..test_message("10", guild, "!ask why")
+        }
+        .to_event()
+        .unwrap()
+    }
+
+    fn failed(stderr: &str) -> DiscliError {
+        DiscliError::CommandFailed {
+            status: "exit status: 1".to_string(),
+            stderr: stderr.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_message_link() {
+        let link = message_link(&message(Some("30"))).unwrap();
+        assert!(link.starts_with("https://discord.com/channels/30/10/"));
+
+        let link = message_link(&message(None)).unwrap();
+        assert!(link.starts_with("https://discord.com/channels/@me/10/"));
+    }
+
+    #[test]
+    fn test_stderr_tail_keeps_last_lines() {
+        let stderr: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+        let tail = stderr_tail(&failed(&stderr)).unwrap();
+        assert!(tail.starts_with("line 11\n"));
+        assert!(tail.ends_with("line 30"));
+
+        assert_eq!(stderr_tail(&failed("  \n")), None);
+        assert_eq!(stderr_tail(&DiscliError::Processor("Command timed out".into())), None);
+
+        let long = "é".repeat(5000);
+        assert_eq!(stderr_tail(&failed(&long)).unwrap().chars().count(), FIELD_LIMIT - 8);
+    }
+
+    #[test]
+    fn test_error_report_fields() {
+        let report = error_report("ask", &message(Some("30")), &failed("Traceback\nKeyError: 'x'\n"));
+        let DiscordMessage::WithEmbeds { embeds, .. } = report else {
+            panic!("expected an embed");
+        };
+        let fields: Vec<(&str, &str)> = embeds[0]
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.value.as_str()))
+            .collect();
+        assert_eq!(fields[0], ("Hook", "`ask`"));
+        assert_eq!(fields[1], ("Event", "message_create"));
+        assert_eq!(fields[2], ("User", "<@20>"));
+        assert_eq!(fields[3].0, "Message");
+        assert_eq!(fields[4], ("Error", "Command failed (exit status: 1)"));
+        assert_eq!(fields[5], ("Stderr", "```\nTraceback\nKeyError: 'x'\n```"));
+    }
+
+    #[test]
+    fn test_failure_reply_template() {
+        let reply = failure_reply(
+            "Sorry {{author_name}}, {{hook_id}} broke: {{error}}",
+            "ask",
+            &message(None),
+            &DiscliError::Processor("Command timed out".into()),
+        );
+        assert_eq!(reply, "Sorry tester, ask broke: Processor error: Command timed out");
+    }
+}
@@ -2,14 +2,60 @@
 //! 
 //! This module provides:
 //! - Hook configuration loading from YAML
+//! - Gateway events hooks can run on (messages, reactions, members, ...)
 //! - Trigger matching (prefix, regex, mention)
+//! - Filters on servers, categories, users, roles and permissions
 //! - Hook execution with prompt processing
+//! - Rate limits and cooldowns
+//! - Concurrency limits with queues for busy hooks
+//! - Error reports and user feedback when hooks fail
+//! - Reloading hooks.yaml while listening
+//! - Recording gateway events for offline replay
+//! - Declarative hook test suites
+//! - Validating hooks.yaml with located diagnostics
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use discli::hooks::{should_trigger, CompiledHookConfig, HooksConfig};
+//! use std::path::PathBuf;
+//!
+//! # fn example() -> discli::Result<()> {
+//! let config = HooksConfig::load(&PathBuf::from("hooks.yaml"))?;
+//! let hooks: Vec<CompiledHookConfig> = config
+//!     .enabled_hooks()
+//!     .into_iter()
+//!     .map(|hook| hook.compile())
+//!     .collect::<discli::Result<_>>()?;
+//! println!("{} hooks ready", hooks.len());
+//! # Ok(())
+//! # }
+//! ```
 
+pub mod concurrency;
 pub mod config;
+pub mod context;
+pub mod dispatch;
+pub mod event;
 pub mod trigger;
 pub mod executor;
+pub mod failure;
+pub mod rate_limit;
+pub mod recording;
+pub mod reload;
+pub mod testing;
+pub mod validate;
 
-pub use config::{HookConfig, HooksConfig, TriggerConfig, FilterConfig, HookAction, Processor, CompiledHookConfig, CompiledTrigger};
-pub use trigger::{TriggerMatcher, should_trigger, matches_filter, matches_channels};
-pub use executor::{HookExecutor, HookResult, RateLimiter};
+pub use config::{HookConfig, HooksConfig, TriggerConfig, FilterConfig, HookAction, Processor, CompiledHookConfig, CompiledTrigger, RateLimitConfig, HookRateLimit, ThrottleResponse, BusyPolicy, Settings, ErrorStrategy, NotifyTarget, FailureFeedback, BotPolicy};
+pub use concurrency::{Busy, ConcurrencyLimiter, Slot};
+pub use context::{ContextNeeds, EventContext, GuildCache};
+pub use dispatch::HookDispatcher;
+pub use event::{HookEvent, IncomingEvent};
+pub use trigger::{TriggerMatcher, TriggerTrace, trace_trigger, Mismatch, check_trigger, check_trigger_in, should_trigger, should_trigger_in, matches_filter, matches_channels};
+pub use executor::{HookExecutor, HookResult, ProcessorStub};
+pub use rate_limit::{Limit, RateLimiter, Throttled};
+pub use recording::{read_recording, EventRecorder, RecordedEvent, Recording};
+pub use reload::{HooksReloader, Reload, ReloadSummary};
+pub use testing::{junit_report, TestCase, TestMessage, TestOutcome, TestSuite};
+pub use validate::{validate_file, validate_source, Diagnostic, Location};
 pub use crate::processing::{CommandProcessor, HttpProcessor};
@@ -0,0 +1,413 @@
+//! Rate limits and cooldowns for hooks
+//!
+//! Triggers are counted in sliding windows per user, channel and server.
+//! Every window lives in its own bucket of a hash map, so a check costs the
+//! same however many users are active. Hooks with their own `rate_limit`
+//! are counted in their own buckets; all other hooks share the global
+//! buckets configured by `settings.rate_limit`.
+
+use crate::hooks::config::{CompiledHookConfig, RateLimitConfig, ThrottleResponse};
+use crate::hooks::event::IncomingEvent;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Expired buckets are swept out every this many checks
+const SWEEP_EVERY: u64 = 1024;
+
+/// Rate limiter for hooks
+pub struct RateLimiter {
+    settings: RwLock<RateLimitConfig>,
+    state: Mutex<State>,
+}
+
+/// What an event was counted against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Limit {
+    /// Triggers by one user
+    User,
+    /// Triggers in one channel
+    Channel,
+    /// Triggers in one server
+    Guild,
+    /// The hook's cooldown for one user
+    Cooldown,
+}
+
+/// Why an event may not run a hook right now
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Throttled {
+    /// The limit that was hit
+    pub limit: Limit,
+    /// How long until the event would be allowed
+    pub retry_after: Duration,
+    /// Whether this is the first event throttled by this limit in the
+    /// current window, and so should get `response`
+    pub notify: bool,
+    /// What to do about it
+    pub response: ThrottleResponse,
+}
+
+impl fmt::Display for Throttled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.limit {
+            Limit::User => f.write_str("Rate limited (user)"),
+            Limit::Channel => f.write_str("Rate limited (channel)"),
+            Limit::Guild => f.write_str("Rate limited (server)"),
+            Limit::Cooldown => write!(f, "On cooldown (retry in {}s)", self.retry_after_secs()),
+        }
+    }
+}
+
+impl Throttled {
+    /// `retry_after` in whole seconds, rounded up
+    pub fn retry_after_secs(&self) -> u64 {
+        let secs = self.retry_after.as_secs();
+        if self.retry_after.subsec_nanos() > 0 {
+            secs + 1
+        } else {
+            secs.max(1)
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    windows: HashMap<BucketKey, Window>,
+    cooldowns: HashMap<(String, String), Cooldown>,
+    checks: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct BucketKey {
+    /// Hook with its own limits, or `None` for the global buckets
+    hook: Option<String>,
+    limit: Limit,
+    id: String,
+}
+
+/// Recent triggers counted against one limit
+struct Window {
+    hits: VecDeque<Instant>,
+    length: Duration,
+    notified: bool,
+}
+
+impl Window {
+    fn new(length: Duration) -> Self {
+        Self {
+            hits: VecDeque::new(),
+            length,
+            notified: false,
+        }
+    }
+
+    /// Forget triggers older than the window
+    fn prune(&mut self, now: Instant) {
+        while self
+            .hits
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > self.length)
+        {
+            self.hits.pop_front();
+        }
+    }
+}
+
+struct Cooldown {
+    last: Instant,
+    length: Duration,
+    notified: bool,
+}
+
+impl RateLimiter {
+    /// Create a limiter with global per-user and per-channel limits
+    pub fn new(per_user: u32, per_channel: u32, window_secs: u64) -> Self {
+        Self::from_config(RateLimitConfig {
+            per_user,
+            per_channel,
+            window_seconds: window_secs,
+            ..RateLimitConfig::default()
+        })
+    }
+
+    /// Create a limiter from `settings.rate_limit`
+    pub fn from_config(settings: RateLimitConfig) -> Self {
+        Self {
+            settings: RwLock::new(settings),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// The global limits
+    pub fn settings(&self) -> RateLimitConfig {
+        self.settings.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Change the global limits, keeping the triggers counted so far
+    pub fn set_settings(&self, settings: RateLimitConfig) {
+        *self.settings.write().unwrap_or_else(|e| e.into_inner()) = settings;
+    }
+
+    /// Check if user is rate limited
+    pub async fn check_user(&self, user_id: &str) -> bool {
+        let settings = self.settings();
+        let limits = [(Limit::User, user_id.to_string(), Some(settings.per_user))];
+        self.acquire(None, &limits, &settings).is_ok()
+    }
+
+    /// Check if channel is rate limited
+    pub async fn check_channel(&self, channel_id: &str) -> bool {
+        let settings = self.settings();
+        let limits = [(Limit::Channel, channel_id.to_string(), Some(settings.per_channel))];
+        self.acquire(None, &limits, &settings).is_ok()
+    }
+
+    /// Count a trigger of `hook` by `event`, unless a limit or the hook's
+    /// cooldown says it must not run
+    ///
+    /// Nothing is counted for a throttled event. Limits on things the
+    /// event doesn't have (a member leaving has no channel) don't apply.
+    pub fn check(&self, hook: &CompiledHookConfig, event: &IncomingEvent) -> Result<(), Throttled> {
+        let (owner, settings) = match &hook.rate_limit {
+            Some(own) => (Some(hook.id.as_str()), own.resolve(&self.settings())),
+            None => (None, self.settings()),
+        };
+
+        let user = event.user_id().map(|id| id.to_string());
+        let limits = [
+            (Limit::User, user.clone(), Some(settings.per_user)),
+            (
+                Limit::Channel,
+                event.channel_id().map(|id| id.to_string()),
+                Some(settings.per_channel),
+            ),
+            (
+                Limit::Guild,
+                event.guild_id().map(|id| id.to_string()),
+                settings.per_guild,
+            ),
+        ];
+        let limits: Vec<(Limit, String, Option<u32>)> = limits
+            .into_iter()
+            .filter_map(|(limit, id, max)| id.map(|id| (limit, id, max)))
+            .collect();
+
+        // Events without a user cool down per channel
+        let cooldown = (hook.cooldown_seconds > 0)
+            .then(|| user.or_else(|| event.channel_id().map(|id| format!("#{}", id))))
+            .flatten()
+            .map(|subject| (hook.id.clone(), subject));
+        if let Some(key) = &cooldown {
+            self.check_cooldown(key, &settings)?;
+        }
+
+        self.acquire(owner, &limits, &settings)?;
+
+        if let Some(key) = cooldown {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.cooldowns.insert(
+                key,
+                Cooldown {
+                    last: Instant::now(),
+                    length: Duration::from_secs(hook.cooldown_seconds),
+                    notified: false,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Fail if the hook is still cooling down for the subject in `key`
+    fn check_cooldown(
+        &self,
+        key: &(String, String),
+        settings: &RateLimitConfig,
+    ) -> Result<(), Throttled> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(cooldown) = state.cooldowns.get_mut(key) else {
+            return Ok(());
+        };
+
+        let elapsed = now.duration_since(cooldown.last);
+        if elapsed >= cooldown.length {
+            return Ok(());
+        }
+        let notify = !cooldown.notified;
+        cooldown.notified = true;
+        Err(Throttled {
+            limit: Limit::Cooldown,
+            retry_after: cooldown.length - elapsed,
+            notify,
+            response: settings.on_limit.clone(),
+        })
+    }
+
+    /// Count a trigger against every limit, or none if any is full
+    fn acquire(
+        &self,
+        hook: Option<&str>,
+        limits: &[(Limit, String, Option<u32>)],
+        settings: &RateLimitConfig,
+    ) -> Result<(), Throttled> {
+        let now = Instant::now();
+        let length = Duration::from_secs(settings.window_seconds);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        state.checks += 1;
+        if state.checks.is_multiple_of(SWEEP_EVERY) {
+            state.sweep(now);
+        }
+
+        let key = |limit: Limit, id: &str| BucketKey {
+            hook: hook.map(str::to_string),
+            limit,
+            id: id.to_string(),
+        };
+
+        for (limit, id, max) in limits {
+            let Some(max) = max else { continue };
+            let window = state
+                .windows
+                .entry(key(*limit, id))
+                .or_insert_with(|| Window::new(length));
+            window.length = length;
+            window.prune(now);
+
+            if window.hits.len() >= *max as usize {
+                let notify = !window.notified;
+                window.notified = true;
+                let retry_after = window
+                    .hits
+                    .front()
+                    .map(|oldest| length.saturating_sub(now.duration_since(*oldest)))
+                    .unwrap_or(length);
+                return Err(Throttled {
+                    limit: *limit,
+                    retry_after,
+                    notify,
+                    response: settings.on_limit.clone(),
+                });
+            }
+            window.notified = false;
+        }
+
+        for (limit, id, max) in limits {
+            if max.is_some() {
+                if let Some(window) = state.windows.get_mut(&key(*limit, id)) {
+                    window.hits.push_back(now);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl State {
+    /// Drop buckets and cooldowns that no longer hold anything back
+    fn sweep(&mut self, now: Instant) {
+        self.windows.retain(|_, window| {
+            window.prune(now);
+            !window.hits.is_empty()
+        });
+        self.cooldowns
+            .retain(|_, cooldown| now.duration_since(cooldown.last) < cooldown.length);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::config::{HookRateLimit, HooksConfig};
+    use crate::hooks::testing::{test_message, TestMessage};
+
+    fn hook(rate_limit: Option<HookRateLimit>, cooldown_seconds: u64) -> CompiledHookConfig {
+        let config: HooksConfig = serde_yaml::from_str(
+            r#"
+version: "1.0"
+hooks:
+  - id: "ping"
+    channels: ["10"]
+    prompt_file: "p.txt"
+    action: { type: "reply" }
+"#,
+        )
+        .unwrap();
+        let mut hook = config.hooks[0].compile().unwrap();
+        hook.rate_limit = rate_limit;
+        hook.cooldown_seconds = cooldown_seconds;
+        hook
+    }
+
+    fn message(author: &str, guild: Option<&str>) -> IncomingEvent {
+        TestMessage {
+            author: author.to_string(),
+            ..test_message("10", guild, "!ping")
+        }
+        .to_event()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_guild_limit_and_single_notice() {
+        let limiter = RateLimiter::from_config(RateLimitConfig {
+            per_guild: Some(2),
+            on_limit: ThrottleResponse::React {
+                emoji: "⏳".to_string(),
+            },
+            ..RateLimitConfig::default()
+        });
+        let hook = hook(None, 0);
+
+        assert!(limiter.check(&hook, &message("1", Some("20"))).is_ok());
+        assert!(limiter.check(&hook, &message("2", Some("20"))).is_ok());
+
+        let throttled = limiter.check(&hook, &message("3", Some("20"))).unwrap_err();
+        assert_eq!(throttled.limit, Limit::Guild);
+        assert!(throttled.notify);
+        assert_eq!(throttled.to_string(), "Rate limited (server)");
+        let again = limiter.check(&hook, &message("3", Some("20"))).unwrap_err();
+        assert!(!again.notify);
+
+        // Direct messages have no server to count against
+        assert!(limiter.check(&hook, &message("3", None)).is_ok());
+    }
+
+    #[test]
+    fn test_hook_limits_are_separate_and_inherit() {
+        let limiter = RateLimiter::new(5, 10, 60);
+        let own = hook(
+            Some(HookRateLimit {
+                per_user: Some(1),
+                ..HookRateLimit::default()
+            }),
+            0,
+        );
+        let shared = hook(None, 0);
+
+        assert!(limiter.check(&own, &message("1", None)).is_ok());
+        let throttled = limiter.check(&own, &message("1", None)).unwrap_err();
+        assert_eq!(throttled.limit, Limit::User);
+        assert!(throttled.retry_after <= Duration::from_secs(60));
+
+        // The hook's triggers didn't count against the global buckets
+        for _ in 0..5 {
+            assert!(limiter.check(&shared, &message("1", None)).is_ok());
+        }
+        assert!(limiter.check(&shared, &message("1", None)).is_err());
+    }
+
+    #[test]
+    fn test_cooldown_per_user() {
+        let limiter = RateLimiter::new(5, 10, 60);
+        let hook = hook(None, 30);
+
+        assert!(limiter.check(&hook, &message("1", None)).is_ok());
+        let throttled = limiter.check(&hook, &message("1", None)).unwrap_err();
+        assert_eq!(throttled.limit, Limit::Cooldown);
+        assert_eq!(throttled.to_string(), "On cooldown (retry in 30s)");
+        assert!(limiter.check(&hook, &message("2", None)).is_ok());
+    }
+}
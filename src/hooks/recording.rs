@@ -0,0 +1,297 @@
+//! Recording gateway events for later replay
+//!
+//! `discli listen --record` writes one JSON line per event the listener
+//! sees and `discli replay` reads them back. Each line holds the event's
+//! gateway dispatch type and payload, exactly as Discord sends them, and
+//! how long after the start of the recording it arrived:
+//!
+//! ```json
+//! {"offset_ms":1520,"t":"MESSAGE_CREATE","d":{"id":"1000","content":"!help",...}}
+//! ```
+//!
+//! READY is recorded too, with only the bot's user, so a replay knows who
+//! the bot is:
+//!
+//! ```json
+//! {"offset_ms":310,"t":"READY","d":{"user":{"id":"999","username":"discli",...}}}
+//! ```
+
+use crate::discord::gateway::RawDispatch;
+use crate::error::{DiscliError, Result};
+use crate::hooks::event::IncomingEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// One line of a recording, with the payload as `D`
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordLine<D> {
+    /// Milliseconds since the recording started
+    offset_ms: u64,
+    /// Gateway dispatch type, e.g. `MESSAGE_CREATE`
+    t: String,
+    /// Gateway dispatch payload
+    d: D,
+}
+
+/// The parts of a gateway payload a recording keeps
+#[derive(Deserialize)]
+struct Dispatch<'a> {
+    t: String,
+    #[serde(borrow)]
+    d: &'a RawValue,
+}
+
+/// The bot's user in a READY payload, all a recording keeps of it
+#[derive(Serialize, Deserialize)]
+struct Ready<U> {
+    user: U,
+}
+
+/// The part of the bot's user a replay needs
+#[derive(Deserialize)]
+struct ReadyUser {
+    id: String,
+}
+
+/// A recording read back from its file
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    /// The bot's user ID, from the recorded READY
+    pub bot_user: Option<String>,
+    /// The recorded events, in file order
+    pub events: Vec<RecordedEvent>,
+}
+
+/// An event read back from a recording
+#[derive(Debug, Clone)]
+pub struct RecordedEvent {
+    /// When the event arrived, relative to the start of the recording
+    pub offset: Duration,
+    /// The event itself
+    pub event: IncomingEvent,
+}
+
+/// Writes gateway dispatches to a recording file as they arrive
+///
+/// Dispatches sent to [`EventRecorder::sender`] are written by a task of
+/// their own, so whoever sends them never waits for the file.
+pub struct EventRecorder {
+    sender: mpsc::UnboundedSender<RawDispatch>,
+    writer: JoinHandle<Result<()>>,
+}
+
+impl EventRecorder {
+    /// Create the recording file, replacing any existing one, and start
+    /// writing to it
+    ///
+    /// Must be called from within a tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created
+    pub async fn create(path: &Path) -> Result<Self> {
+        let file = tokio::fs::File::create(path).await?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Ok(Self {
+            sender,
+            writer: tokio::spawn(write_dispatches(file, receiver, Instant::now())),
+        })
+    }
+
+    /// Where to send dispatches to record, e.g. for
+    /// [`DiscordGateway::with_raw_dispatches`](crate::discord::gateway::DiscordGateway::with_raw_dispatches)
+    pub fn sender(&self) -> mpsc::UnboundedSender<RawDispatch> {
+        self.sender.clone()
+    }
+
+    /// Wait until every other sender is dropped and what they sent is
+    /// written
+    ///
+    /// # Errors
+    ///
+    /// Returns the error that stopped the recording, if a dispatch could
+    /// not be read or written
+    pub async fn finish(self) -> Result<()> {
+        drop(self.sender);
+        self.writer
+            .await
+            .map_err(|e| DiscliError::Io(std::io::Error::other(e)))?
+    }
+}
+
+/// Write each dispatch as a line stamped with its time since `started`,
+/// flushing whenever no more are waiting
+async fn write_dispatches(
+    file: tokio::fs::File,
+    mut receiver: mpsc::UnboundedReceiver<RawDispatch>,
+    started: Instant,
+) -> Result<()> {
+    let mut file = BufWriter::new(file);
+    while let Some(raw) = receiver.recv().await {
+        let malformed =
+            |e: serde_json::Error| DiscliError::Validation(format!("malformed gateway dispatch: {}", e));
+        let dispatch: Dispatch = serde_json::from_str(&raw.payload).map_err(malformed)?;
+        let offset_ms = raw.received.saturating_duration_since(started).as_millis() as u64;
+        let line = if dispatch.t == "READY" {
+            // The rest of READY (session, servers) is of no use to a replay
+            let ready: Ready<&RawValue> = serde_json::from_str(dispatch.d.get()).map_err(malformed)?;
+            serde_json::to_string(&RecordLine {
+                offset_ms,
+                t: dispatch.t,
+                d: ready,
+            })
+        } else {
+            serde_json::to_string(&RecordLine {
+                offset_ms,
+                t: dispatch.t,
+                d: dispatch.d,
+            })
+        };
+        let mut line = line.map_err(std::io::Error::other)?;
+        line.push('\n');
+        file.write_all(line.as_bytes()).await?;
+        if receiver.is_empty() {
+            file.flush().await?;
+        }
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+/// Read a recording: every event, in file order, and who the bot is
+///
+/// Blank lines are skipped.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read, or a validation error
+/// naming the line if one doesn't hold READY or an event hooks can run on
+pub fn read_recording(path: &Path) -> Result<Recording> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut recording = Recording::default();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let invalid = |message: String| {
+            DiscliError::Validation(format!("{}:{}: {}", path.display(), index + 1, message))
+        };
+        let record: RecordLine<serde_json::Value> =
+            serde_json::from_str(&line).map_err(|e| invalid(format!("invalid record: {}", e)))?;
+        if record.t == "READY" {
+            let ready: Ready<ReadyUser> = serde_json::from_value(record.d)
+                .map_err(|e| invalid(format!("invalid READY payload: {}", e)))?;
+            recording.bot_user = Some(ready.user.id);
+            continue;
+        }
+        let event = IncomingEvent::from_dispatch(&record.t, &record.d).map_err(|e| match e {
+            DiscliError::Validation(message) => invalid(message),
+            other => other,
+        })?;
+
+        recording.events.push(RecordedEvent {
+            offset: Duration::from_millis(record.offset_ms),
+            event,
+        });
+    }
+
+    Ok(recording)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::event::HookEvent;
+    use crate::hooks::testing;
+    use tempfile::TempDir;
+
+    fn message_create(content: &str) -> String {
+        testing::message_data(serde_json::json!({ "guild_id": "20", "content": content }))
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_record_and_read_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("events.jsonl");
+
+        let recorder = EventRecorder::create(&path).await.unwrap();
+        let sender = recorder.sender();
+        let data = [message_create("!help"), message_create("hello")];
+        for d in &data {
+            let payload = format!(r#"{{"op":0,"s":1,"t":"MESSAGE_CREATE","d":{}}}"#, d);
+            sender
+                .send(RawDispatch {
+                    received: Instant::now(),
+                    payload,
+                })
+                .unwrap();
+        }
+        drop(sender);
+        recorder.finish().await.unwrap();
+
+        // Payloads are kept as Discord sent them
+        let recording = std::fs::read_to_string(&path).unwrap();
+        for (line, d) in recording.lines().zip(&data) {
+            assert!(line.ends_with(&format!(r#""t":"MESSAGE_CREATE","d":{}}}"#, d)), "{}", line);
+        }
+
+        let recording = read_recording(&path).unwrap();
+        assert_eq!(recording.bot_user, None);
+        let events = recording.events;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event.kind(), HookEvent::MessageCreate);
+        assert_eq!(events[0].event.text(), "!help");
+        assert_eq!(events[1].event.text(), "hello");
+        assert_eq!(events[1].event.channel_id().unwrap().get(), 10);
+        assert!(events[0].offset <= events[1].offset);
+    }
+
+    #[tokio::test]
+    async fn test_records_bot_user_from_ready() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("events.jsonl");
+
+        let recorder = EventRecorder::create(&path).await.unwrap();
+        let payload = r#"{"op":0,"s":1,"t":"READY","d":{"v":10,"session_id":"secret","guilds":[],"user":{"id":"999","username":"discli"}}}"#;
+        recorder
+            .sender()
+            .send(RawDispatch {
+                received: Instant::now(),
+                payload: payload.to_string(),
+            })
+            .unwrap();
+        recorder.finish().await.unwrap();
+
+        let recorded = std::fs::read_to_string(&path).unwrap();
+        assert!(!recorded.contains("secret"), "{}", recorded);
+
+        let recording = read_recording(&path).unwrap();
+        assert_eq!(recording.bot_user.as_deref(), Some("999"));
+        assert!(recording.events.is_empty());
+    }
+
+    #[test]
+    fn test_read_reports_bad_line() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("events.jsonl");
+        std::fs::write(
+            &path,
+            "\n{\"offset_ms\":0,\"t\":\"READY\",\"d\":{}}\n",
+        )
+        .unwrap();
+
+        let err = read_recording(&path).unwrap_err().to_string();
+        assert!(err.contains("events.jsonl:2:"), "{}", err);
+    }
+}
@@ -0,0 +1,201 @@
+//! Reloading hooks.yaml while the listener runs
+//!
+//! [`HooksReloader`] re-reads the hooks file when asked (on SIGHUP, or when
+//! [`HooksReloader::changed_on_disk`] notices an edit) and hands back the
+//! new compiled hooks only if the whole file is valid, so a typo never
+//! replaces a working hook set.
+
+use crate::error::{DiscliError, Result};
+use crate::hooks::config::{CompiledHookConfig, HookConfig, HooksConfig};
+use crate::prompt::loader::{file_stamp, FileStamp};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Watches one hooks file and reloads it on request
+pub struct HooksReloader {
+    path: PathBuf,
+    current: HooksConfig,
+    stamp: Option<FileStamp>,
+}
+
+/// A successfully reloaded hook set
+pub struct Reload {
+    /// The new configuration
+    pub config: HooksConfig,
+    /// Its enabled hooks, compiled
+    pub hooks: Vec<CompiledHookConfig>,
+    /// What changed since the previous configuration
+    pub summary: ReloadSummary,
+}
+
+impl HooksReloader {
+    /// Start watching `path`, whose current contents were loaded as `current`
+    pub fn new(path: PathBuf, current: HooksConfig) -> Self {
+        let stamp = file_stamp(&path);
+        Self {
+            path,
+            current,
+            stamp,
+        }
+    }
+
+    /// The watched hooks file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file was modified since it was last (re)loaded
+    pub fn changed_on_disk(&self) -> bool {
+        file_stamp(&self.path) != self.stamp
+    }
+
+    /// Re-read, validate and compile the hooks file
+    ///
+    /// The file is considered seen either way, so a broken edit is only
+    /// reported once and the next edit triggers another attempt.
+    ///
+    /// # Errors
+    ///
+    /// Returns a configuration error if the file doesn't parse or
+    /// validate, or if any enabled hook fails to compile; the previous
+    /// configuration stays current
+    pub fn reload(&mut self) -> Result<Reload> {
+        self.stamp = file_stamp(&self.path);
+        let config = HooksConfig::load(&self.path)?;
+        let hooks = config.compile_enabled()?;
+        if hooks.is_empty() {
+            return Err(DiscliError::Config("No enabled hooks".into()));
+        }
+
+        let summary = ReloadSummary::between(&self.current, &config);
+        self.current = config.clone();
+        Ok(Reload {
+            config,
+            hooks,
+            summary,
+        })
+    }
+}
+
+/// Hook IDs added, removed and changed by a reload
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadSummary {
+    /// Hooks that are new
+    pub added: Vec<String>,
+    /// Hooks that are gone
+    pub removed: Vec<String>,
+    /// Hooks whose definition changed (including being enabled or disabled)
+    pub changed: Vec<String>,
+    /// Number of hooks that are exactly as before
+    pub unchanged: usize,
+}
+
+impl ReloadSummary {
+    /// Compare two configurations hook by hook, matching hooks by ID
+    pub fn between(old: &HooksConfig, new: &HooksConfig) -> Self {
+        let definition = |hook: &HookConfig| serde_json::to_value(hook).ok();
+
+        let mut summary = ReloadSummary::default();
+        for hook in &new.hooks {
+            match old.hooks.iter().find(|h| h.id == hook.id) {
+                None => summary.added.push(hook.id.clone()),
+                Some(previous) if definition(previous) != definition(hook) => {
+                    summary.changed.push(hook.id.clone())
+                }
+                Some(_) => summary.unchanged += 1,
+            }
+        }
+        for hook in &old.hooks {
+            if !new.hooks.iter().any(|h| h.id == hook.id) {
+                summary.removed.push(hook.id.clone());
+            }
+        }
+        summary
+    }
+
+    /// Whether any hook was added, removed or changed
+    pub fn has_changes(&self) -> bool {
+        !(self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty())
+    }
+}
+
+impl fmt::Display for ReloadSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.has_changes() {
+            return write!(f, "no hook changes ({} unchanged)", self.unchanged);
+        }
+        let mut parts = Vec::new();
+        for (label, ids) in [
+            ("added", &self.added),
+            ("removed", &self.removed),
+            ("changed", &self.changed),
+        ] {
+            if !ids.is_empty() {
+                parts.push(format!("{} {} ({})", ids.len(), label, ids.join(", ")));
+            }
+        }
+        parts.push(format!("{} unchanged", self.unchanged));
+        f.write_str(&parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn hooks_yaml(hooks: &[(&str, &str)]) -> String {
+        let mut yaml = String::from("version: \"1.0\"\nhooks:\n");
+        for (id, prefix) in hooks {
+            yaml.push_str(&format!(
+                "  - id: {}\n    channels: [\"10\"]\n    trigger: {{ type: prefix, prefix: \"{}\" }}\n    prompt_file: p.txt\n    action: {{ type: reply }}\n",
+                id, prefix
+            ));
+        }
+        yaml
+    }
+
+    #[test]
+    fn test_reload_reports_diff() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hooks.yaml");
+        std::fs::write(&path, hooks_yaml(&[("help", "!help"), ("ping", "!ping"), ("old", "!old")]))
+            .unwrap();
+
+        let mut reloader = HooksReloader::new(path.clone(), HooksConfig::load(&path).unwrap());
+        assert!(!reloader.changed_on_disk());
+
+        std::fs::write(
+            &path,
+            hooks_yaml(&[("help", "!help"), ("ping", "!pong"), ("new", "!new")]),
+        )
+        .unwrap();
+        assert!(reloader.changed_on_disk());
+
+        let reload = reloader.reload().unwrap();
+        assert_eq!(reload.hooks.len(), 3);
+        assert_eq!(
+            reload.summary.to_string(),
+            "1 added (new), 1 removed (old), 1 changed (ping), 1 unchanged"
+        );
+        assert!(!reloader.changed_on_disk());
+    }
+
+    #[test]
+    fn test_invalid_reload_keeps_previous() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hooks.yaml");
+        std::fs::write(&path, hooks_yaml(&[("help", "!help")])).unwrap();
+        let mut reloader = HooksReloader::new(path.clone(), HooksConfig::load(&path).unwrap());
+
+        std::fs::write(&path, "version: \"1.0\"\nhooks: [").unwrap();
+        assert!(reloader.reload().is_err());
+        assert!(!reloader.changed_on_disk());
+
+        // The diff is still against the last good configuration
+        std::fs::write(&path, hooks_yaml(&[("help", "!help")])).unwrap();
+        let reload = reloader.reload().unwrap();
+        assert!(!reload.summary.has_changes());
+        assert_eq!(reload.summary.to_string(), "no hook changes (1 unchanged)");
+    }
+}
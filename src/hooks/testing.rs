@@ -0,0 +1,766 @@
+//! Declarative hook test suites
+//!
+//! A test suite (conventionally `hooks.test.yaml`) lists input messages and
+//! what the hooks should do with them:
+//!
+//! ```yaml
+//! hooks_file: hooks.yaml
+//! tests:
+//!   - name: deploy from the ops channel
+//!     message:
+//!       channel: "123"
+//!       guild: "789"
+//!       author: "456"
+//!       content: "!deploy prod"
+//!     processor_output: "Deploying prod"
+//!     expect:
+//!       - hook: deploy
+//!         prompt_contains: ["prod"]
+//!         action: reply
+//!         sent_to: "123"
+//!         response_contains: "Deploying"
+//! ```
+//!
+//! `expect` lists every hook that should fire, in the order they run; an
+//! empty list asserts that none does. Processors never run: each hook
+//! responds with `processor_output`, or with its rendered prompt if that is
+//! not given.
+
+use crate::error::{DiscliError, Result};
+use crate::hooks::config::{parse_permissions, CompiledHookConfig, HookAction};
+use crate::hooks::context::{BotIdentity, EventContext};
+use crate::hooks::event::IncomingEvent;
+use crate::hooks::executor::{HookExecutor, HookResult, ProcessorStub};
+use crate::hooks::dispatch::{run_order, select};
+use crate::sink::{MemorySink, SinkRecord, SinkTarget};
+use regex::Regex;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use twilight_model::util::Timestamp;
+
+/// A hook test suite
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestSuite {
+    /// Hooks file under test, relative to the suite file
+    #[serde(default)]
+    pub hooks_file: Option<PathBuf>,
+    /// Prompts directory, relative to the suite file
+    #[serde(default)]
+    pub prompts_dir: Option<PathBuf>,
+    /// The test cases
+    pub tests: Vec<TestCase>,
+}
+
+/// One input message and the hooks it should fire
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestCase {
+    /// Name shown in the summary and the JUnit report
+    pub name: String,
+    /// The input message
+    pub message: TestMessage,
+    /// What every hook's processor responds with (default: the prompt)
+    #[serde(default)]
+    pub processor_output: Option<String>,
+    /// Every hook that should fire, in the order they run
+    #[serde(default)]
+    pub expect: Vec<ExpectedHook>,
+}
+
+/// User ID the bot has in simulated messages
+pub const SIMULATED_BOT_ID: &str = "999";
+
+/// A synthetic message, as posted to Discord
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestMessage {
+    /// Channel ID the message is posted in
+    pub channel: String,
+    /// Server ID; `None` for a direct message
+    #[serde(default)]
+    pub guild: Option<String>,
+    /// Author's user ID
+    #[serde(default = "default_author")]
+    pub author: String,
+    /// Author's username
+    #[serde(default = "default_author_name")]
+    pub author_name: String,
+    /// Author's role IDs in the server
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// Whether the author is a bot account
+    #[serde(default)]
+    pub bot: bool,
+    /// Category the channel is in
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Author's permissions in the channel, e.g. `MANAGE_MESSAGES`
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    /// The message mentions the bot; `<@999>` is put in front of the
+    /// content unless it is already there
+    #[serde(default)]
+    pub mentions_bot: bool,
+    /// The message replies to one of the bot's messages
+    #[serde(default)]
+    pub reply_to_bot: bool,
+    /// The message is in a thread the bot started
+    #[serde(default)]
+    pub in_bot_thread: bool,
+    /// The message replies to another message (by someone other than the
+    /// bot)
+    #[serde(default)]
+    pub reply: bool,
+    /// The message carries an embed, such as a link preview
+    #[serde(default)]
+    pub embed: bool,
+    /// Attachment filenames; their MIME types are guessed from the
+    /// extension
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    /// Message content
+    #[serde(default)]
+    pub content: String,
+}
+
+impl Default for TestMessage {
+    /// A message with no content in channel 1, in no server, by the
+    /// default author
+    fn default() -> Self {
+        Self {
+            channel: "1".to_string(),
+            guild: None,
+            author: default_author(),
+            author_name: default_author_name(),
+            roles: Vec::new(),
+            bot: false,
+            category: None,
+            permissions: Vec::new(),
+            mentions_bot: false,
+            reply_to_bot: false,
+            in_bot_thread: false,
+            reply: false,
+            embed: false,
+            attachments: Vec::new(),
+            content: String::new(),
+        }
+    }
+}
+
+fn default_author() -> String {
+    "1".to_string()
+}
+
+fn default_author_name() -> String {
+    "simulated-user".to_string()
+}
+
+/// What one fired hook should have done
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExpectedHook {
+    /// ID of the hook
+    pub hook: String,
+    /// Substrings the rendered prompt must contain
+    #[serde(default)]
+    pub prompt_contains: Vec<String>,
+    /// Regexes the rendered prompt must match
+    #[serde(default)]
+    pub prompt_matches: Vec<String>,
+    /// Action type: `reply`, `send_dm`, `forward` or `webhook`
+    #[serde(default)]
+    pub action: Option<String>,
+    /// Channel ID, user ID or webhook URL the response must go to
+    #[serde(default)]
+    pub sent_to: Option<String>,
+    /// Substring the response must contain
+    #[serde(default)]
+    pub response_contains: Option<String>,
+}
+
+/// Result of one test case
+#[derive(Debug, Clone)]
+pub struct TestOutcome {
+    /// Name of the test case
+    pub name: String,
+    /// Why the test failed; empty if it passed
+    pub failures: Vec<String>,
+    /// How long the test took
+    pub duration: Duration,
+}
+
+impl TestOutcome {
+    /// Whether every expectation held
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A hook that fired during a test
+struct FiredHook<'a> {
+    hook: &'a CompiledHookConfig,
+    result: Result<HookResult>,
+    sent: Option<SinkRecord>,
+}
+
+impl TestSuite {
+    /// Load a test suite, resolving its paths against the suite's directory
+    ///
+    /// # Errors
+    ///
+    /// Returns a configuration error if the file cannot be read or parsed,
+    /// or if an expectation is invalid
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| DiscliError::Config(format!("Failed to read test suite: {}", e)))?;
+        let mut suite: TestSuite = serde_yaml::from_str(&content).map_err(|e| {
+            DiscliError::Config(format!("Failed to parse {}: {}", path.display(), e))
+        })?;
+
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        suite.hooks_file = suite.hooks_file.map(|p| dir.join(p));
+        suite.prompts_dir = suite.prompts_dir.map(|p| dir.join(p));
+
+        suite.validate()?;
+        Ok(suite)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for case in &self.tests {
+            for expected in &case.expect {
+                let invalid = |message: String| {
+                    DiscliError::Config(format!(
+                        "Test '{}', hook '{}': {}",
+                        case.name, expected.hook, message
+                    ))
+                };
+                for pattern in &expected.prompt_matches {
+                    Regex::new(pattern)
+                        .map_err(|e| invalid(format!("invalid regex: {}", e)))?;
+                }
+                if let Some(action) = &expected.action {
+                    let names = HookAction::names();
+                    if !names.contains(&action.as_str()) {
+                        return Err(invalid(format!(
+                            "unknown action '{}' (expected one of {})",
+                            action,
+                            names.join(", ")
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run every test case against `hooks`, in order
+    pub async fn run(&self, hooks: &[CompiledHookConfig], prompts_dir: &Path) -> Vec<TestOutcome> {
+        let mut outcomes = Vec::new();
+        for case in &self.tests {
+            outcomes.push(case.run(hooks, prompts_dir).await);
+        }
+        outcomes
+    }
+}
+
+impl TestCase {
+    /// Run the test against `hooks`
+    ///
+    /// Each test gets a fresh executor, so rate limits don't carry over
+    /// between tests.
+    pub async fn run(&self, hooks: &[CompiledHookConfig], prompts_dir: &Path) -> TestOutcome {
+        let started = Instant::now();
+        let mut failures = Vec::new();
+
+        match self.message.to_event().and_then(|event| Ok((event, self.message.context()?))) {
+            Ok((event, context)) => {
+                let sink = Arc::new(MemorySink::new());
+                let stub = match &self.processor_output {
+                    Some(output) => ProcessorStub::Output(output.clone()),
+                    None => ProcessorStub::Echo,
+                };
+                let executor =
+                    HookExecutor::with_prompts_dir(prompts_dir.to_path_buf(), sink.clone())
+                        .with_processor_stub(stub);
+
+                let mut fired = Vec::new();
+                for hook in select(&run_order(hooks), &event, &context) {
+                    let sent_before = sink.records().len();
+                    let result = executor.execute_in(hook, &event, &context).await;
+                    let sent = sink.records().into_iter().nth(sent_before);
+                    fired.push(FiredHook { hook, result, sent });
+                }
+
+                check_expectations(&self.expect, &fired, &mut failures);
+            }
+            Err(e) => failures.push(e.to_string()),
+        }
+
+        TestOutcome {
+            name: self.name.clone(),
+            failures,
+            duration: started.elapsed(),
+        }
+    }
+}
+
+impl TestMessage {
+    /// Build the `MessageCreate` event for this message
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if an ID is not a valid Discord ID
+    pub fn to_event(&self) -> Result<IncomingEvent> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        let timestamp = Timestamp::from_secs(now)
+            .map_err(|e| DiscliError::Validation(format!("invalid timestamp: {}", e)))?
+            .iso_8601()
+            .to_string();
+
+        let member = self.guild.as_ref().map(|_| {
+            serde_json::json!({
+                "roles": self.roles,
+                "joined_at": timestamp,
+                "deaf": false,
+                "mute": false,
+                "flags": 0
+            })
+        });
+        let attachments: Vec<serde_json::Value> = self
+            .attachments
+            .iter()
+            .enumerate()
+            .map(|(index, filename)| {
+                let url = format!(
+                    "https://cdn.discordapp.com/attachments/{}/{}/{}",
+                    self.channel,
+                    index + 1,
+                    filename
+                );
+                serde_json::json!({
+                    "id": (index + 1).to_string(),
+                    "filename": filename,
+                    "content_type": mime_guess::from_path(filename).first().map(|m| m.to_string()),
+                    "size": 0,
+                    "url": url,
+                    "proxy_url": url
+                })
+            })
+            .collect();
+
+        let bot_mention = format!("<@{}>", SIMULATED_BOT_ID);
+        let (content, mentions) = if self.mentions_bot {
+            let content = if self.content.contains(&bot_mention) {
+                self.content.clone()
+            } else {
+                format!("{} {}", bot_mention, self.content)
+            };
+            let bot = serde_json::json!({
+                "id": SIMULATED_BOT_ID,
+                "username": "discli",
+                "discriminator": "0",
+                "avatar": null,
+                "bot": true,
+                "public_flags": 0
+            });
+            (content, vec![bot])
+        } else {
+            (self.content.clone(), Vec::new())
+        };
+        let replied_to = if self.reply_to_bot {
+            Some((SIMULATED_BOT_ID, "discli", true))
+        } else if self.reply {
+            Some(("2", "someone-else", false))
+        } else {
+            None
+        };
+        let referenced = replied_to.map(|(id, username, bot)| {
+            message_data(serde_json::json!({
+                "id": "2",
+                "channel_id": self.channel,
+                "author": {
+                    "id": id,
+                    "username": username,
+                    "discriminator": "0",
+                    "bot": bot
+                },
+                "timestamp": timestamp
+            }))
+        });
+
+        let embeds: Vec<serde_json::Value> = self
+            .embed
+            .then(|| serde_json::json!({ "type": "link", "title": "Simulated embed" }))
+            .into_iter()
+            .collect();
+
+        let data = message_data(serde_json::json!({
+            "id": "1",
+            "channel_id": self.channel,
+            "guild_id": self.guild,
+            "author": {
+                "id": self.author,
+                "username": self.author_name,
+                "discriminator": "0",
+                "bot": self.bot
+            },
+            "member": member,
+            "content": content,
+            "timestamp": timestamp,
+            "mentions": mentions,
+            "attachments": attachments,
+            "embeds": embeds,
+            "type": if referenced.is_some() { 19 } else { 0 },
+            "referenced_message": referenced
+        }));
+        IncomingEvent::from_dispatch("MESSAGE_CREATE", &data).map_err(|_| {
+            DiscliError::Validation(
+                "channel, guild, author and role IDs must be numeric Discord IDs".into(),
+            )
+        })
+    }
+}
+
+impl TestMessage {
+    /// Guild context for the message, as a live lookup would find it
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if a permission name is unknown
+    pub fn context(&self) -> Result<EventContext> {
+        let permissions = match &self.guild {
+            Some(_) => Some(
+                parse_permissions(&self.permissions)
+                    .map_err(|e| DiscliError::Validation(e.to_string()))?,
+            ),
+            None => None,
+        };
+        Ok(EventContext {
+            roles: self.guild.as_ref().map(|_| self.roles.clone()),
+            category: self.category.clone(),
+            permissions,
+            bot: Some(BotIdentity {
+                user_id: SIMULATED_BOT_ID.to_string(),
+                role_id: None,
+            }),
+            thread_owner: self
+                .in_bot_thread
+                .then(|| SIMULATED_BOT_ID.to_string()),
+        })
+    }
+}
+
+/// `MESSAGE_CREATE` data for a plain message: ID 1000 in channel 10 from
+/// user 2000, with `fields` set over it
+pub(crate) fn message_data(fields: serde_json::Value) -> serde_json::Value {
+    let mut data = serde_json::json!({
+        "id": "1000",
+        "channel_id": "10",
+        "author": { "id": "2000", "username": "tester", "discriminator": "0" },
+        "content": "",
+        "timestamp": "2024-01-01T00:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "pinned": false,
+        "type": 0
+    });
+    if let (Some(data), serde_json::Value::Object(fields)) = (data.as_object_mut(), fields) {
+        data.extend(fields);
+    }
+    data
+}
+
+/// A `MESSAGE_CREATE` event for tests, with `fields` set over a plain
+/// message (see [`message_data`])
+#[cfg(test)]
+pub(crate) fn message_create(fields: serde_json::Value) -> IncomingEvent {
+    IncomingEvent::from_dispatch("MESSAGE_CREATE", &message_data(fields))
+        .expect("test message should deserialize")
+}
+
+/// A [`TestMessage`] for tests, by the default author, in `guild` or a
+/// direct message if `None`
+#[cfg(test)]
+pub(crate) fn test_message(channel: &str, guild: Option<&str>, content: &str) -> TestMessage {
+    TestMessage {
+        channel: channel.to_string(),
+        guild: guild.map(str::to_string),
+        content: content.to_string(),
+        ..TestMessage::default()
+    }
+}
+
+/// Compare the hooks that fired against the expected ones
+fn check_expectations(expected: &[ExpectedHook], fired: &[FiredHook], failures: &mut Vec<String>) {
+    let expected_ids: Vec<&str> = expected.iter().map(|e| e.hook.as_str()).collect();
+    let fired_ids: Vec<&str> = fired.iter().map(|f| f.hook.id.as_str()).collect();
+    if expected_ids != fired_ids {
+        failures.push(format!(
+            "expected hooks [{}] to fire, got [{}]",
+            expected_ids.join(", "),
+            fired_ids.join(", ")
+        ));
+        return;
+    }
+
+    for (expected, fired) in expected.iter().zip(fired) {
+        let id = &expected.hook;
+        let result = match &fired.result {
+            Ok(result) => result,
+            Err(e) => {
+                failures.push(format!("{}: failed: {}", id, e));
+                continue;
+            }
+        };
+        if let Some(error) = &result.error {
+            failures.push(format!("{}: {}", id, error));
+            continue;
+        }
+
+        let prompt = result.prompt.as_deref().unwrap_or_default();
+        for substring in &expected.prompt_contains {
+            if !prompt.contains(substring.as_str()) {
+                failures.push(format!(
+                    "{}: prompt does not contain {:?}; prompt was {:?}",
+                    id, substring, prompt
+                ));
+            }
+        }
+        for pattern in &expected.prompt_matches {
+            // Patterns were checked when the suite was loaded
+            let matched = Regex::new(pattern).map(|re| re.is_match(prompt)).unwrap_or(false);
+            if !matched {
+                failures.push(format!(
+                    "{}: prompt does not match /{}/; prompt was {:?}",
+                    id, pattern, prompt
+                ));
+            }
+        }
+
+        if let Some(action) = &expected.action {
+            let actual = fired.hook.action.name();
+            if actual != action {
+                failures.push(format!("{}: action is {}, expected {}", id, actual, action));
+            }
+        }
+
+        if let Some(sent_to) = &expected.sent_to {
+            let target = fired.sent.as_ref().map(|record| match &record.target {
+                SinkTarget::Channel { channel_id } => channel_id.as_str(),
+                SinkTarget::User { user_id } => user_id.as_str(),
+                SinkTarget::Webhook { url } => url.as_str(),
+                SinkTarget::Reaction { message_id, .. } => message_id.as_str(),
+            });
+            if target != Some(sent_to.as_str()) {
+                failures.push(format!(
+                    "{}: response sent to {}, expected {}",
+                    id,
+                    target.unwrap_or("nowhere"),
+                    sent_to
+                ));
+            }
+        }
+
+        if let Some(substring) = &expected.response_contains {
+            let response = fired
+                .sent
+                .as_ref()
+                .and_then(|record| record.content.as_deref())
+                .unwrap_or_default();
+            if !response.contains(substring.as_str()) {
+                failures.push(format!(
+                    "{}: response does not contain {:?}; response was {:?}",
+                    id, substring, response
+                ));
+            }
+        }
+    }
+}
+
+/// Render outcomes as a JUnit XML report, one `<testsuite>` named `suite`
+pub fn junit_report(suite: &str, outcomes: &[TestOutcome]) -> String {
+    let failed = outcomes.iter().filter(|o| !o.passed()).count();
+    let total: Duration = outcomes.iter().map(|o| o.duration).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        outcomes.len(),
+        failed,
+        total.as_secs_f64()
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        xml_escape(suite),
+        outcomes.len(),
+        failed,
+        total.as_secs_f64()
+    ));
+    for outcome in outcomes {
+        let open = format!(
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            xml_escape(&outcome.name),
+            xml_escape(suite),
+            outcome.duration.as_secs_f64()
+        );
+        if outcome.passed() {
+            xml.push_str(&open);
+            xml.push_str("/>\n");
+        } else {
+            xml.push_str(&open);
+            xml.push_str(">\n");
+            xml.push_str(&format!(
+                "      <failure message=\"{}\">{}</failure>\n",
+                xml_escape(&outcome.failures[0]),
+                xml_escape(&outcome.failures.join("\n"))
+            ));
+            xml.push_str("    </testcase>\n");
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::config::HooksConfig;
+    use tempfile::TempDir;
+
+    const HOOKS: &str = r#"
+version: "1.0"
+hooks:
+  - id: deploy
+    channels: ["123"]
+    trigger: { type: prefix, prefix: "!deploy" }
+    prompt_file: deploy.txt
+    action: { type: reply }
+    processing: { processor_type: command, cmd: ["false"] }
+  - id: audit
+    channels: ["123"]
+    trigger: { type: contains, substring: "prod" }
+    prompt_file: deploy.txt
+    action: { type: forward, channel_id: "999" }
+"#;
+
+    fn setup() -> (TempDir, Vec<CompiledHookConfig>) {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("deploy.txt"),
+            "Deploy requested by {{author_name}}: {{content}}",
+        )
+        .unwrap();
+        let config: HooksConfig = serde_yaml::from_str(HOOKS).unwrap();
+        let hooks = config
+            .enabled_hooks()
+            .into_iter()
+            .map(|hook| hook.compile().unwrap())
+            .collect();
+        (temp_dir, hooks)
+    }
+
+    fn suite(yaml: &str) -> TestSuite {
+        let suite: TestSuite = serde_yaml::from_str(yaml).unwrap();
+        suite.validate().unwrap();
+        suite
+    }
+
+    #[tokio::test]
+    async fn test_suite_passes() {
+        let (prompts, hooks) = setup();
+        let suite = suite(
+            r#"
+tests:
+  - name: deploy prod
+    message: { channel: "123", guild: "789", author_name: alice, content: "!deploy prod" }
+    processor_output: "Deploying prod"
+    expect:
+      - hook: deploy
+        prompt_contains: ["alice"]
+        prompt_matches: ["^Deploy requested by \\w+: !deploy prod$"]
+        action: reply
+        sent_to: "123"
+        response_contains: "Deploying"
+      - hook: audit
+        action: forward
+        sent_to: "999"
+  - name: wrong channel
+    message: { channel: "124", content: "!deploy prod" }
+    expect: []
+"#,
+        );
+
+        let outcomes = suite.run(&hooks, prompts.path()).await;
+        assert!(outcomes.iter().all(TestOutcome::passed), "{:?}", outcomes);
+    }
+
+    #[tokio::test]
+    async fn test_suite_reports_failures() {
+        let (prompts, hooks) = setup();
+        let suite = suite(
+            r#"
+tests:
+  - name: staging
+    message: { channel: "123", content: "!deploy staging" }
+    expect:
+      - hook: deploy
+        prompt_contains: ["prod"]
+        sent_to: "555"
+  - name: audit only
+    message: { channel: "123", content: "prod is down" }
+    expect:
+      - hook: deploy
+"#,
+        );
+
+        let outcomes = suite.run(&hooks, prompts.path()).await;
+        assert_eq!(outcomes[0].failures.len(), 2, "{:?}", outcomes[0]);
+        assert!(outcomes[0].failures[0].contains("prompt does not contain \"prod\""));
+        assert_eq!(outcomes[0].failures[1], "deploy: response sent to 123, expected 555");
+        assert_eq!(
+            outcomes[1].failures,
+            vec!["expected hooks [deploy] to fire, got [audit]".to_string()]
+        );
+
+        let report = junit_report("hooks.test.yaml", &outcomes);
+        assert!(report.contains("<testsuite name=\"hooks.test.yaml\" tests=\"2\" failures=\"2\""));
+        assert!(report.contains("<failure message=\"expected hooks [deploy] to fire, got [audit]\">"));
+        assert!(report.contains("&quot;prod&quot;"));
+    }
+
+    #[test]
+    fn test_suite_rejects_bad_regex() {
+        let suite: TestSuite = serde_yaml::from_str(
+            r#"
+tests:
+  - name: bad
+    message: { channel: "1" }
+    expect: [{ hook: deploy, prompt_matches: ["("] }]
+"#,
+        )
+        .unwrap();
+        assert!(suite.validate().is_err());
+    }
+}
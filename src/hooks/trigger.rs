@@ -2,82 +2,448 @@
 //!
 //! This module handles matching messages against hook triggers.
 
-use crate::hooks::config::{CompiledTrigger, FilterConfig};
-use twilight_model::gateway::payload::incoming::MessageCreate;
+use crate::hooks::config::{BotPolicy, CompiledHookConfig, CompiledTrigger, FilterConfig, DM_CHANNEL};
+use crate::hooks::context::{BotIdentity, EventContext};
+use crate::hooks::event::{HookEvent, IncomingEvent};
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+use reqwest::Url;
+use std::fmt;
+use std::sync::OnceLock;
+use twilight_model::channel::message::{EmojiReactionType, MessageType};
+use twilight_model::channel::Message;
 
 /// Trait for matching triggers
 pub trait TriggerMatcher {
-    /// Check if a message matches this trigger
-    fn matches(&self, message: &MessageCreate) -> bool;
+    /// Check if an event matches this trigger
+    ///
+    /// Triggers about the bot itself read it from `context` and never
+    /// match while it is unknown, or on the bot's own messages.
+    fn matches(&self, event: &IncomingEvent, context: &EventContext) -> bool;
 }
 
 impl TriggerMatcher for CompiledTrigger {
-    fn matches(&self, message: &MessageCreate) -> bool {
+    fn matches(&self, event: &IncomingEvent, context: &EventContext) -> bool {
         match self {
             CompiledTrigger::Any => true,
-            CompiledTrigger::Prefix(prefix) => message.0.content.starts_with(prefix),
-            CompiledTrigger::Contains(substring) => message.0.content.contains(substring),
-            CompiledTrigger::Regex(re) => re.is_match(&message.0.content),
+            CompiledTrigger::Prefix(prefix) => event.text().starts_with(prefix),
+            CompiledTrigger::Contains(substring) => event.text().contains(substring),
+            CompiledTrigger::Regex(re) => re.is_match(event.text()),
             CompiledTrigger::Mention => {
-                // Check if the bot was mentioned
-                // For now, check for @bot or bot username
-                // In production, would check message.mentions
-                false // TODO: implement properly with twilight mentions
+                let Some((message, bot)) = message_to_bot(event, context) else {
+                    return false;
+                };
+                message.mentions.iter().any(|m| m.id.to_string() == bot.user_id)
+                    || bot.role_id.as_ref().is_some_and(|role_id| {
+                        message.mention_roles.iter().any(|id| &id.to_string() == role_id)
+                    })
+            }
+            CompiledTrigger::ReplyToBot => {
+                let Some((message, bot)) = message_to_bot(event, context) else {
+                    return false;
+                };
+                message
+                    .referenced_message
+                    .as_ref()
+                    .is_some_and(|replied| replied.author.id.to_string() == bot.user_id)
             }
+            CompiledTrigger::InBotThread => match message_to_bot(event, context) {
+                Some((_, bot)) => context.thread_owner.as_ref() == Some(&bot.user_id),
+                None => false,
+            },
+            CompiledTrigger::HasAttachment(patterns) => event.message().is_some_and(|message| {
+                message.attachments.iter().any(|attachment| {
+                    let content_type = attachment.content_type.as_deref();
+                    patterns.is_empty()
+                        || patterns
+                            .iter()
+                            .any(|p| matches_attachment(p, &attachment.filename, content_type))
+                })
+            }),
+            CompiledTrigger::HasEmbed => event.message().is_some_and(|m| !m.embeds.is_empty()),
+            CompiledTrigger::HasLink(domains) => event.message().is_some_and(|message| {
+                link_hosts(&message.content).any(|host| {
+                    domains.is_empty() || domains.iter().any(|domain| is_host_in(&host, domain))
+                })
+            }),
+            CompiledTrigger::MinLength(length) => event.text().chars().count() >= *length,
+            CompiledTrigger::MaxLength(length) => event.text().chars().count() <= *length,
+            CompiledTrigger::HasCodeBlock(language) => event.message().is_some_and(|message| {
+                code_block_languages(&message.content).any(|found| match language {
+                    Some(language) => found.is_some_and(|found| found.eq_ignore_ascii_case(language)),
+                    None => true,
+                })
+            }),
+            CompiledTrigger::IsReply => {
+                event.message().is_some_and(|m| m.kind == MessageType::Reply)
+            }
+            CompiledTrigger::All(triggers) => triggers.iter().all(|t| t.matches(event, context)),
+            CompiledTrigger::AnyOf(triggers) => triggers.iter().any(|t| t.matches(event, context)),
+            CompiledTrigger::Not(trigger) => !trigger.matches(event, context),
         }
     }
 }
 
-/// Check if a message passes the filter (user/role restrictions)
-pub fn matches_filter(message: &MessageCreate, filter: &Option<FilterConfig>) -> bool {
-    match filter {
-        Some(f) => {
-            // Check user filter
-            if !f.users.is_empty() {
-                let author_id = message.0.author.id.to_string();
-                if !f.users.contains(&author_id) {
-                    return false;
-                }
+/// Whether an attachment matches a glob: MIME type globs such as
+/// `image/*` contain a slash, the rest match the filename. Case is ignored.
+fn matches_attachment(pattern: &Pattern, filename: &str, content_type: Option<&str>) -> bool {
+    let options = MatchOptions {
+        case_sensitive: false,
+        ..MatchOptions::new()
+    };
+    if pattern.as_str().contains('/') {
+        // Drop parameters such as `; charset=utf-8`
+        let mime = content_type.and_then(|t| t.split(';').next()).unwrap_or_default();
+        pattern.matches_with(mime.trim(), options)
+    } else {
+        pattern.matches_with(filename, options)
+    }
+}
+
+/// Hosts of the http(s) links in message text
+fn link_hosts(text: &str) -> impl Iterator<Item = String> + '_ {
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    LINK.get_or_init(|| Regex::new(r"(?i)https?://[^\s<>]+").unwrap())
+        .find_iter(text)
+        .filter_map(|link| Url::parse(link.as_str()).ok())
+        .filter_map(|url| url.host_str().map(str::to_string))
+}
+
+/// Whether `host` is `domain` or one of its subdomains
+fn is_host_in(host: &str, domain: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+/// Language of each fenced code block in message text, `None` for blocks
+/// that name none
+fn code_block_languages(text: &str) -> impl Iterator<Item = Option<&str>> {
+    static CODE_BLOCK: OnceLock<Regex> = OnceLock::new();
+    // A language is the word right after the opening fence, on its own line
+    CODE_BLOCK
+        .get_or_init(|| Regex::new(r"(?s)```(?:([\w+#.-]+)\n)?.*?```").unwrap())
+        .captures_iter(text)
+        .map(|captures| captures.get(1).map(|language| language.as_str()))
+}
+
+/// How a trigger, and each branch of a combination, decided on an event
+#[derive(Debug)]
+pub struct TriggerTrace<'a> {
+    /// The trigger
+    pub trigger: &'a CompiledTrigger,
+    /// Whether it matched; `None` if an earlier branch already decided
+    pub matched: Option<bool>,
+    /// Traces of the triggers it combines, empty if it wasn't checked
+    pub branches: Vec<TriggerTrace<'a>>,
+}
+
+/// Evaluate a trigger like [`TriggerMatcher::matches`], recording which
+/// branches matched
+pub fn trace_trigger<'a>(
+    trigger: &'a CompiledTrigger,
+    event: &IncomingEvent,
+    context: &EventContext,
+) -> TriggerTrace<'a> {
+    // `all` stops at the first branch that fails, `any` at the first that
+    // matches; the branches after it are left unchecked
+    let stop_at = match trigger {
+        CompiledTrigger::All(_) => Some(false),
+        CompiledTrigger::AnyOf(_) => Some(true),
+        _ => None,
+    };
+    let mut decided = false;
+    let branches: Vec<TriggerTrace<'a>> = trigger
+        .branches()
+        .iter()
+        .map(|branch| {
+            if decided {
+                return TriggerTrace {
+                    trigger: branch,
+                    matched: None,
+                    branches: Vec::new(),
+                };
             }
-            // Role filter would need guild context - skip for now
-            true
+            let trace = trace_trigger(branch, event, context);
+            decided = trace.matched == stop_at;
+            trace
+        })
+        .collect();
+    let matched = match trigger {
+        CompiledTrigger::All(_) => branches.iter().all(|b| b.matched != Some(false)),
+        CompiledTrigger::AnyOf(_) => branches.iter().any(|b| b.matched == Some(true)),
+        CompiledTrigger::Not(_) => branches[0].matched == Some(false),
+        leaf => leaf.matches(event, context),
+    };
+    TriggerTrace {
+        trigger,
+        matched: Some(matched),
+        branches,
+    }
+}
+
+impl fmt::Display for TriggerTrace<'_> {
+    /// One line per trigger, indented by nesting, e.g.
+    ///
+    /// ```text
+    /// ✓ all
+    ///   ✓ prefix "!ask"
+    ///   ✓ not
+    ///     ✗ contains "ignore"
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_trace(f: &mut fmt::Formatter<'_>, trace: &TriggerTrace, depth: usize) -> fmt::Result {
+            let mark = match trace.matched {
+                Some(true) => "✓",
+                Some(false) => "✗",
+                None => "-",
+            };
+            write!(f, "{:indent$}{} ", "", mark, indent = depth * 2)?;
+            match trace.trigger {
+                CompiledTrigger::All(_) => f.write_str("all")?,
+                CompiledTrigger::AnyOf(_) => f.write_str("any")?,
+                CompiledTrigger::Not(_) => f.write_str("not")?,
+                leaf => write!(f, "{}", leaf)?,
+            }
+            if trace.matched.is_none() {
+                return f.write_str(" (not checked)");
+            }
+            for branch in &trace.branches {
+                writeln!(f)?;
+                write_trace(f, branch, depth + 1)?;
+            }
+            Ok(())
         }
-        None => true,
+        write_trace(f, self, 0)
     }
 }
 
-/// Check if message is in one of the configured channels
-pub fn matches_channels(message: &MessageCreate, channels: &[String]) -> bool {
-    let channel_id = message.0.channel_id.to_string();
-    channels.contains(&channel_id)
+/// The event's message and the bot, unless the bot is unknown or sent the
+/// message itself
+///
+/// The bot's own replies would otherwise mention it, reply to it or post
+/// in its threads, and trigger the hook that sent them again.
+fn message_to_bot<'a>(
+    event: &'a IncomingEvent,
+    context: &'a EventContext,
+) -> Option<(&'a Message, &'a BotIdentity)> {
+    let message = event.message()?;
+    let bot = context.bot.as_ref()?;
+    (message.author.id.to_string() != bot.user_id).then_some((message, bot))
 }
 
-/// Full trigger match check
-pub fn should_trigger(
-    hook: &crate::hooks::config::CompiledHookConfig,
-    message: &MessageCreate,
-) -> bool {
+/// Remove mentions of the bot (and its managed role) from message text
+///
+/// Leading whitespace left behind is trimmed, so `<@bot> summarize this`
+/// becomes `summarize this`.
+pub fn strip_bot_mentions(text: &str, bot: &BotIdentity) -> String {
+    let mut mentions = vec![format!("<@{}>", bot.user_id), format!("<@!{}>", bot.user_id)];
+    if let Some(role_id) = &bot.role_id {
+        mentions.push(format!("<@&{}>", role_id));
+    }
+    let stripped = mentions
+        .iter()
+        .fold(text.to_string(), |text, mention| text.replace(mention.as_str(), ""));
+    stripped.trim_start().to_string()
+}
+
+/// Check if an event passes the filter (server/user/role/emoji/status
+/// restrictions), using only what the event itself carries
+pub fn matches_filter(event: &IncomingEvent, filter: &Option<FilterConfig>) -> bool {
+    filter_mismatch(event, filter, &EventContext::default()).is_none()
+}
+
+/// Name of the first filter list the event fails, if any
+///
+/// Roles come from the event if it has them, otherwise from `context`.
+fn filter_mismatch(
+    event: &IncomingEvent,
+    filter: &Option<FilterConfig>,
+    context: &EventContext,
+) -> Option<&'static str> {
+    let f = filter.as_ref()?;
+
+    // Check server filter
+    if !f.guilds.is_empty() {
+        let guild_id = event.guild_id().map(|id| id.to_string()).unwrap_or_default();
+        if !f.guilds.contains(&guild_id) {
+            return Some("guilds");
+        }
+    }
+    // Check category filter
+    if !f.categories.is_empty() {
+        match &context.category {
+            Some(category) if f.categories.contains(category) => {}
+            _ => return Some("categories"),
+        }
+    }
+    // Check user filters
+    let author_id = event.user_id().map(|id| id.to_string()).unwrap_or_default();
+    if !f.users.is_empty() && !f.users.contains(&author_id) {
+        return Some("users");
+    }
+    if f.exclude_users.contains(&author_id) {
+        return Some("exclude_users");
+    }
+    if f.bots == BotPolicy::Deny && event.is_bot() == Some(true) {
+        return Some("bots");
+    }
+    // Check role filters; unknown roles fail both, since an excluded
+    // role can't be ruled out
+    if f.uses_roles() {
+        let roles = event.member_roles().or_else(|| context.roles.clone());
+        let Some(roles) = roles else {
+            return Some(if f.roles.is_empty() { "exclude_roles" } else { "roles" });
+        };
+        if !f.roles.is_empty() && !f.roles.iter().any(|role| roles.contains(role)) {
+            return Some("roles");
+        }
+        if f.exclude_roles.iter().any(|role| roles.contains(role)) {
+            return Some("exclude_roles");
+        }
+    }
+    // Check permission filter
+    if !f.permissions.is_empty() {
+        let allowed = match (f.required_permissions(), context.permissions) {
+            (Ok(required), Some(permissions)) => permissions.contains(required),
+            _ => false,
+        };
+        if !allowed {
+            return Some("permissions");
+        }
+    }
+    // Check emoji filter (reaction events)
+    if !f.emojis.is_empty() {
+        let emoji_id = event.reaction().and_then(|r| match &r.emoji {
+            EmojiReactionType::Custom { id, .. } => Some(id.to_string()),
+            EmojiReactionType::Unicode { .. } => None,
+        });
+        let matched = f
+            .emojis
+            .iter()
+            .any(|e| e == event.text() || Some(e) == emoji_id.as_ref());
+        if !matched {
+            return Some("emojis");
+        }
+    }
+    // Check status filter (presence updates)
+    if !f.statuses.is_empty() {
+        let status = event.status().unwrap_or_default();
+        if !f.statuses.iter().any(|s| s == status) {
+            return Some("statuses");
+        }
+    }
+    None
+}
+
+/// Check if the event is in one of the configured channels
+///
+/// The `"dm"` selector matches any direct message to the bot. An empty
+/// list (allowed for non-message events) matches every channel.
+pub fn matches_channels(event: &IncomingEvent, channels: &[String]) -> bool {
+    if channels.is_empty() {
+        return true;
+    }
+    if event.is_direct_message() && channels.iter().any(|c| c == DM_CHANNEL) {
+        return true;
+    }
+    match event.channel_id() {
+        Some(channel_id) => channels
+            .iter()
+            .any(|c| c.parse::<u64>().is_ok_and(|id| id == channel_id.get())),
+        None => false,
+    }
+}
+
+/// Why a hook did not trigger on an event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The hook runs on a different kind of event
+    Event {
+        /// Event the hook runs on
+        expected: HookEvent,
+        /// Event that arrived
+        actual: HookEvent,
+    },
+    /// The event is not in one of the hook's channels
+    Channel,
+    /// The trigger did not match the event's text
+    Trigger,
+    /// The event failed a filter list, e.g. `users` or `roles`
+    Filter(&'static str),
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Event { expected, actual } => {
+                write!(f, "runs on {} events, not {}", expected, actual)
+            }
+            Mismatch::Channel => f.write_str("not in one of the hook's channels"),
+            Mismatch::Trigger => f.write_str("trigger did not match"),
+            Mismatch::Filter(list) => write!(f, "not allowed by filter.{}", list),
+        }
+    }
+}
+
+/// Full trigger match check, reporting the first check that failed
+///
+/// Checks run in order: event type, channel, trigger, filter. Filters see
+/// only what the event carries; see [`check_trigger_in`].
+pub fn check_trigger(hook: &CompiledHookConfig, event: &IncomingEvent) -> Result<(), Mismatch> {
+    check_trigger_in(hook, event, &EventContext::default())
+}
+
+/// Full trigger match check, with looked-up guild context for filters
+pub fn check_trigger_in(
+    hook: &CompiledHookConfig,
+    event: &IncomingEvent,
+    context: &EventContext,
+) -> Result<(), Mismatch> {
+    // Check event type
+    if event.kind() != hook.event {
+        return Err(Mismatch::Event {
+            expected: hook.event,
+            actual: event.kind(),
+        });
+    }
+    
     // Check channel
-    if !matches_channels(message, &hook.channels) {
-        return false;
+    if !matches_channels(event, &hook.channels) {
+        return Err(Mismatch::Channel);
     }
     
     // Check trigger
-    if !hook.trigger.matches(message) {
-        return false;
+    if !hook.trigger.matches(event, context) {
+        return Err(Mismatch::Trigger);
     }
     
     // Check filter
-    if !matches_filter(message, &hook.filter) {
-        return false;
+    if let Some(list) = filter_mismatch(event, &hook.filter, context) {
+        return Err(Mismatch::Filter(list));
     }
     
-    true
+    Ok(())
+}
+
+/// Full trigger match check
+pub fn should_trigger(hook: &CompiledHookConfig, event: &IncomingEvent) -> bool {
+    check_trigger(hook, event).is_ok()
+}
+
+/// Full trigger match check, with looked-up guild context for filters
+pub fn should_trigger_in(
+    hook: &CompiledHookConfig,
+    event: &IncomingEvent,
+    context: &EventContext,
+) -> bool {
+    check_trigger_in(hook, event, context).is_ok()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hooks::testing::{message_create, test_message};
     
     #[test]
     fn test_any_trigger_matches() {
@@ -85,7 +451,7 @@ mod tests {
         let trigger = CompiledTrigger::Any;
         // Just test the enum matching works
         match trigger {
-            CompiledTrigger::Any => assert!(true),
+            CompiledTrigger::Any => {}
             _ => panic!("Expected Any variant"),
         }
     }
@@ -113,7 +479,7 @@ mod tests {
         let re = regex::Regex::new(r"^\d+$").unwrap();
         let trigger = CompiledTrigger::Regex(re.clone());
         match trigger {
-            CompiledTrigger::Regex(_) => assert!(true),
+            CompiledTrigger::Regex(_) => {}
             _ => panic!("Expected Regex variant"),
         }
         
@@ -126,11 +492,55 @@ mod tests {
     fn test_mention_trigger_variant() {
         let trigger = CompiledTrigger::Mention;
         match trigger {
-            CompiledTrigger::Mention => assert!(true),
+            CompiledTrigger::Mention => {}
             _ => panic!("Expected Mention variant"),
         }
     }
     
+    #[test]
+    fn test_matches_dm_channel_selector() {
+        let message = |guild_id: Option<&str>| {
+            message_create(serde_json::json!({ "guild_id": guild_id, "content": "hi" }))
+        };
+        let dm_only = vec!["dm".to_string()];
+
+        assert!(matches_channels(&message(None), &dm_only));
+        assert!(!matches_channels(&message(Some("20")), &dm_only));
+        assert!(matches_channels(&message(Some("20")), &["10".to_string()]));
+    }
+
+    #[test]
+    fn test_reaction_hook_matches_emoji_filter() {
+        let hook: crate::hooks::config::HookConfig = serde_yaml::from_str(
+            r#"
+id: "approve"
+event: "reaction_add"
+channels: ["10"]
+prompt_file: "p.txt"
+filter: { emojis: ["✅"] }
+action: { type: "reply" }
+"#,
+        )
+        .unwrap();
+        let hook = hook.compile().unwrap();
+        let reaction = |emoji: &str| -> IncomingEvent {
+            let reaction: twilight_model::gateway::payload::incoming::ReactionAdd =
+                serde_json::from_value(serde_json::json!({
+                    "user_id": "2000",
+                    "channel_id": "10",
+                    "message_id": "1000",
+                    "guild_id": "20",
+                    "burst": false,
+                    "emoji": { "id": null, "name": emoji }
+                }))
+                .unwrap();
+            IncomingEvent::ReactionAdd(Box::new(reaction))
+        };
+
+        assert!(should_trigger(&hook, &reaction("✅")));
+        assert!(!should_trigger(&hook, &reaction("❌")));
+    }
+
     #[test]
     fn test_matches_filter_no_filter() {
         // Test that None filter returns true
@@ -144,6 +554,9 @@ mod tests {
         let filter = FilterConfig {
             users: vec!["123".to_string()],
             roles: vec![],
+            emojis: vec![],
+            statuses: vec![],
+            ..FilterConfig::default()
         };
         
         // Test that filter with users works
@@ -156,10 +569,275 @@ mod tests {
         let filter = FilterConfig {
             users: vec!["999".to_string()],
             roles: vec![],
+            emojis: vec![],
+            statuses: vec![],
+            ..FilterConfig::default()
         };
         
         // Test that filter has users
         assert!(filter.users.contains(&"999".to_string()));
         assert!(!filter.users.contains(&"123".to_string()));
     }
+
+    #[test]
+    fn test_check_trigger_reports_mismatch() {
+        let hook: crate::hooks::config::HookConfig = serde_yaml::from_str(
+            r#"
+id: deploy
+channels: ["10"]
+trigger: { type: prefix, prefix: "!deploy" }
+filter: { users: ["2000"] }
+prompt_file: deploy.txt
+action: { type: reply }
+"#,
+        )
+        .unwrap();
+        let hook = hook.compile().unwrap();
+        let message = |channel_id: &str, author_id: &str, content: &str| {
+            message_create(serde_json::json!({
+                "channel_id": channel_id,
+                "guild_id": "20",
+                "author": { "id": author_id, "username": "tester", "discriminator": "0" },
+                "content": content
+            }))
+        };
+
+        assert_eq!(check_trigger(&hook, &message("10", "2000", "!deploy prod")), Ok(()));
+        assert_eq!(
+            check_trigger(&hook, &message("11", "2000", "!deploy prod")),
+            Err(Mismatch::Channel)
+        );
+        assert_eq!(
+            check_trigger(&hook, &message("10", "2000", "deploy prod")),
+            Err(Mismatch::Trigger)
+        );
+        assert_eq!(
+            check_trigger(&hook, &message("10", "3000", "!deploy prod")),
+            Err(Mismatch::Filter("users"))
+        );
+        assert_eq!(
+            Mismatch::Filter("users").to_string(),
+            "not allowed by filter.users"
+        );
+    }
+
+    #[test]
+    fn test_guild_filters() {
+        let hook: crate::hooks::config::HookConfig = serde_yaml::from_str(
+            r#"
+id: purge
+channels: ["10"]
+prompt_file: purge.txt
+action: { type: reply }
+filter:
+  guilds: ["1"]
+  categories: ["7"]
+  exclude_users: ["666"]
+  roles: ["50", "51"]
+  exclude_roles: ["99"]
+  bots: deny
+  permissions: [MANAGE_MESSAGES]
+"#,
+        )
+        .unwrap();
+        let hook = hook.compile().unwrap();
+        let base = crate::hooks::testing::TestMessage {
+            roles: vec!["51".to_string()],
+            category: Some("7".to_string()),
+            permissions: vec!["MANAGE_MESSAGES".to_string(), "VIEW_CHANNEL".to_string()],
+            ..test_message("10", Some("1"), "!purge")
+        };
+        let check = |change: &dyn Fn(&mut crate::hooks::testing::TestMessage)| {
+            let mut message = base.clone();
+            change(&mut message);
+            check_trigger_in(&hook, &message.to_event().unwrap(), &message.context().unwrap())
+        };
+
+        assert_eq!(check(&|_| {}), Ok(()));
+        assert_eq!(check(&|m| m.guild = Some("2".into())), Err(Mismatch::Filter("guilds")));
+        assert_eq!(check(&|m| m.category = None), Err(Mismatch::Filter("categories")));
+        assert_eq!(check(&|m| m.author = "666".into()), Err(Mismatch::Filter("exclude_users")));
+        assert_eq!(check(&|m| m.bot = true), Err(Mismatch::Filter("bots")));
+        assert_eq!(check(&|m| m.roles = vec!["52".into()]), Err(Mismatch::Filter("roles")));
+        assert_eq!(
+            check(&|m| m.roles.push("99".into())),
+            Err(Mismatch::Filter("exclude_roles"))
+        );
+        assert_eq!(check(&|m| m.permissions.clear()), Err(Mismatch::Filter("permissions")));
+
+        // Without looked-up context, the category and permissions are
+        // unknown, so the hook stays closed
+        let event = base.to_event().unwrap();
+        assert_eq!(check_trigger(&hook, &event), Err(Mismatch::Filter("categories")));
+    }
+
+    #[test]
+    fn test_bot_triggers() {
+        use crate::hooks::testing::{TestMessage, SIMULATED_BOT_ID};
+
+        let message = |mentions_bot: bool, reply_to_bot: bool, in_bot_thread: bool| TestMessage {
+            mentions_bot,
+            reply_to_bot,
+            in_bot_thread,
+            ..test_message("10", Some("1"), "summarize this")
+        };
+        let matches = |trigger: CompiledTrigger, message: TestMessage| {
+            trigger.matches(&message.to_event().unwrap(), &message.context().unwrap())
+        };
+
+        assert!(matches(CompiledTrigger::Mention, message(true, false, false)));
+        assert!(!matches(CompiledTrigger::Mention, message(false, true, true)));
+        assert!(matches(CompiledTrigger::ReplyToBot, message(false, true, false)));
+        assert!(!matches(CompiledTrigger::ReplyToBot, message(true, false, true)));
+        assert!(matches(CompiledTrigger::InBotThread, message(false, false, true)));
+        assert!(!matches(CompiledTrigger::InBotThread, message(true, true, false)));
+
+        // The bot's own messages in its thread, or replying to it, don't
+        // trigger it again
+        let own = |trigger: CompiledTrigger| {
+            let message = TestMessage {
+                author: SIMULATED_BOT_ID.to_string(),
+                bot: true,
+                ..message(true, true, true)
+            };
+            matches(trigger, message)
+        };
+        assert!(!own(CompiledTrigger::Mention));
+        assert!(!own(CompiledTrigger::ReplyToBot));
+        assert!(!own(CompiledTrigger::InBotThread));
+
+        // Until READY says who the bot is, nothing mentions it
+        let event = message(true, true, true).to_event().unwrap();
+        assert!(!CompiledTrigger::Mention.matches(&event, &EventContext::default()));
+    }
+
+    #[test]
+    fn test_combined_triggers() {
+        use crate::hooks::config::TriggerConfig;
+
+        let trigger: TriggerConfig = serde_yaml::from_str(
+            r#"
+all:
+  - { type: prefix, prefix: "!ask" }
+  - any: [{ type: contains, substring: "?" }, { type: mention }]
+  - not: { type: contains, substring: "ignore" }
+"#,
+        )
+        .unwrap();
+        let trigger = CompiledTrigger::compile(&trigger).unwrap();
+        assert_eq!(
+            trigger.to_string(),
+            r#"all(prefix "!ask", any(contains "?", mention), not(contains "ignore"))"#
+        );
+
+        let check = |content: &str| {
+            let message = test_message("10", None, content);
+            let (event, context) = (message.to_event().unwrap(), message.context().unwrap());
+            let trace = trace_trigger(&trigger, &event, &context);
+            assert_eq!(trace.matched, Some(trigger.matches(&event, &context)));
+            trace.to_string()
+        };
+
+        assert_eq!(
+            check("!ask why?"),
+            "✓ all\n  ✓ prefix \"!ask\"\n  ✓ any\n    ✓ contains \"?\"\n    - mention (not checked)\n  ✓ not\n    ✗ contains \"ignore\""
+        );
+        assert_eq!(
+            check("!ask why? ignore"),
+            "✗ all\n  ✓ prefix \"!ask\"\n  ✓ any\n    ✓ contains \"?\"\n    - mention (not checked)\n  ✗ not\n    ✓ contains \"ignore\""
+        );
+        // `all` stops at the first branch that fails
+        assert_eq!(
+            check("hello?"),
+            "✗ all\n  ✗ prefix \"!ask\"\n  - any (not checked)\n  - not (not checked)"
+        );
+    }
+
+    #[test]
+    fn test_message_shape_triggers() {
+        use crate::hooks::config::TriggerConfig;
+        use crate::hooks::testing::TestMessage;
+
+        let message = |content: &str, attachments: &[&str], reply: bool, embed: bool| TestMessage {
+            reply,
+            embed,
+            attachments: attachments.iter().map(|a| a.to_string()).collect(),
+            ..test_message("10", None, content)
+        };
+        let matches = |yaml: &str, message: TestMessage| {
+            let trigger: TriggerConfig = serde_yaml::from_str(yaml).unwrap();
+            CompiledTrigger::compile(&trigger)
+                .unwrap()
+                .matches(&message.to_event().unwrap(), &EventContext::default())
+        };
+        let text = |content: &str| message(content, &[], false, false);
+
+        let logs = "{ type: has_attachment, patterns: ['*.LOG', 'text/*'] }";
+        assert!(matches(logs, message("", &["crash.log"], false, false)));
+        assert!(matches(logs, message("", &["shot.png", "notes.txt"], false, false)));
+        assert!(!matches(logs, message("", &["shot.png"], false, false)));
+        assert!(matches("{ type: has_attachment }", message("", &["shot.png"], false, false)));
+        assert!(!matches("{ type: has_attachment }", text("crash.log")));
+
+        assert!(matches("{ type: has_embed }", message("", &[], false, true)));
+        assert!(!matches("{ type: has_embed }", text("https://example.com")));
+
+        let github = "{ type: has_link, domains: [github.com] }";
+        assert!(matches(github, text("see <https://GitHub.com/a/b/pull/1>")));
+        assert!(matches(github, text("gist: https://gist.github.com/x")));
+        assert!(!matches(github, text("https://notgithub.com/a or github.com/a")));
+        assert!(matches("{ type: has_link }", text("http://example.com")));
+
+        assert!(matches("{ type: min_length, length: 5 }", text("héllo")));
+        assert!(!matches("{ type: min_length, length: 6 }", text("héllo")));
+        assert!(matches("{ type: max_length, length: 5 }", text("héllo")));
+
+        let rust = "{ type: has_code_block, language: rust }";
+        assert!(matches(rust, text("try:\n```Rust\nfn main() {}\n```")));
+        assert!(!matches(rust, text("```python\nprint()\n```")));
+        assert!(!matches(rust, text("```rust\nunclosed")));
+        assert!(!matches(rust, text("```rust fn main()```")));
+        assert!(matches("{ type: has_code_block }", text("```a b```")));
+
+        assert!(matches("{ type: is_reply }", message("", &[], true, false)));
+        assert!(!matches("{ type: is_reply }", text("hi")));
+    }
+
+    #[test]
+    fn test_role_mention_and_stripping() {
+        let bot = BotIdentity {
+            user_id: "999".to_string(),
+            role_id: Some("77".to_string()),
+        };
+        let event = message_create(serde_json::json!({
+            "guild_id": "1",
+            "content": "<@&77> <@!999> hi <@5>",
+            "mention_roles": ["77"]
+        }));
+        let context = EventContext {
+            bot: Some(bot),
+            ..EventContext::default()
+        };
+
+        assert!(CompiledTrigger::Mention.matches(&event, &context));
+        let vars = event.variables_in(&context);
+        assert_eq!(vars.get("content"), Some("hi <@5>"));
+        assert_eq!(vars.get("raw_content"), Some("<@&77> <@!999> hi <@5>"));
+    }
+
+    #[test]
+    fn test_unknown_roles_do_not_match() {
+        let filter = FilterConfig {
+            exclude_roles: vec!["99".to_string()],
+            ..FilterConfig::default()
+        };
+        let event = test_message("10", None, "hi").to_event().unwrap();
+
+        assert!(!matches_filter(&event, &Some(filter.clone())));
+        let context = EventContext {
+            roles: Some(vec!["5".to_string()]),
+            ..EventContext::default()
+        };
+        assert_eq!(filter_mismatch(&event, &Some(filter), &context), None);
+    }
 }
@@ -0,0 +1,795 @@
+//! Checking hooks.yaml before it is deployed
+//!
+//! [`validate_source`] looks at the whole file and reports every problem
+//! it finds rather than stopping at the first, each with the line and
+//! column of the key or value at fault:
+//!
+//! - YAML syntax errors, wrong types and missing fields
+//! - keys discli doesn't know (usually typos, which serde would ignore)
+//! - duplicate hook IDs
+//! - regex triggers and attachment globs that don't compile, including
+//!   inside `all`, `any` and `not`
+//! - channel IDs that aren't numeric
+//! - processors without a `cmd` or `url`
+//! - prompt files that don't exist, or use variables the event lacks
+//!
+//! A file without diagnostics loads, compiles and has prompts for every
+//! hook, so `discli hooks validate` can gate a deploy.
+
+use crate::error::{DiscliError, Result};
+use crate::hooks::config::{
+    ErrorStrategy, HookAction, HookConfig, HooksConfig, NotifyTarget, Settings, TriggerConfig,
+    DM_CHANNEL,
+};
+use crate::prompt::loader::PromptTemplate;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::OnceLock;
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser as YamlParser};
+use yaml_rust2::scanner::Marker;
+
+/// Position in the hooks file, both counted from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A problem found in the hooks file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Where the problem is, if it can be pinned down
+    pub location: Option<Location>,
+    /// What is wrong
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            Some(at) => write!(f, "{}:{}: {}", at.line, at.column, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Check a hooks file, resolving prompt files against `prompts_dir`
+///
+/// # Errors
+///
+/// Returns a configuration error only if the file cannot be read;
+/// problems with its contents are returned as diagnostics
+pub fn validate_file(path: &Path, prompts_dir: &Path) -> Result<Vec<Diagnostic>> {
+    let source = std::fs::read_to_string(path).map_err(|e| {
+        DiscliError::Config(format!(
+            "Failed to read hooks file {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    Ok(validate_source(&source, prompts_dir))
+}
+
+/// Check the contents of a hooks file
+///
+/// Diagnostics are returned in file order.
+pub fn validate_source(source: &str, prompts_dir: &Path) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        map: SourceMap::parse(source),
+        diagnostics: Vec::new(),
+    };
+    validator.check(source, prompts_dir);
+
+    let mut diagnostics = validator.diagnostics;
+    diagnostics.sort_by_key(|d| d.location);
+    diagnostics
+}
+
+struct Validator {
+    map: SourceMap,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator {
+    fn report(&mut self, location: Option<Location>, message: String) {
+        self.diagnostics.push(Diagnostic { location, message });
+    }
+
+    /// Report a problem with the value at `path`
+    fn error(&mut self, path: &[Segment], message: String) {
+        let location = self.map.locate(path);
+        self.report(location, message);
+    }
+
+    fn check(&mut self, source: &str, prompts_dir: &Path) {
+        let mut root: Value = match serde_yaml::from_str(source) {
+            Ok(root) => root,
+            Err(e) => {
+                let location = e.location().map(|l| Location {
+                    line: l.line(),
+                    column: l.column(),
+                });
+                self.report(
+                    location,
+                    format!("invalid YAML: {}", without_location(&e.to_string())),
+                );
+                return;
+            }
+        };
+        if !root.is_mapping() {
+            self.error(&[], "expected a mapping with `version` and `hooks`".into());
+            return;
+        }
+
+        // Hooks are checked one by one below, so one broken hook doesn't
+        // hide the problems in the others
+        let raw_hooks = root
+            .get_mut("hooks")
+            .map(|hooks| std::mem::replace(hooks, Value::Sequence(Vec::new())));
+        if let Some(config) = self.deserialize::<HooksConfig>(&root, &[]) {
+            if let Ok(parsed) = serde_yaml::to_value(&config) {
+                self.check_unknown_keys(&root, &parsed, &mut Vec::new());
+            }
+            self.check_settings(&config.settings);
+        }
+
+        let hooks_path = [Segment::Key("hooks".into())];
+        let items = match raw_hooks {
+            None => return,
+            Some(Value::Sequence(items)) => items,
+            Some(_) => {
+                self.error(&hooks_path, "`hooks` must be a list of hooks".into());
+                return;
+            }
+        };
+        if items.is_empty() {
+            self.error(&hooks_path, "no hooks defined".into());
+        }
+
+        let mut first_seen: HashMap<String, usize> = HashMap::new();
+        for (index, item) in items.iter().enumerate() {
+            // Checked before parsing, so a hook with other problems
+            // still counts as a duplicate
+            if let Some(id) = item.get("id").and_then(Value::as_str) {
+                match first_seen.get(id) {
+                    Some(&first) => {
+                        let line = self
+                            .map
+                            .locate(&hook_path(first, "id"))
+                            .map_or_else(String::new, |at| {
+                                format!(" (first defined on line {})", at.line)
+                            });
+                        self.error(
+                            &hook_path(index, "id"),
+                            format!("duplicate hook id \"{}\"{}", id, line),
+                        );
+                    }
+                    None => {
+                        first_seen.insert(id.to_string(), index);
+                    }
+                }
+            }
+
+            let base = [Segment::Key("hooks".into()), Segment::Index(index)];
+            let Some(hook) = self.deserialize::<HookConfig>(item, &base) else {
+                continue;
+            };
+            if let Ok(parsed) = serde_yaml::to_value(&hook) {
+                self.check_unknown_keys(item, &parsed, &mut base.to_vec());
+            }
+            self.check_hook(index, &hook, item, prompts_dir);
+        }
+    }
+
+    fn check_settings(&mut self, settings: &Settings) {
+        let path = |field: &str| {
+            let mut path = vec![Segment::Key("settings".into())];
+            path.extend(parse_path(field));
+            path
+        };
+        match &settings.notify {
+            None if settings.on_error == ErrorStrategy::Notify => self.error(
+                &path("on_error"),
+                "on_error is notify, but settings.notify names no channel or webhook".into(),
+            ),
+            Some(NotifyTarget::Channel { channel_id }) if !is_id(channel_id) => self.error(
+                &path("notify.channel_id"),
+                format!("notify channel \"{}\" is not a numeric channel ID", channel_id),
+            ),
+            _ => {}
+        }
+        if settings.max_processors == Some(0) {
+            self.error(
+                &path("max_processors"),
+                "max_processors is 0, so no hook could run".into(),
+            );
+        }
+    }
+
+    fn check_hook(&mut self, index: usize, hook: &HookConfig, raw: &Value, prompts_dir: &Path) {
+        if let Err(e) = hook.validate() {
+            let message = match e {
+                DiscliError::Config(message) => message,
+                other => other.to_string(),
+            };
+            self.error(&hook_path(index, ""), message);
+        }
+
+        self.check_trigger(&hook.trigger, hook_path(index, "trigger"));
+
+        for (i, channel) in hook.channels.iter().enumerate() {
+            if channel != DM_CHANNEL && !is_id(channel) {
+                let mut path = hook_path(index, "channels");
+                path.push(Segment::Index(i));
+                self.error(
+                    &path,
+                    format!(
+                        "channel \"{}\" is not a numeric channel ID or \"{}\"",
+                        channel, DM_CHANNEL
+                    ),
+                );
+            }
+        }
+        if let Some(filter) = &hook.filter {
+            let lists = [
+                ("guilds", &filter.guilds, "server"),
+                ("categories", &filter.categories, "category"),
+                ("users", &filter.users, "user"),
+                ("exclude_users", &filter.exclude_users, "user"),
+                ("roles", &filter.roles, "role"),
+                ("exclude_roles", &filter.exclude_roles, "role"),
+            ];
+            for (list, ids, kind) in lists {
+                for (i, id) in ids.iter().enumerate() {
+                    if !is_id(id) {
+                        let mut path = hook_path(index, &format!("filter.{}", list));
+                        path.push(Segment::Index(i));
+                        self.error(&path, format!("{} \"{}\" is not a numeric {} ID", list, id, kind));
+                    }
+                }
+            }
+        }
+        if let HookAction::Forward { channel_id } = &hook.action {
+            if !is_id(channel_id) {
+                self.error(
+                    &hook_path(index, "action.channel_id"),
+                    format!("channel_id \"{}\" is not a numeric channel ID", channel_id),
+                );
+            }
+        }
+
+        let processing = &hook.processing;
+        if raw.get("processing").is_none() {
+            self.error(
+                &hook_path(index, ""),
+                format!("hook \"{}\" has no processing configured; set processing.cmd or processing.url", hook.id),
+            );
+        } else {
+            match processing.processor_type.as_str() {
+                "command" if processing.cmd.is_empty() => self.error(
+                    &hook_path(index, "processing"),
+                    "command processor has no `cmd`".into(),
+                ),
+                "http" if processing.url.is_empty() => self.error(
+                    &hook_path(index, "processing"),
+                    "http processor has no `url`".into(),
+                ),
+                "command" | "http" => {}
+                other => self.error(
+                    &hook_path(index, "processing.processor_type"),
+                    format!("unknown processor_type \"{}\"; use command or http", other),
+                ),
+            }
+        }
+
+        let prompt_path = if hook.prompt_file.is_absolute() {
+            hook.prompt_file.clone()
+        } else {
+            prompts_dir.join(&hook.prompt_file)
+        };
+        let prompt_at = hook_path(index, "prompt_file");
+        if !prompt_path.is_file() {
+            self.error(
+                &prompt_at,
+                format!("prompt file not found: {}", prompt_path.display()),
+            );
+            return;
+        }
+        match PromptTemplate::load(&prompt_path) {
+            Ok(template) => {
+                let available = hook.event.variables();
+                for variable in &template.variables {
+                    if !available.contains(&variable.as_str()) {
+                        self.error(
+                            &prompt_at,
+                            format!(
+                                "{} uses {{{{{}}}}}, which {} events don't provide",
+                                prompt_path.display(),
+                                variable,
+                                hook.event
+                            ),
+                        );
+                    }
+                }
+            }
+            Err(e) => self.error(
+                &prompt_at,
+                format!("cannot read prompt file {}: {}", prompt_path.display(), e),
+            ),
+        }
+    }
+
+    /// Check the regexes and globs of a trigger and every trigger it combines
+    fn check_trigger(&mut self, trigger: &TriggerConfig, path: Vec<Segment>) {
+        let branch = |name: &str, index: Option<usize>| {
+            let mut path = path.clone();
+            path.push(Segment::Key(name.to_string()));
+            path.extend(index.map(Segment::Index));
+            path
+        };
+        match trigger {
+            TriggerConfig::Regex { pattern } => {
+                if let Err(e) = Regex::new(pattern) {
+                    // Parse errors span several lines, ending with the reason
+                    let error = e.to_string();
+                    let reason = error.lines().last().unwrap_or_default();
+                    self.error(
+                        &branch("pattern", None),
+                        format!("invalid regex: {}", reason.trim_start_matches("error: ")),
+                    );
+                }
+            }
+            TriggerConfig::HasAttachment { patterns } => {
+                for (i, pattern) in patterns.iter().enumerate() {
+                    if let Err(e) = glob::Pattern::new(pattern) {
+                        self.error(
+                            &branch("patterns", Some(i)),
+                            format!("invalid attachment pattern: {}", e.msg),
+                        );
+                    }
+                }
+            }
+            TriggerConfig::All(triggers) | TriggerConfig::AnyOf(triggers) => {
+                for (i, nested) in triggers.iter().enumerate() {
+                    self.check_trigger(nested, branch(trigger.type_name(), Some(i)));
+                }
+            }
+            TriggerConfig::Not(nested) => self.check_trigger(nested, branch("not", None)),
+            _ => {}
+        }
+    }
+
+    /// Deserialize `value`, reporting a failure at the path serde names
+    fn deserialize<T: DeserializeOwned>(&mut self, value: &Value, base: &[Segment]) -> Option<T> {
+        // Going through text makes serde_yaml report the path to the error
+        let text = serde_yaml::to_string(value).ok()?;
+        match serde_yaml::from_str(&text) {
+            Ok(parsed) => Some(parsed),
+            Err(e) => {
+                let error = without_location(&e.to_string());
+                let (path, message) = match error.split_once(": ") {
+                    Some((path, message)) if is_serde_path(path) => (parse_path(path), message),
+                    _ => (Vec::new(), error.as_str()),
+                };
+                let mut full = base.to_vec();
+                full.extend(path);
+                self.error(&full, message.to_string());
+                None
+            }
+        }
+    }
+
+    /// Report keys in `raw` that didn't make it into the parsed `parsed`
+    fn check_unknown_keys(&mut self, raw: &Value, parsed: &Value, path: &mut Vec<Segment>) {
+        match (raw, parsed) {
+            (Value::Mapping(raw), Value::Mapping(parsed)) => {
+                for (key, value) in raw {
+                    let Some(name) = key.as_str() else { continue };
+                    path.push(Segment::Key(name.to_string()));
+                    match parsed.get(name) {
+                        Some(parsed_value) => self.check_unknown_keys(value, parsed_value, path),
+                        None => {
+                            let expected: Vec<&str> =
+                                parsed.keys().filter_map(Value::as_str).collect();
+                            let location = self.map.locate_key(path);
+                            self.report(
+                                location,
+                                format!(
+                                    "unknown key \"{}\" (expected one of: {})",
+                                    name,
+                                    expected.join(", ")
+                                ),
+                            );
+                        }
+                    }
+                    path.pop();
+                }
+            }
+            (Value::Sequence(raw), Value::Sequence(parsed)) => {
+                for (index, (raw, parsed)) in raw.iter().zip(parsed).enumerate() {
+                    path.push(Segment::Index(index));
+                    self.check_unknown_keys(raw, parsed, path);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Path to a field of the hook at `index`, e.g. `trigger.pattern`
+fn hook_path(index: usize, field: &str) -> Vec<Segment> {
+    let mut path = vec![Segment::Key("hooks".into()), Segment::Index(index)];
+    path.extend(parse_path(field));
+    path
+}
+
+/// Whether `value` is a Discord ID (a nonzero integer)
+fn is_id(value: &str) -> bool {
+    value.parse::<u64>().is_ok_and(|id| id > 0)
+}
+
+/// Drop serde_yaml's "at line L column C", which refers to its own input
+fn without_location(message: &str) -> String {
+    static LOCATION: OnceLock<Regex> = OnceLock::new();
+    LOCATION
+        .get_or_init(|| Regex::new(r" at line \d+ column \d+").unwrap())
+        .replace_all(message, "")
+        .into_owned()
+}
+
+fn is_serde_path(path: &str) -> bool {
+    !path.is_empty()
+        && path
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '[' | ']'))
+}
+
+/// One step of a path into the document
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Parse a path like `hooks[0].trigger.pattern`
+fn parse_path(path: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    for part in path.split('.').filter(|p| !p.is_empty()) {
+        let mut pieces = part.split('[');
+        if let Some(key) = pieces.next().filter(|k| !k.is_empty()) {
+            segments.push(Segment::Key(key.to_string()));
+        }
+        for index in pieces {
+            if let Ok(index) = index.trim_end_matches(']').parse() {
+                segments.push(Segment::Index(index));
+            }
+        }
+    }
+    segments
+}
+
+/// Where each key and value of a YAML document starts
+///
+/// serde_yaml's `Value` carries no positions, so the document is parsed a
+/// second time with yaml-rust2, whose events say where each node starts.
+struct SourceMap {
+    root: Option<Node>,
+}
+
+struct Node {
+    at: Location,
+    kind: NodeKind,
+}
+
+enum NodeKind {
+    Mapping(Vec<Entry>),
+    Sequence(Vec<Node>),
+    Scalar,
+}
+
+struct Entry {
+    key: String,
+    at: Location,
+    value: Node,
+}
+
+impl SourceMap {
+    fn parse(source: &str) -> Self {
+        let mut builder = MapBuilder::default();
+        // serde_yaml reports syntax errors; whatever parsed before one is
+        // still mapped
+        let _ = YamlParser::new_from_str(source).load(&mut builder, false);
+        while let Some(open) = builder.open.pop() {
+            builder.add(open.node);
+        }
+        Self { root: builder.root }
+    }
+
+    /// Find the node at `path`, or its deepest ancestor in the document,
+    /// and the key leading to it
+    fn find(&self, path: &[Segment]) -> Option<(Location, Option<Location>)> {
+        let mut node = self.root.as_ref()?;
+        let mut key_at = None;
+        for segment in path {
+            let next = match (segment, &node.kind) {
+                (Segment::Key(key), NodeKind::Mapping(entries)) => entries
+                    .iter()
+                    .find(|e| &e.key == key)
+                    .map(|e| (Some(e.at), &e.value)),
+                (Segment::Index(index), NodeKind::Sequence(items)) => {
+                    items.get(*index).map(|item| (None, item))
+                }
+                _ => None,
+            };
+            match next {
+                Some((at, child)) => {
+                    key_at = at;
+                    node = child;
+                }
+                None => return Some((node.at, None)),
+            }
+        }
+        Some((node.at, key_at))
+    }
+
+    /// Where the value at `path` starts
+    fn locate(&self, path: &[Segment]) -> Option<Location> {
+        self.find(path).map(|(at, _)| at)
+    }
+
+    /// Where the key of the value at `path` starts
+    fn locate_key(&self, path: &[Segment]) -> Option<Location> {
+        self.find(path).map(|(at, key_at)| key_at.unwrap_or(at))
+    }
+}
+
+/// Builds a [`SourceMap`] from parser events
+#[derive(Default)]
+struct MapBuilder {
+    root: Option<Node>,
+    /// Collections not yet ended, innermost last
+    open: Vec<OpenNode>,
+}
+
+/// A collection being built, and for mappings the key awaiting its value
+struct OpenNode {
+    node: Node,
+    key: Option<(String, Location)>,
+}
+
+impl MapBuilder {
+    fn open(&mut self, at: Location, kind: NodeKind) {
+        self.open.push(OpenNode {
+            node: Node { at, kind },
+            key: None,
+        });
+    }
+
+    /// Add a finished node to the collection it is in
+    fn add(&mut self, node: Node) {
+        let Some(parent) = self.open.last_mut() else {
+            self.root.get_or_insert(node);
+            return;
+        };
+        match &mut parent.node.kind {
+            NodeKind::Mapping(entries) => match parent.key.take() {
+                Some((key, at)) => entries.push(Entry { key, at, value: node }),
+                // A collection used as a key, which no hooks field is
+                None => parent.key = Some((String::new(), node.at)),
+            },
+            NodeKind::Sequence(items) => items.push(node),
+            NodeKind::Scalar => {}
+        }
+    }
+
+    fn scalar(&mut self, value: String, at: Location) {
+        match self.open.last_mut() {
+            Some(parent) if matches!(parent.node.kind, NodeKind::Mapping(_)) && parent.key.is_none() => {
+                // Block mappings are marked at their first `:`; they start
+                // at their first key
+                parent.node.at = parent.node.at.min(at);
+                parent.key = Some((value, at));
+            }
+            _ => self.add(Node {
+                at,
+                kind: NodeKind::Scalar,
+            }),
+        }
+    }
+}
+
+impl MarkedEventReceiver for MapBuilder {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        let at = Location {
+            line: mark.line(),
+            column: mark.col() + 1,
+        };
+        match event {
+            Event::MappingStart(..) => self.open(at, NodeKind::Mapping(Vec::new())),
+            Event::SequenceStart(..) => self.open(at, NodeKind::Sequence(Vec::new())),
+            Event::MappingEnd | Event::SequenceEnd => {
+                if let Some(open) = self.open.pop() {
+                    self.add(open.node);
+                }
+            }
+            Event::Scalar(value, ..) => self.scalar(value, at),
+            Event::Alias(_) => self.scalar(String::new(), at),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reports_every_problem_with_location() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("help.txt"), "{{author_name}} asked").unwrap();
+        std::fs::write(temp_dir.path().join("join.txt"), "Welcome {{content}}").unwrap();
+
+        let source = r#"version: "1.0"
+hooks:
+  - id: help
+    channels: ["123", "general"]
+    trigger: { type: regex, pattern: "(unclosed" }
+    prompt_file: help.txt
+    action: { type: reply }
+    processing: { processor_type: command }
+  - id: help
+    event: member_join
+    prompt_file: join.txt
+    action:
+      type: forward
+      channel_id: "123"
+    procesing:
+      cmd: ["cat"]
+  - id: missing
+    channels: ["dm"]
+    prompt_file: missing.txt   # not written
+    action: { type: reply }
+    processing: { processor_type: http }
+"#;
+
+        let diagnostics: Vec<String> = validate_source(source, temp_dir.path())
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        let missing = temp_dir.path().join("missing.txt");
+        let join = temp_dir.path().join("join.txt");
+        assert_eq!(
+            diagnostics,
+            vec![
+                "4:23: channel \"general\" is not a numeric channel ID or \"dm\"".to_string(),
+                "5:38: invalid regex: unclosed group".to_string(),
+                "8:17: command processor has no `cmd`".to_string(),
+                "9:5: hook \"help\" has no processing configured; set processing.cmd or processing.url".to_string(),
+                "9:9: duplicate hook id \"help\" (first defined on line 3)".to_string(),
+                format!("11:18: {} uses {{{{content}}}}, which member_join events don't provide", join.display()),
+                "15:5: unknown key \"procesing\" (expected one of: id, name, enabled, event, channels, trigger, prompt_file, filter, action, processing, rate_limit, cooldown_seconds, on_failure, priority, continue, fallback, max_concurrency, on_busy)".to_string(),
+                format!("19:18: prompt file not found: {}", missing.display()),
+                "21:17: http processor has no `url`".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_trigger_problems() {
+        let source = r#"version: "1.0"
+hooks:
+  - id: a
+    channels: ["1"]
+    trigger:
+      all:
+        - { type: prefix, prefix: "!" }
+        - not: { type: regex, pattern: "[" }
+        - { type: contains, substring: "x", typo: 1 }
+        - { type: has_attachment, patterns: ["*.log", "[log"] }
+    prompt_file: p.txt
+    action: { type: reply }
+    processing: { processor_type: command, cmd: [cat] }
+"#;
+        let diagnostics: Vec<String> = validate_source(source, Path::new("/nonexistent"))
+            .iter()
+            .filter(|d| !d.message.starts_with("prompt file"))
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                "8:40: invalid regex: unclosed character class".to_string(),
+                "9:45: unknown key \"typo\" (expected one of: type, substring)".to_string(),
+                "10:55: invalid attachment pattern: invalid range pattern".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_notify_settings() {
+        let hooks = "hooks:\n  - id: a\n    channels: [\"1\"]\n    prompt_file: p.txt\n    action: { type: reply }\n    processing: { processor_type: command, cmd: [cat] }\n";
+        let check = |settings: &str| -> Vec<String> {
+            let source = format!("version: \"1.0\"\nsettings:\n{}{}", settings, hooks);
+            validate_source(&source, Path::new("/nonexistent"))
+                .iter()
+                .filter(|d| !d.message.starts_with("prompt file"))
+                .map(|d| d.to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            check("  on_error: notify\n"),
+            vec!["3:13: on_error is notify, but settings.notify names no channel or webhook"]
+        );
+        assert_eq!(
+            check("  on_error: notify\n  notify: { type: channel, channel_id: \"admins\" }\n"),
+            vec!["4:40: notify channel \"admins\" is not a numeric channel ID"]
+        );
+        assert!(check("  on_error: notify\n  notify: { type: webhook, url: \"https://example.com/hook\" }\n").is_empty());
+        assert_eq!(
+            check("  max_processors: 0\n"),
+            vec!["3:19: max_processors is 0, so no hook could run"]
+        );
+    }
+
+    #[test]
+    fn test_type_errors_point_at_value() {
+        let source = "version: \"1.0\"\nhooks:\n  - id: a\n    channels: [\"1\"]\n    trigger: { type: regexx }\n    prompt_file: p.txt\n    action: { type: reply }\n  - id: b\n    prompt_file: p.txt\n";
+        let diagnostics = validate_source(source, Path::new("/nonexistent"));
+
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+        assert_eq!(
+            diagnostics[0].location,
+            Some(Location {
+                line: 5,
+                column: 22
+            })
+        );
+        assert!(diagnostics[0].message.contains("unknown variant `regexx`"));
+        assert_eq!(
+            diagnostics[1].location,
+            Some(Location { line: 8, column: 5 })
+        );
+        assert_eq!(diagnostics[1].message, "missing field `action`");
+    }
+
+    #[test]
+    fn test_source_map_locates_block_and_flow_values() {
+        let map = SourceMap::parse("a:\n- b: { c: [1, 2], d: \"x\" }  # note\n  e:\n    - f\n");
+        let at = |path: &str| map.locate(&parse_path(path)).map(|l| (l.line, l.column));
+
+        assert_eq!(at("a[0].b.c[1]"), Some((2, 15)));
+        assert_eq!(at("a[0].b.d"), Some((2, 22)));
+        assert_eq!(at("a[0].e[0]"), Some((4, 7)));
+        // Missing paths fall back to the closest ancestor
+        assert_eq!(at("a[0].missing"), Some((2, 3)));
+        let key = map.locate_key(&parse_path("a[0].e")).unwrap();
+        assert_eq!((key.line, key.column), (3, 3));
+    }
+
+    #[test]
+    fn test_non_ascii_source() {
+        let source = "version: \"1.0\"\nhooks:\n  - { id: \"café\", channels: [\"général\"], trigger: {\u{a0}type: prefix, prefix: \"!x\" }, prompt_file: p.txt, action: { type: reply } }\n";
+        let diagnostics: Vec<String> = validate_source(source, Path::new("/nonexistent"))
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        // YAML doesn't count U+00A0 as whitespace, so `type` is missing;
+        // columns count characters, not bytes
+        assert_eq!(diagnostics, vec!["3:51: missing field `type`"]);
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let diagnostics = validate_source("version: \"1.0\"\nhooks: [\n", Path::new("."));
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.starts_with("invalid YAML: "));
+        assert!(diagnostics[0].location.is_some());
+    }
+}
@@ -0,0 +1,66 @@
+//! discli - Discord messaging, hooks and prompts as a library
+//!
+//! The `discli` binary is a thin layer over this crate. Other Rust programs
+//! can use the same building blocks:
+//!
+//! - [`message`] and [`discord::types`] for building text, attachment and
+//!   embed messages
+//! - [`DiscordClient`] for sending them through the Discord REST API
+//! - [`hooks`] for loading `hooks.yaml`, matching messages against triggers
+//!   and executing hook actions
+//! - [`prompt`] for loading and rendering prompt templates
+//! - [`sink`] for redirecting output to webhooks, stdout, files or memory
+//! - [`mock`] for running a local stand-in for the Discord API in tests
+//!
+//! # Examples
+//!
+//! Sending a message with an embed:
+//!
+//! ```no_run
+//! use discli::{DiscordClient, DiscordMessage, Embed};
+//!
+//! # async fn example() -> discli::Result<()> {
+//! let embed = Embed {
+//!     title: Some("Deploy finished".to_string()),
+//!     description: Some("All services are healthy".to_string()),
+//!     color: Some(0x2ECC71),
+//!     ..Default::default()
+//! };
+//! let message = DiscordMessage::WithEmbeds {
+//!     content: None,
+//!     embeds: vec![embed],
+//! };
+//!
+//! let client = DiscordClient::new("bot-token".to_string());
+//! client.send_message("123456789012345678", &message).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Building a message with the builder:
+//!
+//! ```
+//! use discli::{DiscordMessage, MessageBuilder};
+//!
+//! let message = MessageBuilder::new().content("Build #42 passed").build();
+//! assert!(matches!(message, DiscordMessage::Simple { .. }));
+//! ```
+
+pub mod config;
+pub mod discord;
+pub mod error;
+pub mod hooks;
+pub mod message;
+pub mod mock;
+pub mod processing;
+pub mod prompt;
+pub mod sink;
+
+pub use config::Config;
+pub use discord::types::{
+    DiscordMessage, Embed, EmbedAuthor, EmbedField, EmbedFooter, EmbedMedia, EmbedThumbnail,
+};
+pub use discord::{DiscordClient, DiscordGateway};
+pub use error::{DiscliError, Result};
+pub use message::MessageBuilder;
+pub use sink::{MessageSink, SinkSpec};
@@ -1,42 +1,249 @@
 //! discli - Discord CLI with image support
 //!
 //! A command-line tool for sending Discord notifications with support for
-//! text messages and image attachments.
+//! text messages and image attachments. The heavy lifting lives in the
+//! `discli` library crate; this binary only parses arguments and dispatches
+//! to the command implementations.
 
 mod cli;
 mod commands;
-mod config;
-mod discord;
-mod error;
-mod hooks;
-mod message;
-mod processing;
-mod prompt;
+mod daemon;
 
 use clap::Parser;
-use error::Result;
+use daemon::Fork;
+use discli::config;
+use discli::error::{DiscliError, Result};
+use discli::sink::{MessageSink, SinkSpec};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
-#[tokio::main]
-async fn main() {
-    if let Err(e) = run().await {
+fn main() {
+    let cli = cli::Cli::parse();
+
+    if let Err(e) = start(cli) {
         eprintln!("Error: {}", e);
-        std::process::exit(1);
+        if let Some(hint) = e.hint() {
+            eprintln!("Hint: {}", hint);
+        }
+        std::process::exit(e.exit_code());
     }
 }
 
+/// Set up the process for `listen`, then run the command on a tokio runtime
+///
+/// Detaching forks the process, which has to happen before the runtime
+/// starts its worker threads.
+fn start(cli: cli::Cli) -> Result<()> {
+    let mut pidfile = None;
+    let mut log_file = None;
+
+    if let Some(cli::Commands::Listen {
+        foreground,
+        pidfile: pidfile_path,
+        log_file: log_file_path,
+        stop,
+        status,
+        shutdown_timeout,
+        ..
+    }) = &cli.command
+    {
+        let explicit_pidfile = pidfile_path.is_some();
+        let pidfile_path = pidfile_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(daemon::DEFAULT_PIDFILE));
+
+        if *status {
+            return daemon::status(&pidfile_path);
+        }
+        if *stop {
+            // Leave the listener time to drain its hooks before giving up
+            return daemon::stop(&pidfile_path, Duration::from_secs(shutdown_timeout + 5));
+        }
+
+        if *foreground || !daemon::BACKGROUND_SUPPORTED {
+            if let Some(path) = log_file_path {
+                daemon::redirect_output(path)?;
+                log_file = Some(path.clone());
+            }
+            if explicit_pidfile {
+                pidfile = Some(daemon::Pidfile::acquire(&pidfile_path)?);
+            }
+        } else {
+            let path = log_file_path
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(daemon::DEFAULT_LOG_FILE));
+            daemon::ensure_not_running(&pidfile_path)?;
+            if let Fork::Parent(pid) = daemon::daemonize(&path)? {
+                println!("discli listen started in background (pid {})", pid);
+                println!("  Log file: {}", path.display());
+                println!("  Stop with: discli listen --stop");
+                return Ok(());
+            }
+            pidfile = Some(daemon::Pidfile::acquire(&pidfile_path)?);
+            log_file = Some(path);
+        }
+    }
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let result = runtime.block_on(async {
+        if let Some(path) = log_file {
+            daemon::reopen_log_on_sighup(path)?;
+        }
+        run(cli).await
+    });
+
+    // Remove the pidfile only once the listener has fully stopped
+    drop(pidfile);
+    result
+}
+
 /// Main application entry point
-async fn run() -> Result<()> {
-    // Parse CLI arguments
-    let cli = cli::Cli::parse();
+async fn run(mut cli: cli::Cli) -> Result<()> {
+    // The mock server stands in for Discord, so it needs no configuration
+    if let Some(cli::Commands::MockServer {
+        listen,
+        rate_limit_every,
+        fail_every,
+        fail_status,
+    }) = cli.command
+    {
+        return commands::mock_server::execute(listen, rate_limit_every, fail_every, fail_status)
+            .await;
+    }
+
+    // Replay and the hooks tools work offline, so they need no token
+    match cli.command {
+        Some(cli::Commands::Replay {
+            recording,
+            hooks_file,
+            prompts_dir,
+            realtime,
+            speed,
+            verbose,
+        }) => {
+            let config = config::Config::load_offline()?;
+            let sink = offline_sink(cli.sink, &config)?;
+            let options = commands::replay::ReplayOptions {
+                hooks_file,
+                prompts_dir,
+                realtime,
+                speed,
+                verbose,
+            };
+            return commands::replay::execute(&config, sink, &recording, options).await;
+        }
+        Some(cli::Commands::Hooks { command }) => {
+            let config = config::Config::load_offline()?;
+            let sink = offline_sink(cli.sink, &config)?;
+            return handle_hooks_command(&config, sink, command).await;
+        }
+        command => cli.command = command,
+    }
 
     // Load configuration from environment
     let config = config::Config::load()?;
 
+    // Select where output messages go
+    let sink = cli.sink.build(&config)?;
+
     // Handle backward compatibility for legacy syntax
     if !cli.legacy_message.is_empty() {
-        handle_legacy_syntax(&config, cli.legacy_message).await
+        handle_legacy_syntax(&config, sink, cli.legacy_message).await
     } else {
-        handle_subcommands(&config, cli.command).await
+        handle_subcommands(&config, sink, cli.command).await
+    }
+}
+
+/// Build the sink for a command that must not reach Discord
+///
+/// The default `discord` sink becomes `stdout`; webhooks are refused.
+fn offline_sink(spec: SinkSpec, config: &config::Config) -> Result<Arc<dyn MessageSink>> {
+    match spec {
+        SinkSpec::Discord => SinkSpec::Stdout,
+        SinkSpec::Webhook(_) => {
+            return Err(DiscliError::Config(
+                "this command does not send messages anywhere; use --sink stdout, stdout:json or file:<path>"
+                    .into(),
+            ))
+        }
+        other => other,
+    }
+    .build(config)
+}
+
+/// Handle `discli hooks` subcommands
+async fn handle_hooks_command(
+    config: &config::Config,
+    sink: Arc<dyn MessageSink>,
+    command: cli::HooksCommand,
+) -> Result<()> {
+    match command {
+        cli::HooksCommand::Simulate {
+            channel,
+            guild,
+            author,
+            author_name,
+            roles,
+            permissions,
+            category,
+            bot,
+            mentions_bot,
+            reply_to_bot,
+            in_bot_thread,
+            reply,
+            embed,
+            attachments,
+            content,
+            run,
+            hooks_file,
+            prompts_dir,
+        } => {
+            let options = commands::hooks::SimulateOptions {
+                hooks_file,
+                prompts_dir,
+                channel,
+                guild,
+                author,
+                author_name,
+                roles,
+                permissions,
+                category,
+                bot,
+                mentions_bot,
+                reply_to_bot,
+                in_bot_thread,
+                reply,
+                embed,
+                attachments,
+                content,
+                run,
+            };
+            commands::hooks::simulate(config, sink, options).await
+        }
+        cli::HooksCommand::Test {
+            suite,
+            hooks_file,
+            prompts_dir,
+            junit,
+        } => {
+            let options = commands::hooks::TestOptions {
+                hooks_file,
+                prompts_dir,
+                junit,
+            };
+            commands::hooks::test(config, &suite, options).await
+        }
+        cli::HooksCommand::Validate {
+            hooks_file,
+            prompts_dir,
+        } => {
+            let options = commands::hooks::ValidateOptions {
+                hooks_file,
+                prompts_dir,
+            };
+            commands::hooks::validate(config, options)
+        }
     }
 }
 
@@ -44,7 +251,11 @@ async fn run() -> Result<()> {
 ///
 /// Legacy syntax: `discli "message"`
 /// New syntax: `discli send "message"`
-async fn handle_legacy_syntax(config: &config::Config, legacy_message: Vec<String>) -> Result<()> {
+async fn handle_legacy_syntax(
+    config: &config::Config,
+    sink: Arc<dyn MessageSink>,
+    legacy_message: Vec<String>,
+) -> Result<()> {
     // Warn about deprecation
     eprintln!("⚠️  Warning: Direct message argument is deprecated.");
     eprintln!("  Current: discli \"message\"");
@@ -53,12 +264,13 @@ async fn handle_legacy_syntax(config: &config::Config, legacy_message: Vec<Strin
 
     // Use legacy behavior
     let content = legacy_message.join(" ");
-    commands::send::execute(config, content, Vec::new(), Vec::new(), None).await
+    commands::send::execute(config, sink.as_ref(), content, Vec::new(), Vec::new(), None).await
 }
 
 /// Handle subcommands
 async fn handle_subcommands(
     config: &config::Config,
+    sink: Arc<dyn MessageSink>,
     command: Option<cli::Commands>,
 ) -> Result<()> {
     match command {
@@ -68,14 +280,14 @@ async fn handle_subcommands(
             embed_url,
             caption,
         }) => {
-            commands::send::execute(config, content, attach, embed_url, caption).await
+            commands::send::execute(config, sink.as_ref(), content, attach, embed_url, caption).await
         }
         Some(cli::Commands::Image {
             attach,
             caption,
             embed_url,
         }) => {
-            commands::image::execute(config, attach, caption, embed_url).await
+            commands::image::execute(config, sink.as_ref(), attach, caption, embed_url).await
         }
         Some(cli::Commands::Embed {
             title,
@@ -93,6 +305,7 @@ async fn handle_subcommands(
         }) => {
             commands::embed::execute(
                 config,
+                sink.as_ref(),
                 title,
                 embed_url,
                 description,
@@ -109,12 +322,41 @@ async fn handle_subcommands(
             .await
         }
         Some(cli::Commands::Listen {
-            foreground,
             hooks_file,
             prompts_dir,
             verbose,
+            shutdown_timeout,
+            shards,
+            shard_range,
+            record,
+            reload_interval,
+            ..
         }) => {
-            commands::listen::execute(config, hooks_file, prompts_dir, verbose).await
+            let options = commands::listen::ListenOptions {
+                hooks_file,
+                prompts_dir,
+                verbose,
+                shutdown_timeout: Duration::from_secs(shutdown_timeout),
+                shards,
+                shard_range,
+                record,
+                reload_interval: (reload_interval > 0)
+                    .then(|| Duration::from_secs(reload_interval)),
+            };
+            commands::listen::execute(config, sink, options).await
+        }
+        Some(cli::Commands::Doctor {
+            hooks_file,
+            prompts_dir,
+            api_base,
+        }) => {
+            commands::doctor::execute(config, hooks_file, prompts_dir, api_base).await
+        }
+        Some(cli::Commands::MockServer { .. }) => {
+            unreachable!("mock-server is handled before configuration is loaded")
+        }
+        Some(cli::Commands::Replay { .. }) | Some(cli::Commands::Hooks { .. }) => {
+            unreachable!("offline commands are handled before configuration is loaded")
         }
         None => {
             // No subcommand provided, show help
@@ -0,0 +1,304 @@
+//! Minimal HTTP/1.1 plumbing for the mock server
+//!
+//! Just enough to serve reqwest and curl: request line, headers,
+//! `Content-Length` bodies, keep-alive, and `multipart/form-data` decoding.
+
+use std::collections::HashMap;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest request the mock server accepts (headers + body)
+const MAX_REQUEST_SIZE: usize = 64 * 1024 * 1024;
+
+/// A parsed HTTP request
+#[derive(Debug)]
+pub struct Request {
+    /// Request method, e.g. `POST`
+    pub method: String,
+    /// Request path without the query string
+    pub path: String,
+    /// Header map with lowercased names
+    pub headers: HashMap<String, String>,
+    /// Raw request body
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Whether the client asked to close the connection after this request
+    pub fn wants_close(&self) -> bool {
+        self.headers
+            .get("connection")
+            .map(|v| v.eq_ignore_ascii_case("close"))
+            .unwrap_or(false)
+    }
+}
+
+/// An HTTP response
+#[derive(Debug)]
+pub struct Response {
+    /// Status code
+    pub status: u16,
+    /// Extra headers
+    pub headers: Vec<(String, String)>,
+    /// JSON body (empty for 204)
+    pub body: String,
+}
+
+impl Response {
+    /// A JSON response
+    pub fn json(status: u16, body: &serde_json::Value) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    /// An empty `204 No Content` response
+    pub fn no_content() -> Self {
+        Self {
+            status: 204,
+            headers: Vec::new(),
+            body: String::new(),
+        }
+    }
+
+    /// A Discord-style JSON error response
+    pub fn error(status: u16, code: u64, message: &str) -> Self {
+        Self::json(
+            status,
+            &serde_json::json!({ "message": message, "code": code }),
+        )
+    }
+}
+
+/// Read one request from the stream
+///
+/// Returns `Ok(None)` when the client closed the connection cleanly.
+pub async fn read_request<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    buffer: &mut Vec<u8>,
+) -> std::io::Result<Option<Request>> {
+    let header_end = loop {
+        if let Some(pos) = find(buffer, b"\r\n\r\n") {
+            break pos;
+        }
+        if buffer.len() > MAX_REQUEST_SIZE {
+            return Err(invalid("request headers too large"));
+        }
+        let mut chunk = [0u8; 8192];
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return if buffer.is_empty() {
+                Ok(None)
+            } else {
+                Err(invalid("connection closed mid-request"))
+            };
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default();
+    let path = target.split('?').next().unwrap_or_default().to_string();
+
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let content_length: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    if content_length > MAX_REQUEST_SIZE {
+        return Err(invalid("request body too large"));
+    }
+
+    let body_start = header_end + 4;
+    while buffer.len() < body_start + content_length {
+        let mut chunk = [0u8; 8192];
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(invalid("connection closed mid-body"));
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    }
+
+    let body = buffer[body_start..body_start + content_length].to_vec();
+    buffer.drain(..body_start + content_length);
+
+    Ok(Some(Request {
+        method,
+        path,
+        headers,
+        body,
+    }))
+}
+
+/// Write a response to the stream
+pub async fn write_response<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    response: &Response,
+    close: bool,
+) -> std::io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\n",
+        response.status,
+        reason(response.status),
+        response.body.len()
+    );
+    if !response.body.is_empty() {
+        head.push_str("Content-Type: application/json\r\n");
+    }
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(if close {
+        "Connection: close\r\n\r\n"
+    } else {
+        "Connection: keep-alive\r\n\r\n"
+    });
+
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.flush().await
+}
+
+/// One part of a `multipart/form-data` body
+#[derive(Debug)]
+pub struct FormPart {
+    /// Form field name
+    pub name: String,
+    /// Uploaded filename, for file parts
+    pub filename: Option<String>,
+    /// Part content
+    pub data: Vec<u8>,
+}
+
+/// Decode a `multipart/form-data` body
+///
+/// Returns an empty list if the content type has no boundary.
+pub fn parse_multipart(content_type: &str, body: &[u8]) -> Vec<FormPart> {
+    let Some(boundary) = content_type
+        .split(';')
+        .filter_map(|p| p.trim().strip_prefix("boundary="))
+        .next()
+    else {
+        return Vec::new();
+    };
+    let delimiter = format!("--{}", boundary.trim_matches('"'));
+
+    let mut parts = Vec::new();
+    let mut rest = body;
+    while let Some(start) = find(rest, delimiter.as_bytes()) {
+        rest = &rest[start + delimiter.len()..];
+        if rest.starts_with(b"--") {
+            break;
+        }
+        let rest_trimmed = rest.strip_prefix(b"\r\n").unwrap_or(rest);
+        let Some(header_end) = find(rest_trimmed, b"\r\n\r\n") else {
+            break;
+        };
+        let Some(next) = find(&rest_trimmed[header_end..], delimiter.as_bytes()) else {
+            break;
+        };
+
+        let headers = String::from_utf8_lossy(&rest_trimmed[..header_end]).to_string();
+        let data_end = (header_end + next).saturating_sub(2).max(header_end + 4);
+        let data = rest_trimmed[header_end + 4..data_end].to_vec();
+
+        let disposition = headers
+            .lines()
+            .find(|l| l.to_ascii_lowercase().starts_with("content-disposition"))
+            .unwrap_or_default();
+        parts.push(FormPart {
+            name: disposition_param(disposition, "name").unwrap_or_default(),
+            filename: disposition_param(disposition, "filename"),
+            data,
+        });
+
+        rest = &rest_trimmed[header_end..];
+    }
+
+    parts
+}
+
+/// Extract a quoted parameter from a `Content-Disposition` header
+fn disposition_param(header: &str, param: &str) -> Option<String> {
+    header
+        .split(';')
+        .map(str::trim)
+        .filter_map(|p| p.split_once('='))
+        .find(|(key, _)| *key == param)
+        .map(|(_, value)| value.trim_matches('"').to_string())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_request_with_body() {
+        let raw = b"POST /api/v10/channels/1/messages?wait=true HTTP/1.1\r\nHost: x\r\nContent-Length: 5\r\n\r\nhello";
+        let mut stream = &raw[..];
+        let mut buffer = Vec::new();
+
+        let request = read_request(&mut stream, &mut buffer)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/api/v10/channels/1/messages");
+        assert_eq!(request.body, b"hello");
+        assert!(read_request(&mut stream, &mut buffer)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_parse_multipart() {
+        let body = b"--xyz\r\nContent-Disposition: form-data; name=\"payload_json\"\r\n\r\n{\"content\":\"hi\"}\r\n--xyz\r\nContent-Disposition: form-data; name=\"files[0]\"; filename=\"a.png\"\r\nContent-Type: image/png\r\n\r\nPNGDATA\r\n--xyz--\r\n";
+
+        let parts = parse_multipart("multipart/form-data; boundary=xyz", body);
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name, "payload_json");
+        assert_eq!(parts[0].data, b"{\"content\":\"hi\"}");
+        assert_eq!(parts[1].filename.as_deref(), Some("a.png"));
+        assert_eq!(parts[1].data, b"PNGDATA");
+    }
+}
@@ -0,0 +1,38 @@
+//! Local stand-in for the Discord REST API
+//!
+//! [`MockServer`] implements the endpoints discli uses (current user,
+//! gateway info, channels, guilds, members, channel messages, webhooks,
+//! reactions and DM channels) and keeps posted messages in memory. Point `DISCORD_API_BASE`
+//! at [`MockServer::base_url`] to run notification steps without a real bot.
+//!
+//! Stored state can be inspected over HTTP:
+//!
+//! - `GET /_mock/messages` lists posted messages, `DELETE` clears them
+//! - `GET /_mock/reactions` lists reactions, `DELETE` clears them
+//!
+//! # Examples
+//!
+//! ```
+//! use discli::mock::{MockServer, MockServerConfig};
+//! use discli::{DiscordClient, DiscordMessage};
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> discli::Result<()> {
+//! let server = MockServer::start("127.0.0.1:0", MockServerConfig::default()).await?;
+//! let client = DiscordClient::with_base_url("token".to_string(), server.base_url());
+//!
+//! let message = DiscordMessage::Simple { content: "hello".to_string() };
+//! client.send_message("123", &message).await?;
+//!
+//! assert_eq!(server.messages()[0].content.as_deref(), Some("hello"));
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod http;
+pub mod server;
+
+pub use server::{
+    MockAttachment, MockMessage, MockReaction, MockServer, MockServerConfig, MOCK_BOT_ID,
+    MOCK_GUILD_ID,
+};
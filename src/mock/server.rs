@@ -0,0 +1,771 @@
+//! In-memory stand-in for the Discord REST API
+
+use super::http::{parse_multipart, read_request, write_response, Request, Response};
+use crate::discord::error::{EMPTY_MESSAGE, INVALID_FORM_BODY, UNKNOWN_CHANNEL, UNKNOWN_GUILD};
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use twilight_model::guild::Permissions;
+
+/// User ID of the bot account the mock server pretends to be
+pub const MOCK_BOT_ID: &str = "100000000000000001";
+
+/// ID of the single guild every mock channel belongs to
+pub const MOCK_GUILD_ID: &str = "100000000000000002";
+
+/// Discord's message length limit
+const MAX_CONTENT_LENGTH: usize = 2000;
+
+/// How long to wait before accepting again after a failed accept
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Fault injection settings for the mock server
+///
+/// Injected responses are deterministic: with `rate_limit_every: Some(3)`
+/// the 3rd, 6th, 9th... API request is answered with a 429. Requests to the
+/// `/_mock/*` inspection endpoints are never counted or failed.
+#[derive(Debug, Clone)]
+pub struct MockServerConfig {
+    /// Answer every Nth API request with `429 Too Many Requests`
+    pub rate_limit_every: Option<u64>,
+    /// Answer every Nth API request with `fail_status`
+    pub fail_every: Option<u64>,
+    /// Status code used for injected failures
+    pub fail_status: u16,
+    /// `retry_after` seconds reported in injected 429s
+    pub retry_after: f64,
+    /// Shard count recommended by `GET /gateway/bot`
+    pub shards: u32,
+}
+
+impl Default for MockServerConfig {
+    fn default() -> Self {
+        Self {
+            rate_limit_every: None,
+            fail_every: None,
+            fail_status: 500,
+            retry_after: 1.0,
+            shards: 1,
+        }
+    }
+}
+
+/// An attachment uploaded with a mock message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockAttachment {
+    /// Uploaded filename
+    pub filename: String,
+    /// Size in bytes
+    pub size: usize,
+}
+
+/// A message posted to the mock server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockMessage {
+    /// Generated message ID
+    pub id: String,
+    /// Channel the message was posted to (channel messages only)
+    pub channel_id: Option<String>,
+    /// Webhook the message was posted through (webhook messages only)
+    pub webhook_id: Option<String>,
+    /// Message content
+    pub content: Option<String>,
+    /// Embeds as sent
+    #[serde(default)]
+    pub embeds: Vec<Value>,
+    /// Uploaded files
+    #[serde(default)]
+    pub attachments: Vec<MockAttachment>,
+}
+
+/// A reaction added through the mock server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockReaction {
+    /// Channel of the reacted message
+    pub channel_id: String,
+    /// Reacted message ID
+    pub message_id: String,
+    /// Emoji as it appeared in the URL
+    pub emoji: String,
+}
+
+#[derive(Default)]
+struct State {
+    messages: Vec<MockMessage>,
+    reactions: Vec<MockReaction>,
+    request_count: u64,
+    next_id: u64,
+}
+
+impl State {
+    fn next_id(&mut self) -> String {
+        self.next_id += 1;
+        (200_000_000_000_000_000 + self.next_id).to_string()
+    }
+}
+
+/// A running mock Discord API server
+///
+/// The server stops when this handle is dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Bind to `addr` and start serving in the background
+    ///
+    /// Use port 0 to pick a free port, then read it back with
+    /// [`MockServer::local_addr`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be bound
+    pub async fn start(addr: &str, config: MockServerConfig) -> Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let config = Arc::new(config);
+
+        let accept_state = Arc::clone(&state);
+        let task = tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        // Usually out of file descriptors; retrying at once
+                        // would only spin until connections close
+                        tracing::warn!("Mock server failed to accept a connection: {}", e);
+                        tokio::time::sleep(ACCEPT_BACKOFF).await;
+                        continue;
+                    }
+                };
+                let state = Arc::clone(&accept_state);
+                let config = Arc::clone(&config);
+                tokio::spawn(async move {
+                    if let Err(e) = serve_connection(stream, state, config).await {
+                        tracing::debug!("Mock connection closed: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self { addr, state, task })
+    }
+
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// API base URL to use as `DISCORD_API_BASE`
+    pub fn base_url(&self) -> String {
+        format!("http://{}/api/v10", self.addr)
+    }
+
+    /// URL of a webhook served by the mock
+    pub fn webhook_url(&self, webhook_id: &str, token: &str) -> String {
+        format!("http://{}/api/webhooks/{}/{}", self.addr, webhook_id, token)
+    }
+
+    /// All messages posted so far, in order
+    pub fn messages(&self) -> Vec<MockMessage> {
+        lock(&self.state).messages.clone()
+    }
+
+    /// All reactions currently present, in the order they were added
+    pub fn reactions(&self) -> Vec<MockReaction> {
+        lock(&self.state).reactions.clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn lock(state: &Mutex<State>) -> std::sync::MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+async fn serve_connection(
+    mut stream: TcpStream,
+    state: Arc<Mutex<State>>,
+    config: Arc<MockServerConfig>,
+) -> std::io::Result<()> {
+    let mut buffer = Vec::new();
+    while let Some(request) = read_request(&mut stream, &mut buffer).await? {
+        let response = handle(&request, &state, &config);
+        tracing::info!("{} {} -> {}", request.method, request.path, response.status);
+        let close = request.wants_close();
+        write_response(&mut stream, &response, close).await?;
+        if close {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Strip the `/api` or `/api/v{N}` prefix and split the path into segments
+fn route_segments(path: &str) -> Vec<&str> {
+    let mut segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if segments.first() == Some(&"api") {
+        segments.remove(0);
+        if segments.first().is_some_and(|s| {
+            s.len() > 1 && s.starts_with('v') && s[1..].chars().all(|c| c.is_ascii_digit())
+        }) {
+            segments.remove(0);
+        }
+    }
+    segments
+}
+
+fn handle(request: &Request, state: &Mutex<State>, config: &MockServerConfig) -> Response {
+    let segments = route_segments(&request.path);
+    let method = request.method.as_str();
+
+    if segments.first() == Some(&"_mock") {
+        return handle_inspection(method, &segments[1..], state);
+    }
+
+    let count = {
+        let mut state = lock(state);
+        state.request_count += 1;
+        state.request_count
+    };
+    if config
+        .rate_limit_every
+        .is_some_and(|n| n > 0 && count % n == 0)
+    {
+        let mut response = Response::json(
+            429,
+            &json!({
+                "message": "You are being rate limited.",
+                "retry_after": config.retry_after,
+                "global": false,
+                "code": 0,
+            }),
+        );
+        response.headers = vec![
+            (
+                "Retry-After".to_string(),
+                config.retry_after.ceil().to_string(),
+            ),
+            ("X-RateLimit-Remaining".to_string(), "0".to_string()),
+            (
+                "X-RateLimit-Reset-After".to_string(),
+                config.retry_after.to_string(),
+            ),
+        ];
+        return response;
+    }
+    if config.fail_every.is_some_and(|n| n > 0 && count % n == 0) {
+        let message = format!("{}: Injected failure", config.fail_status);
+        return Response::error(config.fail_status, 0, &message);
+    }
+
+    // Webhooks authenticate with the token in the URL
+    if let ["webhooks", webhook_id, _token] = segments.as_slice() {
+        return match method {
+            "POST" => post_webhook(request, webhook_id, state),
+            _ => method_not_allowed(),
+        };
+    }
+
+    let authorized = request
+        .headers
+        .get("authorization")
+        .is_some_and(|v| v.starts_with("Bot ") && v.len() > 4);
+    if !authorized {
+        return Response::error(401, 0, "401: Unauthorized");
+    }
+
+    match (method, segments.as_slice()) {
+        ("GET", ["users", "@me"]) => Response::json(
+            200,
+            &json!({ "id": MOCK_BOT_ID, "username": "discli-mock", "bot": true }),
+        ),
+        ("POST", ["users", "@me", "channels"]) => create_dm(request, state),
+        ("GET", ["gateway", "bot"]) => Response::json(
+            200,
+            &json!({
+                "url": "wss://gateway.discord.gg",
+                "shards": config.shards,
+                "session_start_limit": {
+                    "total": 1000,
+                    "remaining": 1000,
+                    "reset_after": 0,
+                    "max_concurrency": 1,
+                },
+            }),
+        ),
+        ("GET", ["channels", channel_id]) => get_channel(channel_id),
+        ("POST", ["channels", channel_id, "messages"]) => post_message(request, channel_id, state),
+        ("PUT", ["channels", channel_id, "messages", message_id, "reactions", emoji, "@me"]) => {
+            let mut state = lock(state);
+            let reaction = MockReaction {
+                channel_id: channel_id.to_string(),
+                message_id: message_id.to_string(),
+                emoji: emoji.to_string(),
+            };
+            if !state.reactions.contains(&reaction) {
+                state.reactions.push(reaction);
+            }
+            Response::no_content()
+        }
+        ("DELETE", ["channels", channel_id, "messages", message_id, "reactions", emoji, "@me"]) => {
+            lock(state).reactions.retain(|r| {
+                !(r.channel_id == *channel_id && r.message_id == *message_id && r.emoji == *emoji)
+            });
+            Response::no_content()
+        }
+        ("GET", ["guilds", guild_id]) if *guild_id == MOCK_GUILD_ID => get_guild(),
+        ("GET", ["guilds", guild_id, "members", _]) if *guild_id == MOCK_GUILD_ID => {
+            Response::json(200, &json!({ "roles": [] }))
+        }
+        ("GET", ["guilds", ..]) => Response::error(404, UNKNOWN_GUILD, "Unknown Guild"),
+        _ => Response::error(404, 0, "404: Not Found"),
+    }
+}
+
+/// `/_mock/messages` and `/_mock/reactions`: inspect or reset stored state
+fn handle_inspection(method: &str, segments: &[&str], state: &Mutex<State>) -> Response {
+    let mut state = lock(state);
+    match (method, segments) {
+        ("GET", ["messages"]) => Response::json(200, &json!(state.messages)),
+        ("DELETE", ["messages"]) => {
+            state.messages.clear();
+            state.reactions.clear();
+            Response::no_content()
+        }
+        ("GET", ["reactions"]) => Response::json(200, &json!(state.reactions)),
+        ("DELETE", ["reactions"]) => {
+            state.reactions.clear();
+            Response::no_content()
+        }
+        _ => Response::error(404, 0, "404: Not Found"),
+    }
+}
+
+fn method_not_allowed() -> Response {
+    Response::error(405, 0, "405: Method Not Allowed")
+}
+
+/// Every numeric channel ID exists, as a text channel in the mock guild
+fn get_channel(channel_id: &str) -> Response {
+    if !is_snowflake(channel_id) {
+        return Response::error(404, UNKNOWN_CHANNEL, "Unknown Channel");
+    }
+    Response::json(
+        200,
+        &json!({
+            "id": channel_id,
+            "type": 0,
+            "guild_id": MOCK_GUILD_ID,
+            "name": "mock-channel",
+            "permission_overwrites": [],
+        }),
+    )
+}
+
+fn get_guild() -> Response {
+    let everyone = Permissions::VIEW_CHANNEL
+        | Permissions::SEND_MESSAGES
+        | Permissions::ATTACH_FILES
+        | Permissions::EMBED_LINKS
+        | Permissions::READ_MESSAGE_HISTORY
+        | Permissions::ADD_REACTIONS;
+    Response::json(
+        200,
+        &json!({
+            "id": MOCK_GUILD_ID,
+            "name": "Mock Guild",
+            "owner_id": "100000000000000003",
+            "roles": [{
+                "id": MOCK_GUILD_ID,
+                "name": "@everyone",
+                "permissions": everyone.bits().to_string(),
+            }],
+        }),
+    )
+}
+
+fn create_dm(request: &Request, state: &Mutex<State>) -> Response {
+    let body: Value = serde_json::from_slice(&request.body).unwrap_or(Value::Null);
+    let Some(recipient_id) = body["recipient_id"].as_str() else {
+        return form_error(
+            "recipient_id",
+            "BASE_TYPE_REQUIRED",
+            "This field is required",
+        );
+    };
+    let channel_id = lock(state).next_id();
+    Response::json(
+        200,
+        &json!({
+            "id": channel_id,
+            "type": 1,
+            "recipients": [{ "id": recipient_id, "username": "mock-user" }],
+        }),
+    )
+}
+
+fn post_message(request: &Request, channel_id: &str, state: &Mutex<State>) -> Response {
+    if !is_snowflake(channel_id) {
+        return Response::error(404, UNKNOWN_CHANNEL, "Unknown Channel");
+    }
+    let mut message = match decode_message(request) {
+        Ok(message) => message,
+        Err(response) => return response,
+    };
+    message.channel_id = Some(channel_id.to_string());
+
+    let mut state = lock(state);
+    message.id = state.next_id();
+    state.messages.push(message.clone());
+    Response::json(200, &message_json(&message))
+}
+
+fn post_webhook(request: &Request, webhook_id: &str, state: &Mutex<State>) -> Response {
+    let mut message = match decode_message(request) {
+        Ok(message) => message,
+        Err(response) => return response,
+    };
+    message.webhook_id = Some(webhook_id.to_string());
+
+    let mut state = lock(state);
+    message.id = state.next_id();
+    state.messages.push(message);
+    Response::no_content()
+}
+
+/// Decode a JSON or multipart message body, validating it like Discord does
+fn decode_message(request: &Request) -> std::result::Result<MockMessage, Response> {
+    let content_type = request
+        .headers
+        .get("content-type")
+        .map(String::as_str)
+        .unwrap_or_default();
+
+    let mut attachments = Vec::new();
+    let payload: Value = if content_type.starts_with("multipart/form-data") {
+        let mut payload = json!({});
+        for part in parse_multipart(content_type, &request.body) {
+            match part.filename {
+                Some(filename) => attachments.push(MockAttachment {
+                    filename,
+                    size: part.data.len(),
+                }),
+                None if part.name == "payload_json" => {
+                    payload = serde_json::from_slice(&part.data).unwrap_or(Value::Null);
+                }
+                None => {}
+            }
+        }
+        payload
+    } else {
+        serde_json::from_slice(&request.body).unwrap_or(Value::Null)
+    };
+
+    if !payload.is_object() {
+        return Err(Response::error(
+            400,
+            50109,
+            "The request body contains invalid JSON.",
+        ));
+    }
+
+    let content = payload["content"].as_str().map(str::to_string);
+    let embeds = payload["embeds"].as_array().cloned().unwrap_or_default();
+
+    if content
+        .as_deref()
+        .is_some_and(|c| c.chars().count() > MAX_CONTENT_LENGTH)
+    {
+        return Err(form_error(
+            "content",
+            "BASE_TYPE_MAX_LENGTH",
+            "Must be 2000 or fewer in length.",
+        ));
+    }
+    if content.as_deref().unwrap_or_default().is_empty()
+        && embeds.is_empty()
+        && attachments.is_empty()
+    {
+        return Err(Response::error(
+            400,
+            EMPTY_MESSAGE,
+            "Cannot send an empty message",
+        ));
+    }
+
+    Ok(MockMessage {
+        id: String::new(),
+        channel_id: None,
+        webhook_id: None,
+        content,
+        embeds,
+        attachments,
+    })
+}
+
+/// The message object Discord returns after a successful post
+fn message_json(message: &MockMessage) -> Value {
+    json!({
+        "id": message.id,
+        "channel_id": message.channel_id,
+        "content": message.content.clone().unwrap_or_default(),
+        "embeds": message.embeds,
+        "attachments": message.attachments,
+        "author": { "id": MOCK_BOT_ID, "username": "discli-mock", "bot": true },
+        "type": 0,
+    })
+}
+
+/// A `50035 Invalid Form Body` error for a single field
+fn form_error(field: &str, code: &str, message: &str) -> Response {
+    Response::json(
+        400,
+        &json!({
+            "message": "Invalid Form Body",
+            "code": INVALID_FORM_BODY,
+            "errors": { field: { "_errors": [{ "code": code, "message": message }] } },
+        }),
+    )
+}
+
+fn is_snowflake(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::types::{DiscordMessage, Embed};
+    use crate::discord::DiscordClient;
+    use crate::error::{DiscliError, ErrorClass};
+    use crate::message::MessageBuilder;
+    use tempfile::TempDir;
+
+    async fn start(config: MockServerConfig) -> (MockServer, DiscordClient) {
+        let server = MockServer::start("127.0.0.1:0", config).await.unwrap();
+        let client = DiscordClient::with_base_url("token".to_string(), server.base_url());
+        (server, client)
+    }
+
+    #[test]
+    fn test_route_segments_strip_api_prefix() {
+        assert_eq!(route_segments("/api/v10/users/@me"), vec!["users", "@me"]);
+        assert_eq!(
+            route_segments("/api/webhooks/1/abc"),
+            vec!["webhooks", "1", "abc"]
+        );
+        assert_eq!(route_segments("/channels/1"), vec!["channels", "1"]);
+    }
+
+    #[tokio::test]
+    async fn test_stores_simple_embed_and_attachment_messages() {
+        let (server, client) = start(MockServerConfig::default()).await;
+        let temp_dir = TempDir::new().unwrap();
+        let image = temp_dir.path().join("chart.png");
+        std::fs::write(&image, b"not really a png").unwrap();
+
+        client
+            .send_message(
+                "10",
+                &DiscordMessage::Simple {
+                    content: "hello".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        client
+            .send_message(
+                "10",
+                &DiscordMessage::WithEmbeds {
+                    content: None,
+                    embeds: vec![Embed {
+                        title: Some("Deploy".to_string()),
+                        ..Default::default()
+                    }],
+                },
+            )
+            .await
+            .unwrap();
+        client
+            .send_message(
+                "11",
+                &MessageBuilder::new()
+                    .content("see chart")
+                    .add_attachment(&image)
+                    .unwrap()
+                    .build(),
+            )
+            .await
+            .unwrap();
+
+        let messages = server.messages();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].content.as_deref(), Some("hello"));
+        assert_eq!(messages[1].embeds[0]["title"], "Deploy");
+        assert_eq!(messages[2].channel_id.as_deref(), Some("11"));
+        assert_eq!(messages[2].content.as_deref(), Some("see chart"));
+        assert_eq!(
+            messages[2].attachments,
+            vec![MockAttachment {
+                filename: "chart.png".to_string(),
+                size: 16,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_and_inspection_endpoints() {
+        let (server, client) = start(MockServerConfig::default()).await;
+        let message = DiscordMessage::Simple {
+            content: "via webhook".to_string(),
+        };
+
+        client
+            .execute_webhook(&server.webhook_url("42", "secret"), &message)
+            .await
+            .unwrap();
+
+        let http = reqwest::Client::new();
+        let url = format!("http://{}/_mock/messages", server.local_addr());
+        let listed: Vec<MockMessage> = http.get(&url).send().await.unwrap().json().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].webhook_id.as_deref(), Some("42"));
+
+        http.delete(&url).send().await.unwrap();
+        assert!(server.messages().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reactions() {
+        let (server, _client) = start(MockServerConfig::default()).await;
+        let http = reqwest::Client::new();
+        let url = format!(
+            "{}/channels/10/messages/20/reactions/%F0%9F%91%8D/@me",
+            server.base_url()
+        );
+
+        let status = http
+            .put(&url)
+            .header("Authorization", "Bot token")
+            .send()
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, 204);
+        assert_eq!(server.reactions().len(), 1);
+
+        http.delete(&url)
+            .header("Authorization", "Bot token")
+            .send()
+            .await
+            .unwrap();
+        assert!(server.reactions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_gateway_bot_reports_configured_shards() {
+        let config = MockServerConfig {
+            shards: 4,
+            ..Default::default()
+        };
+        let (_server, client) = start(config).await;
+
+        let info = client.get_gateway_bot().await.unwrap();
+
+        assert_eq!(info.shards, 4);
+        assert_eq!(info.session_start_limit.max_concurrency, 1);
+    }
+
+    #[tokio::test]
+    async fn test_send_dm_opens_dm_channel() {
+        let (server, client) = start(MockServerConfig::default()).await;
+
+        let message = DiscordMessage::Simple {
+            content: "psst".to_string(),
+        };
+        client.send_dm("42", &message).await.unwrap();
+
+        let messages = server.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content.as_deref(), Some("psst"));
+    }
+
+    #[tokio::test]
+    async fn test_client_add_reaction() {
+        let (server, client) = start(MockServerConfig::default()).await;
+
+        client.add_reaction("10", "20", "👍").await.unwrap();
+
+        let reactions = server.reactions();
+        assert_eq!(reactions.len(), 1);
+        assert_eq!(reactions[0].message_id, "20");
+        assert_eq!(reactions[0].emoji, "%F0%9F%91%8D");
+    }
+
+    #[tokio::test]
+    async fn test_injected_rate_limit_and_failure() {
+        let config = MockServerConfig {
+            rate_limit_every: Some(2),
+            fail_every: Some(3),
+            fail_status: 503,
+            ..Default::default()
+        };
+        let (server, client) = start(config).await;
+        let message = DiscordMessage::Simple {
+            content: "ping".to_string(),
+        };
+
+        assert!(client.send_message("10", &message).await.is_ok());
+
+        let err = client.send_message("10", &message).await.unwrap_err();
+        assert_eq!(err.class(), ErrorClass::RateLimit);
+        match err {
+            DiscliError::DiscordApi(api) => assert_eq!(api.retry_after, Some(1.0)),
+            other => panic!("unexpected error: {other:?}"),
+        }
+
+        let err = client.send_message("10", &message).await.unwrap_err();
+        assert_eq!(err.class(), ErrorClass::Network);
+        assert_eq!(server.messages().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_validation_errors_match_discord() {
+        let (_server, client) = start(MockServerConfig::default()).await;
+
+        let err = client
+            .send_message(
+                "general",
+                &DiscordMessage::Simple {
+                    content: "hi".to_string(),
+                },
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.class(), ErrorClass::Config);
+
+        let err = client
+            .send_message(
+                "10",
+                &DiscordMessage::Simple {
+                    content: "x".repeat(2001),
+                },
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.class(), ErrorClass::Validation);
+    }
+}
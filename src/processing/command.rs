@@ -30,28 +30,40 @@ impl CommandProcessor {
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
-            .map_err(|e| DiscliError::Io(e))?;
+            .map_err(DiscliError::Io)?;
         
-        // Write prompt to stdin
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin.write_all(prompt.as_bytes()).await
-                .map_err(|e| DiscliError::Io(e))?;
-        }
+        // Write prompt to stdin while the output is read, so neither side
+        // fills its pipe waiting on the other, and both count against the
+        // timeout. A command that exits without reading the prompt closes
+        // the pipe; its exit status says whether that's a failure.
+        let stdin = child.stdin.take();
+        let write_prompt = async move {
+            if let Some(mut stdin) = stdin {
+                match stdin.write_all(prompt.as_bytes()).await {
+                    Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e),
+                    _ => {}
+                }
+            }
+            Ok(())
+        };
+        let run = async {
+            let (written, output) = tokio::join!(write_prompt, child.wait_with_output());
+            written.and(output)
+        };
         
         // Set timeout
         let timeout = tokio::time::Duration::from_secs(self.timeout_secs);
         
-        let output = tokio::time::timeout(timeout, child.wait_with_output())
+        let output = tokio::time::timeout(timeout, run)
             .await
-            .map_err(|_| DiscliError::Config("Command timed out".into()))?
-            .map_err(|e| DiscliError::Io(e))?;
+            .map_err(|_| DiscliError::Processor("Command timed out".into()))?
+            .map_err(DiscliError::Io)?;
         
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(DiscliError::Config(format!(
-                "Command failed: {}",
-                stderr
-            )));
+            return Err(DiscliError::CommandFailed {
+                status: output.status.to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            });
         }
         
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
@@ -78,4 +90,22 @@ mod tests {
         // This might fail on CI, so we'll just check it runs
         println!("Result: {:?}", result);
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_that_ignores_a_large_prompt() {
+        let prompt = "x".repeat(1 << 20);
+        let cmd = |script: &str| vec!["sh".to_string(), "-c".to_string(), script.to_string()];
+
+        let processor = CommandProcessor::new(5);
+        let output = processor.execute(&cmd("echo done"), &prompt).await.unwrap();
+        assert_eq!(output, "done\n");
+
+        // Still running with the pipe full: the write counts against the timeout
+        let processor = CommandProcessor::new(1);
+        let started = std::time::Instant::now();
+        let error = processor.execute(&cmd("sleep 10"), &prompt).await.unwrap_err();
+        assert!(matches!(error, DiscliError::Processor(_)), "{}", error);
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
 }
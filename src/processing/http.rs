@@ -39,14 +39,14 @@ impl HttpProcessor {
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
-            return Err(DiscliError::DiscordApi(format!(
+            return Err(DiscliError::Processor(format!(
                 "HTTP webhook failed: {} - {}",
                 status, text
             )));
         }
         
         let text = response.text().await
-            .map_err(|e| DiscliError::Network(e))?;
+            .map_err(DiscliError::Network)?;
         
         Ok(text)
     }
@@ -5,6 +5,7 @@
 use crate::error::{DiscliError, Result};
 use std::path::{Path, PathBuf};
 use std::collections::HashMap;
+use std::time::SystemTime;
 use regex::Regex;
 
 /// A loaded prompt template
@@ -22,7 +23,7 @@ impl PromptTemplate {
     /// Load a prompt template from a file
     pub fn load(path: &Path) -> Result<Self> {
         let content = std::fs::read_to_string(path)
-            .map_err(|e| DiscliError::Io(e))?;
+            .map_err(DiscliError::Io)?;
         
         let name = path
             .file_stem()
@@ -49,12 +50,20 @@ fn extract_variables(template: &str) -> Vec<String> {
     vars
 }
 
+/// Modification time and size of a file, used to notice edits
+pub(crate) type FileStamp = (Option<SystemTime>, u64);
+
+pub(crate) fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok(), metadata.len()))
+}
+
 /// Loader for prompt templates
 pub struct PromptLoader {
     /// Base directory for prompts
     prompts_dir: PathBuf,
-    /// Cached templates
-    cache: HashMap<PathBuf, PromptTemplate>,
+    /// Cached templates, with the stamp of the file they were read from
+    cache: HashMap<PathBuf, (PromptTemplate, Option<FileStamp>)>,
 }
 
 impl PromptLoader {
@@ -68,7 +77,8 @@ impl PromptLoader {
     
     /// Load a prompt template
     /// 
-    /// Path can be relative (to prompts_dir) or absolute
+    /// Path can be relative (to prompts_dir) or absolute. Templates are
+    /// cached until their file's modification time or size changes.
     pub fn load(&mut self, path: &Path) -> Result<PromptTemplate> {
         // Resolve absolute path
         let absolute_path: PathBuf = if path.is_absolute() {
@@ -77,16 +87,20 @@ impl PromptLoader {
             self.prompts_dir.join(path)
         };
         
-        // Check cache
-        if let Some(cached) = self.cache.get(&absolute_path) {
-            return Ok(cached.clone());
+        // Check cache, dropping the entry if the file was edited
+        let stamp = file_stamp(&absolute_path);
+        if let Some((cached, cached_stamp)) = self.cache.get(&absolute_path) {
+            if *cached_stamp == stamp {
+                return Ok(cached.clone());
+            }
+            self.cache.remove(&absolute_path);
         }
         
         // Load template
         let template = PromptTemplate::load(&absolute_path)?;
         
         // Cache it
-        self.cache.insert(absolute_path, template.clone());
+        self.cache.insert(absolute_path, (template.clone(), stamp));
         
         Ok(template)
     }
@@ -150,4 +164,20 @@ mod tests {
         assert_eq!(template.content, "Hello {{author_name}}!");
         assert_eq!(template.variables, vec!["author_name"]);
     }
+    
+    #[test]
+    fn test_loader_reloads_edited_template() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("greeting.txt");
+        std::fs::write(&file_path, "Hello").unwrap();
+
+        let mut loader = PromptLoader::new(temp_dir.path().to_path_buf());
+        assert_eq!(loader.load(Path::new("greeting.txt")).unwrap().content, "Hello");
+
+        // A different size is noticed even within the mtime resolution
+        std::fs::write(&file_path, "Hello, {{author_name}}").unwrap();
+        let template = loader.load(Path::new("greeting.txt")).unwrap();
+        assert_eq!(template.content, "Hello, {{author_name}}");
+        assert_eq!(template.variables, vec!["author_name"]);
+    }
 }
@@ -4,6 +4,26 @@
 //! - Loading prompt templates from files
 //! - Variable substitution with message data
 //! - Template validation
+//!
+//! # Examples
+//!
+//! ```
+//! use discli::prompt::{substitute_variables, MessageVariables};
+//!
+//! let vars = MessageVariables {
+//!     content: "!echo hi".to_string(),
+//!     author_id: "1".to_string(),
+//!     author_name: "kyle".to_string(),
+//!     channel_id: "2".to_string(),
+//!     message_id: "3".to_string(),
+//!     timestamp: "2024-01-01T00:00:00Z".to_string(),
+//!     attachments: vec![],
+//!     embed_count: 0,
+//! };
+//!
+//! let prompt = substitute_variables("{{author_name}} said {{content}}", &vars);
+//! assert_eq!(prompt, "kyle said !echo hi");
+//! ```
 
 pub mod loader;
 pub mod variables;
@@ -11,5 +31,5 @@ pub mod registry;
 
 pub use loader::PromptLoader;
 pub use loader::PromptTemplate;
-pub use variables::{MessageVariables, substitute_variables};
+pub use variables::{EventVariables, MessageVariables, TemplateVariables, substitute_variables};
 pub use registry::PromptRegistry;
@@ -3,9 +3,9 @@
 //! Provides a centralized way to access and render prompt templates.
 
 use super::loader::PromptLoader;
-use super::variables::MessageVariables;
+use super::variables::TemplateVariables;
 use crate::error::Result;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Registry of available prompt templates
 pub struct PromptRegistry {
@@ -21,7 +21,7 @@ impl PromptRegistry {
     }
     
     /// Get a prompt template by path
-    pub fn get(&mut self, path: &PathBuf) -> Result<super::loader::PromptTemplate> {
+    pub fn get(&mut self, path: &Path) -> Result<super::loader::PromptTemplate> {
         self.loader.load(path)
     }
     
@@ -31,10 +31,10 @@ impl PromptRegistry {
     }
     
     /// Load and substitute a prompt with variables
-    pub fn render(
+    pub fn render<V: TemplateVariables + ?Sized>(
         &mut self, 
-        path: &PathBuf, 
-        vars: &MessageVariables
+        path: &Path, 
+        vars: &V
     ) -> Result<String> {
         let template = self.get(path)?;
         Ok(super::variables::substitute_variables(&template.content, vars))
@@ -1,7 +1,8 @@
 //! Variable extraction and substitution for prompt templates
 //!
-//! Provides MessageVariables for extracting data from Discord messages
-//! and substitute_variables for template variable replacement.
+//! Provides MessageVariables for extracting data from Discord messages,
+//! EventVariables for other gateway events, and substitute_variables for
+//! template variable replacement.
 
 use regex::Regex;
 use std::collections::HashMap;
@@ -63,8 +64,59 @@ impl MessageVariables {
     }
 }
 
+/// Values that can fill `{{variable}}` placeholders
+pub trait TemplateVariables {
+    /// Get all variables as a map for substitution
+    fn to_map(&self) -> HashMap<String, String>;
+}
+
+impl TemplateVariables for MessageVariables {
+    fn to_map(&self) -> HashMap<String, String> {
+        MessageVariables::to_map(self)
+    }
+}
+
+/// Variables for any hook event, by name
+///
+/// Built by [`IncomingEvent::variables`](crate::hooks::event::IncomingEvent::variables).
+#[derive(Debug, Clone, Default)]
+pub struct EventVariables {
+    vars: HashMap<String, String>,
+}
+
+impl EventVariables {
+    /// Create an empty set of variables
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a variable, replacing any previous value
+    pub fn insert(&mut self, name: &str, value: String) {
+        self.vars.insert(name.to_string(), value);
+    }
+
+    /// Get a variable's value
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(String::as_str)
+    }
+}
+
+impl From<&MessageVariables> for EventVariables {
+    fn from(vars: &MessageVariables) -> Self {
+        Self {
+            vars: vars.to_map(),
+        }
+    }
+}
+
+impl TemplateVariables for EventVariables {
+    fn to_map(&self) -> HashMap<String, String> {
+        self.vars.clone()
+    }
+}
+
 /// Substitute {{variable}} placeholders in template
-pub fn substitute_variables(template: &str, vars: &MessageVariables) -> String {
+pub fn substitute_variables<V: TemplateVariables + ?Sized>(template: &str, vars: &V) -> String {
     let var_map = vars.to_map();
     let re = Regex::new(r"\{\{(\w+)\}\}").unwrap();
     
@@ -0,0 +1,47 @@
+//! Sink that posts through the Discord REST API
+
+use super::MessageSink;
+use crate::config::Config;
+use crate::discord::types::DiscordMessage;
+use crate::discord::DiscordClient;
+use crate::error::Result;
+use async_trait::async_trait;
+
+/// Sends messages to Discord using the bot token
+pub struct DiscordSink {
+    client: DiscordClient,
+}
+
+impl DiscordSink {
+    /// Create a sink around an existing client
+    pub fn new(client: DiscordClient) -> Self {
+        Self { client }
+    }
+
+    /// Create a sink using the token and API base URL from the configuration
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(DiscordClient::with_base_url(
+            config.discord_token.clone(),
+            config.api_base.clone(),
+        ))
+    }
+}
+
+#[async_trait]
+impl MessageSink for DiscordSink {
+    async fn send_message(&self, channel_id: &str, message: &DiscordMessage) -> Result<()> {
+        self.client.send_message(channel_id, message).await
+    }
+
+    async fn send_webhook(&self, url: &str, message: &DiscordMessage) -> Result<()> {
+        self.client.execute_webhook(url, message).await
+    }
+
+    async fn send_dm(&self, user_id: &str, message: &DiscordMessage) -> Result<()> {
+        self.client.send_dm(user_id, message).await
+    }
+
+    async fn add_reaction(&self, channel_id: &str, message_id: &str, emoji: &str) -> Result<()> {
+        self.client.add_reaction(channel_id, message_id, emoji).await
+    }
+}
@@ -0,0 +1,98 @@
+//! Sink that records messages to a JSONL file
+
+use super::{MessageSink, SinkRecord, SinkTarget};
+use crate::discord::types::DiscordMessage;
+use crate::error::Result;
+use async_trait::async_trait;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Appends one JSON [`SinkRecord`] per message to a file
+pub struct FileSink {
+    file: Mutex<File>,
+}
+
+impl FileSink {
+    /// Open (or create) the file in append mode
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    fn append(&self, record: &SinkRecord) -> Result<()> {
+        let line = serde_json::to_string(record).map_err(std::io::Error::other)?;
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(file, "{}", line)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MessageSink for FileSink {
+    async fn send_message(&self, channel_id: &str, message: &DiscordMessage) -> Result<()> {
+        let target = SinkTarget::Channel {
+            channel_id: channel_id.to_string(),
+        };
+        self.append(&SinkRecord::new(target, message))
+    }
+
+    async fn send_webhook(&self, url: &str, message: &DiscordMessage) -> Result<()> {
+        let target = SinkTarget::Webhook {
+            url: url.to_string(),
+        };
+        self.append(&SinkRecord::new(target, message))
+    }
+
+    async fn send_dm(&self, user_id: &str, message: &DiscordMessage) -> Result<()> {
+        let target = SinkTarget::User {
+            user_id: user_id.to_string(),
+        };
+        self.append(&SinkRecord::new(target, message))
+    }
+
+    async fn add_reaction(&self, channel_id: &str, message_id: &str, emoji: &str) -> Result<()> {
+        self.append(&SinkRecord::reaction(channel_id, message_id, emoji))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_file_sink_appends_jsonl() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("messages.jsonl");
+        let sink = FileSink::open(&path).unwrap();
+
+        let message = DiscordMessage::Simple {
+            content: "first".to_string(),
+        };
+        sink.send_message("1", &message).await.unwrap();
+        sink.send_webhook("https://example.com/hook", &message).await.unwrap();
+
+        let lines: Vec<SinkRecord> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0].target,
+            SinkTarget::Channel {
+                channel_id: "1".to_string()
+            }
+        );
+        assert_eq!(lines[1].content.as_deref(), Some("first"));
+    }
+}
@@ -0,0 +1,64 @@
+//! Sink that keeps messages in memory
+
+use super::{MessageSink, SinkRecord, SinkTarget};
+use crate::discord::types::DiscordMessage;
+use crate::error::Result;
+use async_trait::async_trait;
+use std::sync::Mutex;
+
+/// Collects messages in memory so tests can assert on them
+#[derive(Default)]
+pub struct MemorySink {
+    records: Mutex<Vec<SinkRecord>>,
+}
+
+impl MemorySink {
+    /// Create an empty sink
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All messages recorded so far, in order
+    pub fn records(&self) -> Vec<SinkRecord> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn push(&self, record: SinkRecord) {
+        self.records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(record);
+    }
+}
+
+#[async_trait]
+impl MessageSink for MemorySink {
+    async fn send_message(&self, channel_id: &str, message: &DiscordMessage) -> Result<()> {
+        let target = SinkTarget::Channel {
+            channel_id: channel_id.to_string(),
+        };
+        self.push(SinkRecord::new(target, message));
+        Ok(())
+    }
+
+    async fn send_webhook(&self, url: &str, message: &DiscordMessage) -> Result<()> {
+        let target = SinkTarget::Webhook {
+            url: url.to_string(),
+        };
+        self.push(SinkRecord::new(target, message));
+        Ok(())
+    }
+
+    async fn send_dm(&self, user_id: &str, message: &DiscordMessage) -> Result<()> {
+        let target = SinkTarget::User {
+            user_id: user_id.to_string(),
+        };
+        self.push(SinkRecord::new(target, message));
+        Ok(())
+    }
+
+    async fn add_reaction(&self, channel_id: &str, message_id: &str, emoji: &str) -> Result<()> {
+        self.push(SinkRecord::reaction(channel_id, message_id, emoji));
+        Ok(())
+    }
+}
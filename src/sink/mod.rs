@@ -0,0 +1,229 @@
+//! Output sinks for outgoing messages
+//!
+//! Everything discli posts goes through a [`MessageSink`]. The default sink
+//! talks to the Discord REST API; the others redirect output to a single
+//! webhook, stdout, a JSONL file or memory, so commands and hooks can run
+//! end to end without Discord and tests can assert on what would be posted.
+
+pub mod discord;
+pub mod file;
+pub mod memory;
+pub mod stdout;
+pub mod webhook;
+
+pub use discord::DiscordSink;
+pub use file::FileSink;
+pub use memory::MemorySink;
+pub use stdout::StdoutSink;
+pub use webhook::WebhookSink;
+
+use crate::config::Config;
+use crate::discord::types::DiscordMessage;
+use crate::error::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Destination for outgoing messages
+#[async_trait]
+pub trait MessageSink: Send + Sync {
+    /// Send a message to a channel
+    async fn send_message(&self, channel_id: &str, message: &DiscordMessage) -> Result<()>;
+
+    /// Post a message through a webhook URL
+    async fn send_webhook(&self, url: &str, message: &DiscordMessage) -> Result<()>;
+
+    /// Send a direct message to a user
+    async fn send_dm(&self, user_id: &str, message: &DiscordMessage) -> Result<()>;
+
+    /// React to a message with an emoji (a character, or `name:id` for a
+    /// custom emoji)
+    async fn add_reaction(&self, channel_id: &str, message_id: &str, emoji: &str) -> Result<()>;
+}
+
+/// Where an outgoing message was addressed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkTarget {
+    /// A channel, by ID
+    Channel { channel_id: String },
+    /// A webhook, by URL
+    Webhook { url: String },
+    /// A user's direct messages, by user ID
+    User { user_id: String },
+    /// A reaction on a message; the record's content is the emoji
+    Reaction {
+        channel_id: String,
+        message_id: String,
+    },
+}
+
+/// A sink-independent record of an outgoing message
+///
+/// This is what the stdout (JSON), file and memory sinks emit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SinkRecord {
+    /// Where the message was addressed
+    pub target: SinkTarget,
+    /// Text content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Embeds, as they would be sent to Discord
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub embeds: Vec<serde_json::Value>,
+    /// Attachment filenames
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<String>,
+}
+
+impl SinkRecord {
+    /// Create a record for a message sent to `target`
+    pub fn new(target: SinkTarget, message: &DiscordMessage) -> Self {
+        let (content, embeds, attachments) = match message {
+            DiscordMessage::Simple { content } => (Some(content.clone()), Vec::new(), Vec::new()),
+            DiscordMessage::WithAttachments {
+                content,
+                attachments,
+            } => (
+                content.clone(),
+                Vec::new(),
+                attachments.iter().map(|a| a.filename.clone()).collect(),
+            ),
+            DiscordMessage::WithEmbeds { content, embeds } => (
+                content.clone(),
+                embeds
+                    .iter()
+                    .map(|e| serde_json::to_value(e).unwrap_or_default())
+                    .collect(),
+                Vec::new(),
+            ),
+        };
+
+        Self {
+            target,
+            content,
+            embeds,
+            attachments,
+        }
+    }
+
+    /// Create a record for a reaction
+    pub fn reaction(channel_id: &str, message_id: &str, emoji: &str) -> Self {
+        Self {
+            target: SinkTarget::Reaction {
+                channel_id: channel_id.to_string(),
+                message_id: message_id.to_string(),
+            },
+            content: Some(emoji.to_string()),
+            embeds: Vec::new(),
+            attachments: Vec::new(),
+        }
+    }
+}
+
+/// Sink selection, as given to `--sink`
+///
+/// Accepted forms: `discord`, `webhook:<url>`, `stdout`, `stdout:json`
+/// and `file:<path>`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SinkSpec {
+    /// Discord REST API (the default)
+    #[default]
+    Discord,
+    /// Redirect everything to one Discord webhook
+    Webhook(String),
+    /// Pretty-print messages to stdout
+    Stdout,
+    /// Print one JSON record per message to stdout
+    StdoutJson,
+    /// Append one JSON record per message to a file
+    File(PathBuf),
+}
+
+impl FromStr for SinkSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (kind, arg) = match s.split_once(':') {
+            Some((kind, arg)) => (kind, Some(arg)),
+            None => (s, None),
+        };
+
+        match (kind, arg) {
+            ("discord", None) => Ok(SinkSpec::Discord),
+            ("stdout", None) | ("stdout", Some("pretty")) => Ok(SinkSpec::Stdout),
+            ("stdout", Some("json")) | ("json", None) => Ok(SinkSpec::StdoutJson),
+            ("webhook", Some(url)) if !url.is_empty() => Ok(SinkSpec::Webhook(url.to_string())),
+            ("file", Some(path)) if !path.is_empty() => Ok(SinkSpec::File(PathBuf::from(path))),
+            _ => Err(format!(
+                "invalid sink '{}' (expected discord, webhook:<url>, stdout, stdout:json or file:<path>)",
+                s
+            )),
+        }
+    }
+}
+
+impl SinkSpec {
+    /// Build the sink described by this spec
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file sink cannot be opened
+    pub fn build(&self, config: &Config) -> Result<Arc<dyn MessageSink>> {
+        let sink: Arc<dyn MessageSink> = match self {
+            SinkSpec::Discord => Arc::new(DiscordSink::from_config(config)),
+            SinkSpec::Webhook(url) => Arc::new(WebhookSink::new(url.clone())),
+            SinkSpec::Stdout => Arc::new(StdoutSink::new(false)),
+            SinkSpec::StdoutJson => Arc::new(StdoutSink::new(true)),
+            SinkSpec::File(path) => Arc::new(FileSink::open(path)?),
+        };
+        Ok(sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::types::Embed;
+
+    #[test]
+    fn test_parse_sink_spec() {
+        assert_eq!("discord".parse::<SinkSpec>().unwrap(), SinkSpec::Discord);
+        assert_eq!("stdout".parse::<SinkSpec>().unwrap(), SinkSpec::Stdout);
+        assert_eq!("stdout:json".parse::<SinkSpec>().unwrap(), SinkSpec::StdoutJson);
+        assert_eq!(
+            "webhook:https://discord.com/api/webhooks/1/abc".parse::<SinkSpec>().unwrap(),
+            SinkSpec::Webhook("https://discord.com/api/webhooks/1/abc".to_string())
+        );
+        assert_eq!(
+            "file:out/messages.jsonl".parse::<SinkSpec>().unwrap(),
+            SinkSpec::File(PathBuf::from("out/messages.jsonl"))
+        );
+        assert!("webhook".parse::<SinkSpec>().is_err());
+        assert!("carrier-pigeon".parse::<SinkSpec>().is_err());
+    }
+
+    #[test]
+    fn test_record_from_embed_message() {
+        let message = DiscordMessage::WithEmbeds {
+            content: Some("hi".to_string()),
+            embeds: vec![Embed {
+                title: Some("Title".to_string()),
+                ..Default::default()
+            }],
+        };
+        let record = SinkRecord::new(
+            SinkTarget::Channel {
+                channel_id: "1".to_string(),
+            },
+            &message,
+        );
+
+        assert_eq!(record.content.as_deref(), Some("hi"));
+        assert_eq!(record.embeds[0]["title"], "Title");
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains(r#""target":{"type":"channel","channel_id":"1"}"#));
+    }
+}
@@ -0,0 +1,84 @@
+//! Sink that prints messages to stdout
+
+use super::{MessageSink, SinkRecord, SinkTarget};
+use crate::discord::types::DiscordMessage;
+use crate::error::Result;
+use async_trait::async_trait;
+
+/// Prints messages instead of sending them
+pub struct StdoutSink {
+    json: bool,
+}
+
+impl StdoutSink {
+    /// Create a stdout sink
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - Print one JSON record per line instead of a readable summary
+    pub fn new(json: bool) -> Self {
+        Self { json }
+    }
+
+    fn print(&self, record: &SinkRecord) -> Result<()> {
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string(record).map_err(std::io::Error::other)?
+            );
+            return Ok(());
+        }
+
+        match &record.target {
+            SinkTarget::Channel { channel_id } => println!("── message → channel {}", channel_id),
+            SinkTarget::Webhook { url } => println!("── message → webhook {}", url),
+            SinkTarget::User { user_id } => println!("── message → DM user {}", user_id),
+            SinkTarget::Reaction {
+                channel_id,
+                message_id,
+            } => println!("── reaction → message {} in channel {}", message_id, channel_id),
+        }
+        if let Some(content) = &record.content {
+            println!("{}", content);
+        }
+        for embed in &record.embeds {
+            let title = embed["title"].as_str().unwrap_or("(untitled)");
+            println!("[embed] {}", title);
+            if let Some(description) = embed["description"].as_str() {
+                println!("        {}", description);
+            }
+        }
+        for attachment in &record.attachments {
+            println!("[attachment] {}", attachment);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MessageSink for StdoutSink {
+    async fn send_message(&self, channel_id: &str, message: &DiscordMessage) -> Result<()> {
+        let target = SinkTarget::Channel {
+            channel_id: channel_id.to_string(),
+        };
+        self.print(&SinkRecord::new(target, message))
+    }
+
+    async fn send_webhook(&self, url: &str, message: &DiscordMessage) -> Result<()> {
+        let target = SinkTarget::Webhook {
+            url: url.to_string(),
+        };
+        self.print(&SinkRecord::new(target, message))
+    }
+
+    async fn send_dm(&self, user_id: &str, message: &DiscordMessage) -> Result<()> {
+        let target = SinkTarget::User {
+            user_id: user_id.to_string(),
+        };
+        self.print(&SinkRecord::new(target, message))
+    }
+
+    async fn add_reaction(&self, channel_id: &str, message_id: &str, emoji: &str) -> Result<()> {
+        self.print(&SinkRecord::reaction(channel_id, message_id, emoji))
+    }
+}
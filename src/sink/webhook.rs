@@ -0,0 +1,47 @@
+//! Sink that redirects every message to a single Discord webhook
+
+use super::MessageSink;
+use crate::discord::api::execute_webhook;
+use crate::discord::types::DiscordMessage;
+use crate::error::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+
+/// Posts every message to one webhook, regardless of its target
+///
+/// Useful for routing all output of a listener to a test channel without
+/// giving it a bot token with send permissions.
+pub struct WebhookSink {
+    http_client: Client,
+    url: String,
+}
+
+impl WebhookSink {
+    /// Create a sink for the given webhook URL
+    pub fn new(url: String) -> Self {
+        Self {
+            http_client: Client::new(),
+            url,
+        }
+    }
+}
+
+#[async_trait]
+impl MessageSink for WebhookSink {
+    async fn send_message(&self, _channel_id: &str, message: &DiscordMessage) -> Result<()> {
+        execute_webhook(&self.http_client, &self.url, message).await
+    }
+
+    async fn send_webhook(&self, _url: &str, message: &DiscordMessage) -> Result<()> {
+        execute_webhook(&self.http_client, &self.url, message).await
+    }
+
+    async fn send_dm(&self, _user_id: &str, message: &DiscordMessage) -> Result<()> {
+        execute_webhook(&self.http_client, &self.url, message).await
+    }
+
+    /// Webhooks can't react to messages, so reactions are dropped
+    async fn add_reaction(&self, _channel_id: &str, _message_id: &str, _emoji: &str) -> Result<()> {
+        Ok(())
+    }
+}